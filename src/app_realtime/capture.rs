@@ -0,0 +1,452 @@
+/*
+Gravação das mensagens MQTT recebidas, para reproduzir problemas com o modo "--replay".
+ - Fica desligada por padrão e é ligada/desligada pela rota "/diel-internal/realtime-rs/mqttCapture".
+ - Cada mensagem vira uma linha JSON { ts, topic, payload } em arquivos "./capture/capture-*.jsonl.gz".
+ - Pode filtrar por tópico (com os curingas "+" e "#" do MQTT) e por dev_id.
+ - O arquivo é trocado quando passa do tamanho máximo ou do tempo máximo. Os arquivos mais antigos que o
+   tempo de retenção são apagados, assim como os mais antigos quando a pasta passa do tamanho máximo.
+ - Pode ter um horário para desligar sozinha (ex.: gravar um dispositivo com problema por 1 hora).
+ - A compactação e a escrita no disco ficam em uma thread separada ("CaptureWriter::run"). As mensagens chegam
+   nela por um canal, assim a leitura do broker não espera o disco. Se a fila encher, as mensagens excedentes
+   são descartadas e contadas em "dropped_messages".
+*/
+
+use crate::app_realtime::sim_clock;
+use crate::GlobalVars;
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, TrySendError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+const CAPTURE_DIR: &str = "./capture";
+const QUEUE_CAPACITY: usize = 10000;
+// Intervalo para conferir o horário de desligar, a troca de arquivo e a retenção, mesmo sem chegar mensagens
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Uma mensagem gravada. Também é o formato lido pelo modo "--replay".
+#[derive(Serialize, Deserialize)]
+pub struct CaptureLine {
+    pub ts: u64, // Horário do servidor (em milissegundos) em que a mensagem chegou
+    pub topic: String,
+    pub payload: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureSettings {
+    pub topics: Vec<String>,  // Vazio grava todos os tópicos
+    pub dev_ids: Vec<String>, // Vazio grava todos os dispositivos
+    pub until: Option<DateTime<Utc>>,
+    pub max_file_bytes: u64, // Tamanho (sem compressão) a partir do qual troca de arquivo
+    pub rotate_secs: u64,
+    pub retention_secs: u64,
+    pub max_total_bytes: u64, // Tamanho máximo da pasta "./capture"
+}
+
+impl Default for CaptureSettings {
+    fn default() -> Self {
+        Self {
+            topics: Vec::new(),
+            dev_ids: Vec::new(),
+            until: None,
+            max_file_bytes: 100 * 1024 * 1024,
+            rotate_secs: 60 * 60,
+            retention_secs: 7 * 24 * 60 * 60,
+            max_total_bytes: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+struct CaptureFile {
+    path: String,
+    encoder: GzEncoder<BufWriter<File>>,
+    opened_at: u64,
+    written_bytes: u64,
+    lines: u64,
+}
+
+#[derive(Default)]
+struct CaptureRecorder {
+    settings: Option<CaptureSettings>, // None indica que a gravação está desligada
+    current: Option<CaptureFile>,
+}
+
+impl CaptureRecorder {
+    fn start(&mut self, settings: CaptureSettings) -> Result<(), String> {
+        std::fs::create_dir_all(CAPTURE_DIR).map_err(|err| format!("[247] {err}"))?;
+        self.close_file()?;
+        crate::write_to_log_file(
+            "CAPTURE",
+            &format!(
+                "Gravação iniciada, tópicos {:?}, dispositivos {:?}, até {:?}",
+                settings.topics, settings.dev_ids, settings.until
+            ),
+        );
+        self.settings = Some(settings);
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), String> {
+        if self.settings.take().is_some() {
+            crate::write_to_log_file("CAPTURE", "Gravação encerrada");
+        }
+        self.close_file()
+    }
+
+    fn status(&self) -> serde_json::Value {
+        serde_json::json!({
+            "enabled": self.settings.is_some(),
+            "settings": self.settings,
+            "current_file": self.current.as_ref().map(|x| &x.path),
+            "current_file_lines": self.current.as_ref().map(|x| x.lines),
+        })
+    }
+
+    /// Grava a mensagem se a gravação estiver ligada e ela passar pelos filtros
+    fn record(&mut self, message: CaptureMessage) -> Result<(), String> {
+        let Some(settings) = self.settings.as_ref() else {
+            return Ok(());
+        };
+        if !settings.topics.is_empty()
+            && !settings
+                .topics
+                .iter()
+                .any(|filter| topic_matches(filter, &message.packet.topic))
+        {
+            return Ok(());
+        }
+        // O parse do payload só é feito aqui, fora da leitura do broker, e só quando tem filtro por dev_id
+        if !settings.dev_ids.is_empty()
+            && !payload_dev_id(&message.packet.payload)
+                .is_some_and(|x| settings.dev_ids.contains(&x))
+        {
+            return Ok(());
+        }
+
+        let now_millis = message.ts;
+        let line = CaptureLine {
+            ts: now_millis,
+            topic: message.packet.topic,
+            payload: String::from_utf8_lossy(&message.packet.payload).into_owned(),
+        };
+        let mut bytes = serde_json::to_vec(&line).map_err(|err| format!("[247] {err}"))?;
+        bytes.push(b'\n');
+
+        self.rotate_if_needed(now_millis)?;
+        let current = match self.current.as_mut() {
+            Some(x) => x,
+            None => self.current.insert(open_file(now_millis)?),
+        };
+        current
+            .encoder
+            .write_all(&bytes)
+            .map_err(|err| format!("[247] {err}"))?;
+        current.written_bytes += bytes.len() as u64;
+        current.lines += 1;
+        Ok(())
+    }
+
+    /// Desliga a gravação no horário combinado e troca o arquivo quando passa do tamanho ou do tempo máximo
+    fn rotate_if_needed(&mut self, now_millis: u64) -> Result<(), String> {
+        let Some(settings) = self.settings.as_ref() else {
+            return Ok(());
+        };
+        if settings
+            .until
+            .is_some_and(|until| until.timestamp_millis() as u64 <= now_millis)
+        {
+            return self.stop();
+        }
+        let Some(current) = self.current.as_ref() else {
+            return Ok(());
+        };
+        let file_age_secs = now_millis.saturating_sub(current.opened_at) / 1000;
+        if current.written_bytes >= settings.max_file_bytes || file_age_secs >= settings.rotate_secs
+        {
+            self.close_file()?;
+        }
+        Ok(())
+    }
+
+    fn close_file(&mut self) -> Result<(), String> {
+        let Some(current) = self.current.take() else {
+            return Ok(());
+        };
+        let mut writer = current
+            .encoder
+            .finish()
+            .map_err(|err| format!("[247] {err}"))?;
+        writer.flush().map_err(|err| format!("[247] {err}"))?;
+        Ok(())
+    }
+}
+
+fn open_file(now_millis: u64) -> Result<CaptureFile, String> {
+    let date = DateTime::from_timestamp_millis(now_millis as i64).unwrap_or_else(Utc::now);
+    let path = format!(
+        "{CAPTURE_DIR}/capture-{}.jsonl.gz",
+        date.format("%Y%m%d-%H%M%S%.3f")
+    );
+    let file = File::create(&path).map_err(|err| format!("[247] {path}: {err}"))?;
+    Ok(CaptureFile {
+        path,
+        encoder: GzEncoder::new(BufWriter::new(file), Compression::default()),
+        opened_at: now_millis,
+        written_bytes: 0,
+        lines: 0,
+    })
+}
+
+/// Comparação de tópico com os curingas do MQTT: "+" é um nível qualquer e "#" são todos os níveis restantes
+fn topic_matches(filter: &str, topic: &str) -> bool {
+    let mut topic_levels = topic.split('/');
+    for filter_level in filter.split('/') {
+        if filter_level == "#" {
+            return true;
+        }
+        match topic_levels.next() {
+            Some(level) if filter_level == "+" || filter_level == level => {}
+            _ => return false,
+        }
+    }
+    topic_levels.next().is_none()
+}
+
+/// Apaga os arquivos de captura mais antigos que a retenção e os mais antigos quando a pasta passa do tamanho máximo
+fn apply_retention(settings: &CaptureSettings, current_path: Option<&str>) -> Result<(), String> {
+    let dir = match std::fs::read_dir(CAPTURE_DIR) {
+        Ok(x) => x,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(format!("[248] {err}")),
+    };
+    let mut files = Vec::new();
+    for entry in dir.flatten() {
+        let path = entry.path();
+        let is_capture = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .is_some_and(|x| x.starts_with("capture-") && x.ends_with(".jsonl.gz"));
+        if !is_capture {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let age_secs = metadata
+            .modified()
+            .ok()
+            .and_then(|x| x.elapsed().ok())
+            .map(|x| x.as_secs())
+            .unwrap_or(0);
+        files.push((path, metadata.len(), age_secs));
+    }
+    // Os nomes têm a data de criação, então a ordem alfabética é do mais antigo para o mais novo
+    files.sort();
+
+    let mut total_bytes: u64 = files.iter().map(|(_, size, _)| size).sum();
+    for (path, size, age_secs) in files.iter() {
+        if current_path.is_some_and(|x| std::path::Path::new(x) == path) {
+            continue;
+        }
+        if *age_secs > settings.retention_secs || total_bytes > settings.max_total_bytes {
+            std::fs::remove_file(path).map_err(|err| format!("[248] {err}"))?;
+            total_bytes = total_bytes.saturating_sub(*size);
+        }
+    }
+    Ok(())
+}
+
+/// O "dev_id" do payload JSON das mensagens dos dispositivos
+fn payload_dev_id(payload: &[u8]) -> Option<String> {
+    let payload_json: serde_json::Value = serde_json::from_slice(payload).ok()?;
+    payload_json["dev_id"].as_str().map(|x| x.to_owned())
+}
+
+/// Mensagem recebida do broker
+pub struct CaptureMessage {
+    ts: u64,
+    packet: rumqttc::Publish,
+}
+
+pub enum CaptureControl {
+    Start(CaptureSettings),
+    Stop,
+    Status,
+}
+
+enum CaptureCommand {
+    Record(CaptureMessage),
+    Control(
+        CaptureControl,
+        oneshot::Sender<Result<serde_json::Value, String>>,
+    ),
+}
+
+/// Lado do serviço: envia as mensagens e os comandos da rota para a thread de gravação
+pub struct CaptureHandle {
+    enabled: Arc<AtomicBool>, // Cópia do estado da thread de gravação, para não enviar mensagens à toa
+    dropped: Arc<AtomicU64>,
+    sender: mpsc::SyncSender<CaptureCommand>,
+}
+
+/// Thread de gravação, dona dos arquivos de captura
+pub struct CaptureWriter {
+    enabled: Arc<AtomicBool>,
+    dropped: Arc<AtomicU64>,
+    receiver: mpsc::Receiver<CaptureCommand>,
+    recorder: CaptureRecorder,
+}
+
+impl CaptureHandle {
+    pub fn new() -> (CaptureHandle, CaptureWriter) {
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+        let enabled = Arc::new(AtomicBool::new(false));
+        let dropped = Arc::new(AtomicU64::new(0));
+        let handle = CaptureHandle {
+            enabled: enabled.clone(),
+            dropped: dropped.clone(),
+            sender,
+        };
+        let writer = CaptureWriter {
+            enabled,
+            dropped,
+            receiver,
+            recorder: CaptureRecorder::default(),
+        };
+        (handle, writer)
+    }
+
+    /// Liga, desliga ou consulta a gravação e retorna a situação atual
+    pub async fn control(&self, control: CaptureControl) -> Result<serde_json::Value, String> {
+        let (reply, response) = oneshot::channel();
+        self.sender
+            .try_send(CaptureCommand::Control(control, reply))
+            .map_err(|err| format!("[247] {err}"))?;
+        response.await.map_err(|err| format!("[247] {err}"))?
+    }
+}
+
+impl CaptureWriter {
+    /// Recebe as mensagens pelo canal e faz a manutenção dos arquivos a cada minuto. Termina quando o serviço é encerrado.
+    pub fn run(mut self) {
+        let mut next_maintenance = Instant::now() + MAINTENANCE_INTERVAL;
+        loop {
+            let timeout = next_maintenance.saturating_duration_since(Instant::now());
+            match self.receiver.recv_timeout(timeout) {
+                Ok(CaptureCommand::Record(message)) => {
+                    self.recorder
+                        .record(message)
+                        .map_err(|err| crate::log_err("[247]", err))
+                        .ok();
+                }
+                Ok(CaptureCommand::Control(control, reply)) => {
+                    let result = self.on_control(control);
+                    reply.send(result).ok();
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.recorder
+                        .stop()
+                        .map_err(|err| crate::log_err("[247]", err))
+                        .ok();
+                    return;
+                }
+            }
+
+            if Instant::now() >= next_maintenance {
+                self.maintenance();
+                next_maintenance = Instant::now() + MAINTENANCE_INTERVAL;
+            }
+            self.enabled
+                .store(self.recorder.settings.is_some(), Ordering::Relaxed);
+        }
+    }
+
+    fn on_control(&mut self, control: CaptureControl) -> Result<serde_json::Value, String> {
+        match control {
+            CaptureControl::Start(settings) => {
+                self.dropped.store(0, Ordering::Relaxed);
+                self.recorder.start(settings)?;
+            }
+            CaptureControl::Stop => self.recorder.stop()?,
+            CaptureControl::Status => {}
+        }
+        let mut status = self.recorder.status();
+        status["dropped_messages"] = self.dropped.load(Ordering::Relaxed).into();
+        Ok(status)
+    }
+
+    /// Desliga no horário combinado, troca o arquivo e apaga as gravações antigas
+    fn maintenance(&mut self) {
+        self.recorder
+            .rotate_if_needed(sim_clock::now_millis())
+            .map_err(|err| crate::log_err("[247]", err))
+            .ok();
+        // Com a gravação desligada vale a retenção padrão
+        let settings = self.recorder.settings.clone().unwrap_or_default();
+        let current_path = self.recorder.current.as_ref().map(|x| x.path.as_str());
+        apply_retention(&settings, current_path)
+            .map_err(|err| crate::log_err("[248]", err))
+            .ok();
+    }
+}
+
+/// Chamado para cada mensagem recebida do broker, antes de criar a tarefa que processa a mensagem
+pub fn on_mqtt_message(packet: &rumqttc::Publish, globs: &GlobalVars) {
+    let capture = &globs.capture;
+    if !capture.enabled.load(Ordering::Relaxed) {
+        return;
+    }
+    let message = CaptureMessage {
+        ts: sim_clock::now_millis(),
+        packet: packet.clone(),
+    };
+    match capture.sender.try_send(CaptureCommand::Record(message)) {
+        Ok(()) => {}
+        Err(TrySendError::Full(_)) => {
+            capture.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        Err(TrySendError::Disconnected(_)) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topic_filter_wildcards() {
+        assert!(topic_matches("iotrelay/data/#", "iotrelay/data/dut/DUT123"));
+        assert!(topic_matches(
+            "iotrelay/+/dut/+",
+            "iotrelay/data/dut/DUT123"
+        ));
+        assert!(!topic_matches(
+            "iotrelay/+/dac/+",
+            "iotrelay/data/dut/DUT123"
+        ));
+        assert!(!topic_matches(
+            "iotrelay/data/dut",
+            "iotrelay/data/dut/DUT123"
+        ));
+    }
+
+    #[test]
+    fn dev_id_comes_from_the_payload() {
+        assert_eq!(
+            payload_dev_id(br#"{"dev_id":"DUT123","Temperature":[25.1]}"#),
+            Some("DUT123".to_owned())
+        );
+        assert_eq!(payload_dev_id(b"Current RMT state: 1"), None);
+        assert_eq!(
+            payload_dev_id(br#"{"timestamp":"2026-01-01T10:00:00"}"#),
+            None
+        );
+    }
+}
@@ -0,0 +1,156 @@
+/*
+Neste arquivo fica a estimativa de desvio de relógio (RTC) de cada dispositivo.
+A cada telemetria comparamos o "timestamp" do dispositivo (convertido para UTC usando o GMT)
+com o horário do servidor no momento em que a mensagem chegou.
+*/
+
+use super::global_vars::DevInfo;
+use crate::helpers::telemetry_payloads::telemetry_formats::{
+    get_json_saved_data, get_json_timestamp_with_gmt,
+};
+use crate::GlobalVars;
+use chrono::TimeDelta;
+use std::sync::Arc;
+
+// Peso de cada nova amostra na média móvel exponencial
+const EWMA_ALPHA: f64 = 0.1;
+// Número mínimo de amostras antes de considerar que o relógio do dispositivo está com desvio
+const MIN_SAMPLES: u64 = 5;
+
+#[derive(Debug)]
+pub struct ClockDrift {
+    pub offset_ms: f64, // Média móvel de (horário do dispositivo - horário do servidor)
+    pub last_sample_ms: i64, // Diferença calculada na última telemetria
+    pub samples: u64,   // Quantidade de telemetrias usadas na estimativa
+    pub last_update: u64, // Timestamp do servidor da última amostra
+}
+
+impl ClockDrift {
+    pub fn new(sample_ms: i64, now_millis: u64) -> ClockDrift {
+        ClockDrift {
+            offset_ms: sample_ms as f64,
+            last_sample_ms: sample_ms,
+            samples: 1,
+            last_update: now_millis,
+        }
+    }
+
+    pub fn add_sample(&mut self, sample_ms: i64, now_millis: u64) {
+        self.offset_ms += EWMA_ALPHA * (sample_ms as f64 - self.offset_ms);
+        self.last_sample_ms = sample_ms;
+        self.samples += 1;
+        self.last_update = now_millis;
+    }
+
+    pub fn offset_secs(&self) -> i64 {
+        (self.offset_ms / 1000.0).round() as i64
+    }
+
+    pub fn is_drifting(&self, limit_secs: u64) -> bool {
+        self.samples >= MIN_SAMPLES && self.offset_secs().unsigned_abs() > limit_secs
+    }
+}
+
+/// Calcula a diferença entre o relógio do dispositivo e o do servidor e atualiza a estimativa do "dev_info".
+pub async fn on_device_telemetry(
+    dev_info: &DevInfo,
+    payload_json: &serde_json::Value,
+    now_millis: u64,
+) {
+    // Telemetrias históricas foram geradas muito antes de chegar no servidor, não servem para a estimativa
+    if get_json_saved_data(payload_json) {
+        return;
+    }
+
+    let Ok((payload_timestamp, gmt)) = get_json_timestamp_with_gmt(payload_json) else {
+        return;
+    };
+    let device_millis = payload_timestamp.and_utc().timestamp_millis() - (gmt * 3600 * 1000);
+    let sample_ms = device_millis - (now_millis as i64);
+
+    let mut clock_drift = dev_info.clock_drift.write().await;
+    match clock_drift.as_mut() {
+        Some(clock_drift) => clock_drift.add_sample(sample_ms, now_millis),
+        None => {
+            *clock_drift = Some(ClockDrift::new(sample_ms, now_millis));
+        }
+    }
+}
+
+/// Se o dispositivo estiver com desvio de relógio e a correção estiver habilitada, retorna o
+/// valor que deve ser subtraído dos timestamps das telemetrias para avaliar as notificações.
+pub async fn get_notifs_correction(
+    dev_info: &DevInfo,
+    globs: &Arc<GlobalVars>,
+) -> Option<TimeDelta> {
+    if !globs.configfile.clock_drift_correct_notifs {
+        return None;
+    }
+    let clock_drift = dev_info.clock_drift.read().await;
+    let clock_drift = clock_drift.as_ref()?;
+    if !clock_drift.is_drifting(globs.configfile.clock_drift_limit_secs) {
+        return None;
+    }
+    Some(TimeDelta::seconds(clock_drift.offset_secs()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-01-01 10:00:00 UTC
+    const NOW_MS: u64 = 1_767_261_600_000;
+
+    fn feed(dev_info: &DevInfo, payload_json: serde_json::Value, now_millis: u64) {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(on_device_telemetry(dev_info, &payload_json, now_millis));
+    }
+
+    #[test]
+    fn device_ahead_is_flagged_after_min_samples() {
+        let dev_info = DevInfo::new(NOW_MS, "DUT1");
+        // Horário local GMT-3 dez minutos adiantado
+        let payload = serde_json::json!({ "timestamp": "2026-01-01T07:10:00", "GMT": -3 });
+        for _ in 0..(MIN_SAMPLES - 1) {
+            feed(&dev_info, payload.clone(), NOW_MS);
+        }
+        {
+            let clock_drift = dev_info.clock_drift.try_read().unwrap();
+            let clock_drift = clock_drift.as_ref().unwrap();
+            assert_eq!(clock_drift.offset_secs(), 600);
+            assert!(!clock_drift.is_drifting(300));
+        }
+
+        feed(&dev_info, payload, NOW_MS);
+        let clock_drift = dev_info.clock_drift.try_read().unwrap();
+        assert!(clock_drift.as_ref().unwrap().is_drifting(300));
+        assert!(!clock_drift.as_ref().unwrap().is_drifting(600));
+    }
+
+    #[test]
+    fn saved_data_does_not_change_the_estimate() {
+        let dev_info = DevInfo::new(NOW_MS, "DUT1");
+        let payload = serde_json::json!({
+            "timestamp": "2026-01-01T01:00:00",
+            "GMT": -3,
+            "saved_data": true,
+        });
+        feed(&dev_info, payload, NOW_MS);
+        assert!(dev_info.clock_drift.try_read().unwrap().is_none());
+    }
+
+    #[test]
+    fn moving_average_smooths_a_single_outlier() {
+        let mut clock_drift = ClockDrift::new(0, NOW_MS);
+        for _ in 0..10 {
+            clock_drift.add_sample(0, NOW_MS);
+        }
+        clock_drift.add_sample(3_600_000, NOW_MS + 1000);
+        assert_eq!(clock_drift.last_sample_ms, 3_600_000);
+        assert_eq!(clock_drift.offset_secs(), 360);
+        assert_eq!(clock_drift.samples, 12);
+        assert_eq!(clock_drift.last_update, NOW_MS + 1000);
+    }
+}
@@ -1,10 +1,16 @@
 use crate::envvars_loader;
 use crate::lib_rumqtt::BrokerConfig;
+use std::ops::RangeInclusive;
 
 pub struct ConfigFile {
     pub listen_http_api: String,
     pub broker_config: BrokerConfig,
     pub apiserver_internal_api: String,
+    pub clock_drift_limit_secs: u64, // Desvio de relógio a partir do qual o dispositivo é sinalizado
+    pub clock_drift_correct_notifs: bool, // Se as notificações devem usar o horário corrigido pelo servidor
+    pub incident_window_secs: u64, // Janela de agrupamento de detecções em incidentes (0, o padrão, desativa o agrupamento)
+    pub incident_min_devices: usize, // Número de dispositivos com a mesma detecção na janela para virar incidente
+    pub incident_offline_percent: u64, // Percentual dos dispositivos da unidade ficando offline na janela para virar incidente
 }
 
 impl ConfigFile {
@@ -26,6 +32,70 @@ impl ConfigFile {
             apiserver_internal_api: envvars_loader::get_var_string_required(
                 "APISERVER_INTERNAL_API",
             )?,
+            clock_drift_limit_secs: envvars_loader::get_var_u16_optional(
+                "CLOCK_DRIFT_LIMIT_SECONDS",
+            )?
+            .unwrap_or(300) as u64,
+            clock_drift_correct_notifs: envvars_loader::get_var_bool_optional(
+                "CLOCK_DRIFT_CORRECT_NOTIFS",
+            )?
+            .unwrap_or(false),
+            // As detecções ficam seguradas no máximo um dia
+            incident_window_secs: get_var_u64_in_range("INCIDENT_WINDOW_SECONDS", 0, 0..=86400)?,
+            // Um único dispositivo nunca é um incidente
+            incident_min_devices: get_var_u64_in_range("INCIDENT_MIN_DEVICES", 10, 2..=100_000)?
+                as usize,
+            // O incidente é "mais de X%" dos dispositivos, então 100% nunca seria atingido
+            incident_offline_percent: get_var_u64_in_range("INCIDENT_OFFLINE_PERCENT", 50, 1..=99)?,
         })
     }
+
+    /// Configs do modo "--replay": não conecta no broker nem no API-Server, então só os parâmetros das notificações são lidos
+    pub fn for_replay() -> Result<ConfigFile, String> {
+        envvars_loader::load_env_vars();
+
+        let broker_config = BrokerConfig {
+            host: String::new(),
+            port: 0,
+            username: String::new(),
+            password: String::new(),
+            use_tls: false,
+            ca_cert: None,
+        };
+
+        Ok(ConfigFile {
+            listen_http_api: String::new(),
+            broker_config,
+            apiserver_internal_api: String::new(),
+            clock_drift_limit_secs: envvars_loader::get_var_u16_optional(
+                "CLOCK_DRIFT_LIMIT_SECONDS",
+            )?
+            .unwrap_or(300) as u64,
+            clock_drift_correct_notifs: envvars_loader::get_var_bool_optional(
+                "CLOCK_DRIFT_CORRECT_NOTIFS",
+            )?
+            .unwrap_or(false),
+            // As detecções são listadas individualmente, sem agrupar em incidentes
+            incident_window_secs: 0,
+            incident_min_devices: 0,
+            incident_offline_percent: 0,
+        })
+    }
+}
+
+/// Lê uma configuração numérica opcional e confere se está dentro da faixa permitida
+fn get_var_u64_in_range(
+    name: &str,
+    default: u64,
+    range: RangeInclusive<u64>,
+) -> Result<u64, String> {
+    let val = envvars_loader::get_var_u64_optional(name)?.unwrap_or(default);
+    if !range.contains(&val) {
+        return Err(format!(
+            "A configuração '{name}' tem que estar entre {} e {}: {val}",
+            range.start(),
+            range.end()
+        ));
+    }
+    Ok(val)
 }
@@ -0,0 +1,85 @@
+use crate::{
+    app_realtime::global_vars::DevInfo,
+    global_vars::GlobalVars,
+    lib_http::{
+        response::respond_http_json_bytes,
+        types::{HttpRequest, HttpResponse},
+    },
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::{atomic::Ordering, Arc};
+
+/*
+  ['/diel-internal/realtime-rs/getDacCompressorUsage']: (reqParams: {
+    devIds?: string[]
+  }) => {
+    compressorUsage: {
+      [devId: string]: {
+        timestamp: string // Timestamp da última telemetria usada nos totalizadores
+        compressor_on: boolean
+        run_seconds: number // Tempo que o compressor está ligado sem interrupção
+        day_usage_seconds: number // Tempo de compressor ligado no dia da última telemetria
+      }
+    }
+  },
+
+  // Os totalizadores só são calculados para os DACs que têm notificações configuradas
+*/
+
+#[derive(Deserialize)]
+pub struct ParamsGetDacCompressorUsage {
+    pub devIds: Option<Vec<String>>,
+}
+
+pub async fn get_dac_compressor_usage(
+    req: &HttpRequest,
+    globs: &Arc<GlobalVars>,
+) -> Result<HttpResponse, String> {
+    let req_params: ParamsGetDacCompressorUsage =
+        serde_json::from_slice(&req.content).map_err(|e| e.to_string())?;
+
+    let all_devs = globs.devs_info.read().await;
+    let mut resp_devs = json!({});
+
+    match req_params.devIds {
+        None => {
+            for (dev_id, dev_info) in all_devs.iter() {
+                if let Some(usage) = get_dev_compressor_usage(dev_info).await {
+                    resp_devs[dev_id] = usage;
+                }
+            }
+        }
+        Some(dev_ids) => {
+            for dev_id in &dev_ids {
+                if let Some(dev_info) = all_devs.get(dev_id) {
+                    if let Some(usage) = get_dev_compressor_usage(dev_info).await {
+                        resp_devs[dev_id] = usage;
+                    }
+                };
+            }
+        }
+    };
+
+    let response = json!({
+      "compressorUsage": resp_devs,
+    });
+
+    let response = serde_json::to_vec(&response).map_err(|err| format!("[79] {err}"))?;
+    Ok(respond_http_json_bytes(200, response))
+}
+
+async fn get_dev_compressor_usage(dev_info: &DevInfo) -> Option<serde_json::Value> {
+    if !dev_info.has_notifs_dac.load(Ordering::Relaxed) {
+        return None;
+    }
+    let notifs_dac = dev_info.notifs_dac.read().await;
+    let usage = &notifs_dac.as_ref()?.compressor_usage;
+    let timestamp = usage.timestamp?;
+    Some(json!({
+        "timestamp": timestamp,
+        "compressor_on": usage.lcmp != 0,
+        "run_seconds": usage.run_secs,
+        "day_usage_seconds": usage.day_usage_secs,
+    }))
+}
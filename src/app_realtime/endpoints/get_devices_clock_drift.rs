@@ -0,0 +1,98 @@
+use crate::{
+    app_realtime::global_vars::DevInfo,
+    global_vars::GlobalVars,
+    lib_http::{
+        response::respond_http_json_bytes,
+        types::{HttpRequest, HttpResponse},
+    },
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+/*
+  ['/diel-internal/realtime-rs/getDevicesClockDrift']: (reqParams: {
+    devIds?: string[]
+    onlyFlagged?: boolean
+  }) => {
+    limitSeconds: number // Desvio a partir do qual o dispositivo é sinalizado
+    clockDrift: {
+      [devId: string]: {
+        offset_secs: number // Média móvel de (horário do dispositivo - horário do servidor)
+        last_sample_secs: number // Diferença calculada na última telemetria
+        samples: number
+        last_update: number // Timestamp do servidor da última amostra
+        flagged: boolean
+      }
+    }
+  },
+
+*/
+
+#[derive(Deserialize)]
+pub struct ParamsGetDevicesClockDrift {
+    pub devIds: Option<Vec<String>>,
+    pub onlyFlagged: Option<bool>,
+}
+
+pub async fn get_devices_clock_drift(
+    req: &HttpRequest,
+    globs: &Arc<GlobalVars>,
+) -> Result<HttpResponse, String> {
+    let req_params: ParamsGetDevicesClockDrift =
+        serde_json::from_slice(&req.content).map_err(|e| e.to_string())?;
+    let only_flagged = req_params.onlyFlagged.unwrap_or(false);
+    let limit_secs = globs.configfile.clock_drift_limit_secs;
+
+    let all_devs = globs.devs_info.read().await;
+    let mut resp_devs = json!({});
+
+    match req_params.devIds {
+        None => {
+            for (dev_id, dev_info) in all_devs.iter() {
+                if let Some(drift) = get_dev_clock_drift(dev_info, limit_secs, only_flagged).await {
+                    resp_devs[dev_id] = drift;
+                }
+            }
+        }
+        Some(dev_ids) => {
+            for dev_id in &dev_ids {
+                if let Some(dev_info) = all_devs.get(dev_id) {
+                    if let Some(drift) =
+                        get_dev_clock_drift(dev_info, limit_secs, only_flagged).await
+                    {
+                        resp_devs[dev_id] = drift;
+                    }
+                };
+            }
+        }
+    };
+
+    let response = json!({
+      "limitSeconds": limit_secs,
+      "clockDrift": resp_devs,
+    });
+
+    let response = serde_json::to_vec(&response).map_err(|err| format!("[78] {err}"))?;
+    Ok(respond_http_json_bytes(200, response))
+}
+
+async fn get_dev_clock_drift(
+    dev_info: &DevInfo,
+    limit_secs: u64,
+    only_flagged: bool,
+) -> Option<serde_json::Value> {
+    let clock_drift = dev_info.clock_drift.read().await;
+    let clock_drift = clock_drift.as_ref()?;
+    let flagged = clock_drift.is_drifting(limit_secs);
+    if only_flagged && !flagged {
+        return None;
+    }
+    Some(json!({
+        "offset_secs": clock_drift.offset_secs(),
+        "last_sample_secs": clock_drift.last_sample_ms / 1000,
+        "samples": clock_drift.samples,
+        "last_update": clock_drift.last_update,
+        "flagged": flagged,
+    }))
+}
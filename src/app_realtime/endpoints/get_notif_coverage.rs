@@ -0,0 +1,54 @@
+use crate::{
+    global_vars::GlobalVars,
+    lib_http::{
+        response::respond_http_json_bytes,
+        types::{HttpRequest, HttpResponse},
+    },
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+/*
+  ['/diel-internal/realtime-rs/getNotifCoverage']: (reqParams: {
+    notifId?: number
+    devId?: string
+  }) => {
+    notifs: {
+      notif_id: number
+      cond_id: string
+      status: 'active'|'inactive'|'unknown_cond_id' // 'active' se estiver ativa em pelo menos um dispositivo
+      active_devices: number
+      devices: {
+        dev_id: string
+        status: 'active'|'rejected'|'pending'|'never_seen'|'unknown_cond_id'
+        reason: string|null // Motivo da recusa, ex.: 'CO2MAX é obrigatório para a notificação'
+      }[]
+    }[]
+  },
+*/
+
+#[derive(Deserialize)]
+pub struct ParamsGetNotifCoverage {
+    pub notifId: Option<u64>,
+    pub devId: Option<String>,
+}
+
+pub async fn get_notif_coverage(
+    req: &HttpRequest,
+    globs: &Arc<GlobalVars>,
+) -> Result<HttpResponse, String> {
+    let req_params: ParamsGetNotifCoverage =
+        serde_json::from_slice(&req.content).map_err(|e| e.to_string())?;
+
+    // Mesma ordem de locks da atualização das notificações: "devs_info" antes de "notif_coverage"
+    let devs_info = globs.devs_info.read().await;
+    let coverage = globs.notif_coverage.read().await;
+    let list = coverage.report(req_params.notifId, req_params.devId.as_deref(), &devs_info);
+    drop(coverage);
+    drop(devs_info);
+
+    let response = json!({ "notifs": list });
+    let response = serde_json::to_vec(&response).map_err(|err| format!("[78] {err}"))?;
+    Ok(respond_http_json_bytes(200, response))
+}
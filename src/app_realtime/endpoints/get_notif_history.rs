@@ -0,0 +1,71 @@
+use crate::{
+    global_vars::GlobalVars,
+    lib_http::{
+        response::respond_http_json_bytes,
+        types::{HttpRequest, HttpResponse},
+    },
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+/*
+  ['/diel-internal/realtime-rs/getNotifHistory']: (reqParams: {
+    devId?: string
+    notifId?: number
+    since?: string // ISO 8601
+    until?: string // ISO 8601
+  }) => {
+    history: {
+      dev_id: string
+      ts: string // Horário do servidor em que o evento foi registrado
+      notif_id: number|null
+      kind: 'sent'|'failed'|'snoozed'|'incident'|'reset'
+      notif_path: string|null
+      detail: {...} // Ex.: tentativas de envio, snooze_id, motivo do reset ('descontinuidade'|'new_day'|'config_change')
+    }[]
+  },
+*/
+
+#[derive(Deserialize)]
+pub struct ParamsGetNotifHistory {
+    pub devId: Option<String>,
+    pub notifId: Option<u64>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+pub async fn get_notif_history(
+    req: &HttpRequest,
+    globs: &Arc<GlobalVars>,
+) -> Result<HttpResponse, String> {
+    let req_params: ParamsGetNotifHistory =
+        serde_json::from_slice(&req.content).map_err(|e| e.to_string())?;
+
+    let history = globs.notif_history.read().await;
+    let events = history.query(
+        req_params.devId.as_deref(),
+        req_params.notifId,
+        req_params.since.as_ref(),
+        req_params.until.as_ref(),
+    );
+    let list: Vec<serde_json::Value> = events
+        .into_iter()
+        .map(|(dev_id, event)| {
+            json!({
+                "dev_id": dev_id,
+                "ts": event.ts,
+                "notif_id": event.notif_id,
+                "kind": event.kind,
+                "notif_path": event.notif_path,
+                "detail": event.detail,
+            })
+        })
+        .collect();
+    drop(history);
+
+    let response = json!({ "history": list });
+    let response = serde_json::to_vec(&response).map_err(|err| format!("[78] {err}"))?;
+    Ok(respond_http_json_bytes(200, response))
+}
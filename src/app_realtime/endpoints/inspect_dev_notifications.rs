@@ -1,16 +1,37 @@
 /**
  * Funções de debug das notificações
  */
-use crate::app_realtime::notifications::inspection::get_dev_notifs_info;
+use crate::app_realtime::notifications::inspection::{get_dev_notifs_info, get_devs_with_notif};
 use crate::global_vars::GlobalVars;
 use crate::helpers::lib_http::response::respond_http_json_bytes;
 use crate::lib_http::types::{HttpRequest, HttpResponse};
 use serde::Deserialize;
+use serde_json::json;
 use std::sync::Arc;
 
+/*
+  ['/diel-internal/realtime-rs/inspect_dev_notifications']: (reqParams: {
+    device_code?: string
+    devIds?: string[]
+    notifId?: number // Lista todos os dispositivos monitorados pela notificação
+  }) => {
+    devices: {
+      device_code: string
+      device_time: string // Horário local do dispositivo usado para resolver a programação do dia
+      last_telemetry: {...}|null
+      has_notifs_dut: boolean
+      notifs_dut: {...}|null // Estado das regras (acumuladores, episódios, last_notif_sent...)
+      schedule_dut: { permission, start, end, inside_schedule }|null
+      ...
+    }[]
+  },
+*/
+
 #[derive(Deserialize)]
 pub struct ParamsInspectDevNotifs {
-    pub device_code: String,
+    pub device_code: Option<String>,
+    pub devIds: Option<Vec<String>>,
+    pub notifId: Option<u64>,
 }
 pub async fn inspect_dev_notifications(
     req: &HttpRequest,
@@ -19,9 +40,28 @@ pub async fn inspect_dev_notifications(
     let req_params: ParamsInspectDevNotifs =
         serde_json::from_slice(&req.content).map_err(|e| e.to_string())?;
 
-    let response = get_dev_notifs_info(&req_params.device_code, globs)
-        .await
-        .map_err(|err| format!("[58] {err}"))?;
+    let mut dev_ids = req_params.devIds.unwrap_or_default();
+    if let Some(device_code) = req_params.device_code {
+        dev_ids.push(device_code);
+    }
+    if let Some(notif_id) = req_params.notifId {
+        dev_ids.extend(get_devs_with_notif(notif_id, globs).await);
+    }
+    if dev_ids.is_empty() {
+        return Err("Missing device_code, devIds or notifId".to_owned());
+    }
+    dev_ids.sort();
+    dev_ids.dedup();
+
+    let mut devices = Vec::with_capacity(dev_ids.len());
+    for dev_id in dev_ids.iter() {
+        let info = get_dev_notifs_info(dev_id, globs)
+            .await
+            .map_err(|err| format!("[58] {err}"))?;
+        devices.push(info);
+    }
 
-    Ok(respond_http_json_bytes(501, response.into_bytes()))
+    let response = json!({ "devices": devices });
+    let response = serde_json::to_vec(&response).map_err(|err| format!("[78] {err}"))?;
+    Ok(respond_http_json_bytes(200, response))
 }
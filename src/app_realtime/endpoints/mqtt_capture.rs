@@ -0,0 +1,89 @@
+use crate::{
+    app_realtime::capture::{CaptureControl, CaptureSettings},
+    app_realtime::sim_clock,
+    global_vars::GlobalVars,
+    lib_http::{
+        response::respond_http_json_bytes,
+        types::{HttpRequest, HttpResponse},
+    },
+};
+use chrono::TimeDelta;
+use serde::Deserialize;
+use std::sync::Arc;
+
+/*
+  ['/diel-internal/realtime-rs/mqttCapture']: (reqParams: {
+    enabled?: boolean // Se não for informado, só retorna a situação atual
+    topics?: string[] // Filtros de tópico, aceita os curingas "+" e "#". Ex.: ["iotrelay/data/dut/#"]
+    devIds?: string[]
+    durationMinutes?: number // Desliga sozinha depois deste tempo
+    maxFileMB?: number
+    rotateMinutes?: number
+    retentionHours?: number
+    maxTotalMB?: number
+  }) => {
+    enabled: boolean
+    settings: {...}|null
+    current_file: string|null
+    current_file_lines: number|null
+    dropped_messages: number // Mensagens descartadas porque a fila da gravação estava cheia
+  },
+*/
+
+#[derive(Deserialize)]
+pub struct ParamsMqttCapture {
+    pub enabled: Option<bool>,
+    pub topics: Option<Vec<String>>,
+    pub devIds: Option<Vec<String>>,
+    pub durationMinutes: Option<u64>,
+    pub maxFileMB: Option<u64>,
+    pub rotateMinutes: Option<u64>,
+    pub retentionHours: Option<u64>,
+    pub maxTotalMB: Option<u64>,
+}
+
+pub async fn mqtt_capture(
+    req: &HttpRequest,
+    globs: &Arc<GlobalVars>,
+) -> Result<HttpResponse, String> {
+    let req_params: ParamsMqttCapture =
+        serde_json::from_slice(&req.content).map_err(|e| e.to_string())?;
+
+    // A gravação roda em uma thread separada, que responde com a situação atual
+    let control = match req_params.enabled {
+        Some(true) => {
+            let default = CaptureSettings::default();
+            let until = req_params
+                .durationMinutes
+                .map(|minutes| sim_clock::now_utc() + TimeDelta::minutes(minutes as i64));
+            let settings = CaptureSettings {
+                topics: req_params.topics.unwrap_or_default(),
+                dev_ids: req_params.devIds.unwrap_or_default(),
+                until,
+                max_file_bytes: req_params
+                    .maxFileMB
+                    .map(|x| x * 1024 * 1024)
+                    .unwrap_or(default.max_file_bytes),
+                rotate_secs: req_params
+                    .rotateMinutes
+                    .map(|x| x * 60)
+                    .unwrap_or(default.rotate_secs),
+                retention_secs: req_params
+                    .retentionHours
+                    .map(|x| x * 60 * 60)
+                    .unwrap_or(default.retention_secs),
+                max_total_bytes: req_params
+                    .maxTotalMB
+                    .map(|x| x * 1024 * 1024)
+                    .unwrap_or(default.max_total_bytes),
+            };
+            CaptureControl::Start(settings)
+        }
+        Some(false) => CaptureControl::Stop,
+        None => CaptureControl::Status,
+    };
+    let status = globs.capture.control(control).await?;
+
+    let response = serde_json::to_vec(&status).map_err(|err| format!("[78] {err}"))?;
+    Ok(respond_http_json_bytes(200, response))
+}
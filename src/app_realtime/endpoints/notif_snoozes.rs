@@ -0,0 +1,144 @@
+use crate::{
+    app_realtime::{notifications::snooze, sim_clock},
+    global_vars::GlobalVars,
+    lib_http::{
+        response::respond_http_json_bytes,
+        types::{HttpRequest, HttpResponse},
+    },
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+/*
+  ['/diel-internal/realtime-rs/snoozeNotifications']: (reqParams: {
+    devId: string
+    notifId?: number // Se não for informado silencia todas as notificações do dispositivo
+    since?: string // ISO 8601
+    until: string // ISO 8601
+    reason: string
+  }) => {
+    snooze: Snooze
+  },
+*/
+
+#[derive(Deserialize)]
+pub struct ParamsSnoozeNotifications {
+    pub devId: String,
+    pub notifId: Option<u64>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: DateTime<Utc>,
+    pub reason: String,
+}
+
+pub async fn snooze_notifications(
+    req: &HttpRequest,
+    globs: &Arc<GlobalVars>,
+) -> Result<HttpResponse, String> {
+    let req_params: ParamsSnoozeNotifications =
+        serde_json::from_slice(&req.content).map_err(|e| e.to_string())?;
+
+    let mut snoozes = globs.snoozes.write().await;
+    let created = snoozes
+        .add(
+            req_params.devId,
+            req_params.notifId,
+            req_params.since,
+            req_params.until,
+            req_params.reason,
+            None,
+        )?
+        .clone();
+    drop(snoozes);
+    snooze::save_to_file(globs).await?;
+
+    let response = json!({ "snooze": created });
+    let response = serde_json::to_vec(&response).map_err(|err| format!("[78] {err}"))?;
+    Ok(respond_http_json_bytes(200, response))
+}
+
+/*
+  ['/diel-internal/realtime-rs/getNotifSnoozes']: (reqParams: {
+    devIds?: string[]
+    onlyActive?: boolean // Ignora os que ainda não começaram (janelas de manutenção futuras)
+  }) => {
+    snoozes: Snooze[]
+  },
+*/
+
+#[derive(Deserialize)]
+pub struct ParamsGetNotifSnoozes {
+    pub devIds: Option<Vec<String>>,
+    pub onlyActive: Option<bool>,
+}
+
+pub async fn get_notif_snoozes(
+    req: &HttpRequest,
+    globs: &Arc<GlobalVars>,
+) -> Result<HttpResponse, String> {
+    let req_params: ParamsGetNotifSnoozes =
+        serde_json::from_slice(&req.content).map_err(|e| e.to_string())?;
+    let only_active = req_params.onlyActive.unwrap_or(false);
+    let now = sim_clock::now_utc();
+
+    let snoozes = globs.snoozes.read().await;
+    let list: Vec<&snooze::Snooze> = snoozes
+        .items
+        .iter()
+        .filter(|x| now < x.until)
+        .filter(|x| !only_active || x.is_active(&now))
+        .filter(|x| {
+            req_params
+                .devIds
+                .as_ref()
+                .is_none_or(|dev_ids| dev_ids.contains(&x.dev_id))
+        })
+        .collect();
+
+    let response = json!({ "snoozes": list });
+    let response = serde_json::to_vec(&response).map_err(|err| format!("[78] {err}"))?;
+    Ok(respond_http_json_bytes(200, response))
+}
+
+/*
+  ['/diel-internal/realtime-rs/cancelNotifSnooze']: (reqParams: {
+    snoozeId?: number
+    devId?: string // Cancela todos os snoozes do dispositivo (ou só os da notifId)
+    notifId?: number
+  }) => {
+    removed: number
+  },
+*/
+
+#[derive(Deserialize)]
+pub struct ParamsCancelNotifSnooze {
+    pub snoozeId: Option<u64>,
+    pub devId: Option<String>,
+    pub notifId: Option<u64>,
+}
+
+pub async fn cancel_notif_snooze(
+    req: &HttpRequest,
+    globs: &Arc<GlobalVars>,
+) -> Result<HttpResponse, String> {
+    let req_params: ParamsCancelNotifSnooze =
+        serde_json::from_slice(&req.content).map_err(|e| e.to_string())?;
+
+    let mut snoozes = globs.snoozes.write().await;
+    let removed = match (req_params.snoozeId, req_params.devId) {
+        (Some(snooze_id), _) => snoozes.cancel(snooze_id),
+        (None, Some(dev_id)) => snoozes.cancel_dev(&dev_id, req_params.notifId),
+        (None, None) => {
+            return Err("snoozeId ou devId é obrigatório".to_owned());
+        }
+    };
+    drop(snoozes);
+    if removed > 0 {
+        snooze::save_to_file(globs).await?;
+    }
+
+    let response = json!({ "removed": removed });
+    let response = serde_json::to_vec(&response).map_err(|err| format!("[78] {err}"))?;
+    Ok(respond_http_json_bytes(200, response))
+}
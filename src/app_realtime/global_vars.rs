@@ -1,6 +1,17 @@
+use super::capture::{CaptureHandle, CaptureWriter};
+use super::clock_drift::ClockDrift;
+use super::notifications::coverage::NotifCoverage;
 use super::notifications::dac::NotifsDac;
+use super::notifications::dal::NotifsDal;
+use super::notifications::dam::NotifsDam;
+use super::notifications::dma::NotifsDma;
+use super::notifications::dmt::NotifsDmt;
 use super::notifications::dut::NotifsDut;
+use super::notifications::history::NotifHistory;
+use super::notifications::incidents::IncidentGroups;
+use super::notifications::offline::NotifsOffline;
 use super::notifications::send_queue::MsgToQueue;
+use super::notifications::snooze::SnoozeList;
 use super::notifications::update_queue::MsgToQueueNotifUpdate;
 use crate::ConfigFile;
 use serde::{Deserialize, Serialize};
@@ -15,15 +26,31 @@ pub struct GlobalVars {
     pub need_update_notifs: AtomicBool,
     pub to_notifs_queue: mpsc::Sender<MsgToQueue>,
     pub to_notif_update_queue: mpsc::Sender<MsgToQueueNotifUpdate>,
+    pub snoozes: RwLock<SnoozeList>, // Notificações silenciadas (snooze e janelas de manutenção)
+    pub incident_groups: RwLock<IncidentGroups>, // Unidades e notificações usadas no agrupamento em incidentes
+    pub notif_history: RwLock<NotifHistory>, // Últimas detecções e resets de acumuladores de cada dispositivo
+    pub notif_coverage: RwLock<NotifCoverage>, // Dispositivos de cada notificação e se ela foi aceita em cada um
+    pub capture: CaptureHandle,                // Gravação das mensagens MQTT (desligada por padrão)
 }
 
 pub struct DevInfo {
     pub last_timestamp: AtomicU64, // Timestamp do servidor da última vez que chegou mensagem do dispostivo
     pub last_telemetry: RwLock<Option<DevLastMessage>>,
+    pub clock_drift: RwLock<Option<ClockDrift>>, // Estimativa do desvio do relógio do dispositivo
     pub has_notifs_dut: AtomicBool,
     pub notifs_dut: RwLock<Option<NotifsDut>>,
     pub has_notifs_dac: AtomicBool,
     pub notifs_dac: RwLock<Option<NotifsDac>>,
+    pub has_notifs_dma: AtomicBool,
+    pub notifs_dma: RwLock<Option<NotifsDma>>,
+    pub has_notifs_dam: AtomicBool,
+    pub notifs_dam: RwLock<Option<NotifsDam>>,
+    pub has_notifs_dal: AtomicBool,
+    pub notifs_dal: RwLock<Option<NotifsDal>>,
+    pub has_notifs_dmt: AtomicBool,
+    pub notifs_dmt: RwLock<Option<NotifsDmt>>,
+    pub has_notifs_offline: AtomicBool,
+    pub notifs_offline: RwLock<Option<NotifsOffline>>,
 }
 
 impl DevInfo {
@@ -31,10 +58,21 @@ impl DevInfo {
         DevInfo {
             last_timestamp: AtomicU64::new(now_millis),
             last_telemetry: RwLock::new(None),
+            clock_drift: RwLock::new(None),
             notifs_dut: RwLock::new(None),
             has_notifs_dut: AtomicBool::new(false),
             notifs_dac: RwLock::new(None),
             has_notifs_dac: AtomicBool::new(false),
+            notifs_dma: RwLock::new(None),
+            has_notifs_dma: AtomicBool::new(false),
+            notifs_dam: RwLock::new(None),
+            has_notifs_dam: AtomicBool::new(false),
+            notifs_dal: RwLock::new(None),
+            has_notifs_dal: AtomicBool::new(false),
+            notifs_dmt: RwLock::new(None),
+            has_notifs_dmt: AtomicBool::new(false),
+            notifs_offline: RwLock::new(None),
+            has_notifs_offline: AtomicBool::new(false),
         }
     }
 }
@@ -54,10 +92,12 @@ impl GlobalVars {
         GlobalVars,
         mpsc::Receiver<MsgToQueue>,
         mpsc::Receiver<MsgToQueueNotifUpdate>,
+        CaptureWriter,
     ) {
         let (to_notifs_queue, receiver_notifs) = mpsc::channel::<MsgToQueue>(10000);
         let (to_notif_update_queue, receiver_notif_update) =
             mpsc::channel::<MsgToQueueNotifUpdate>(1000);
+        let (capture, capture_writer) = CaptureHandle::new();
 
         let globs = GlobalVars {
             configfile,
@@ -65,8 +105,18 @@ impl GlobalVars {
             need_update_notifs: AtomicBool::new(true),
             to_notifs_queue,
             to_notif_update_queue,
+            snoozes: RwLock::new(SnoozeList::default()),
+            incident_groups: RwLock::new(IncidentGroups::default()),
+            notif_history: RwLock::new(NotifHistory::default()),
+            notif_coverage: RwLock::new(NotifCoverage::default()),
+            capture,
         };
 
-        (globs, receiver_notifs, receiver_notif_update)
+        (
+            globs,
+            receiver_notifs,
+            receiver_notif_update,
+            capture_writer,
+        )
     }
 }
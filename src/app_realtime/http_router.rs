@@ -1,6 +1,14 @@
+use super::endpoints::get_dac_compressor_usage::get_dac_compressor_usage;
+use super::endpoints::get_devices_clock_drift::get_devices_clock_drift;
 use super::endpoints::get_devices_last_telemetries::get_devices_last_telemetries;
 use super::endpoints::get_devices_last_ts::get_devices_last_ts;
+use super::endpoints::get_notif_coverage::get_notif_coverage;
+use super::endpoints::get_notif_history::get_notif_history;
 use super::endpoints::inspect_dev_notifications::inspect_dev_notifications;
+use super::endpoints::mqtt_capture::mqtt_capture;
+use super::endpoints::notif_snoozes::{
+    cancel_notif_snooze, get_notif_snoozes, snooze_notifications,
+};
 use crate::lib_http::response::{respond_http_plain_text, send_response};
 use crate::lib_http::types::HttpRequest;
 use crate::GlobalVars;
@@ -22,11 +30,37 @@ pub async fn on_http_req(
         "/diel-internal/realtime-rs/getDevicesLastTS" => get_devices_last_ts(&req, &globs)
             .await
             .unwrap_or_else(|err| respond_http_plain_text(400, &err)),
+        "/diel-internal/realtime-rs/getDevicesClockDrift" => get_devices_clock_drift(&req, &globs)
+            .await
+            .unwrap_or_else(|err| respond_http_plain_text(400, &err)),
+        "/diel-internal/realtime-rs/getDacCompressorUsage" => {
+            get_dac_compressor_usage(&req, &globs)
+                .await
+                .unwrap_or_else(|err| respond_http_plain_text(400, &err))
+        }
         "/diel-internal/realtime-rs/inspect_dev_notifications" => {
             inspect_dev_notifications(&req, &globs)
                 .await
                 .unwrap_or_else(|err| respond_http_plain_text(400, &err))
         }
+        "/diel-internal/realtime-rs/getNotifCoverage" => get_notif_coverage(&req, &globs)
+            .await
+            .unwrap_or_else(|err| respond_http_plain_text(400, &err)),
+        "/diel-internal/realtime-rs/mqttCapture" => mqtt_capture(&req, &globs)
+            .await
+            .unwrap_or_else(|err| respond_http_plain_text(400, &err)),
+        "/diel-internal/realtime-rs/getNotifHistory" => get_notif_history(&req, &globs)
+            .await
+            .unwrap_or_else(|err| respond_http_plain_text(400, &err)),
+        "/diel-internal/realtime-rs/snoozeNotifications" => snooze_notifications(&req, &globs)
+            .await
+            .unwrap_or_else(|err| respond_http_plain_text(400, &err)),
+        "/diel-internal/realtime-rs/getNotifSnoozes" => get_notif_snoozes(&req, &globs)
+            .await
+            .unwrap_or_else(|err| respond_http_plain_text(400, &err)),
+        "/diel-internal/realtime-rs/cancelNotifSnooze" => cancel_notif_snooze(&req, &globs)
+            .await
+            .unwrap_or_else(|err| respond_http_plain_text(400, &err)),
         _ => {
             crate::write_to_log_file(
                 "ERROR",
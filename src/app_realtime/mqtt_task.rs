@@ -1,3 +1,4 @@
+use super::capture;
 use super::on_mqtt_message;
 use crate::lib_rumqtt::{abrir_conexao_broker_rumqtt, next_mqtt_message_rumqtt};
 use crate::GlobalVars;
@@ -63,6 +64,9 @@ async fn task_mqtt_client_broker_rumqtt(globs: &Arc<GlobalVars>) -> Result<Strin
     loop {
         let packet = next_mqtt_message_rumqtt(&mut eventloop, broker_config).await?;
 
+        // Gravação opcional das mensagens para o modo "--replay", na ordem em que chegaram do broker
+        capture::on_mqtt_message(&packet, globs);
+
         on_mqtt_message::process_payload(packet, globs);
     }
 }
@@ -0,0 +1,238 @@
+/*
+Índice das notificações configuradas: quais dispositivos cada notificação monitora e se ela está ativa em cada um.
+ - O "from_notif_cfg" dos tipos de notificação pode recusar a configuração em um dispositivo
+   (ex.: "DUT_CO2 >" sem CO2MAX na programação). O motivo da recusa fica registrado aqui.
+ - Dispositivos que ainda não enviaram nenhuma mensagem não têm registro no "globs.devs_info", então a
+   notificação ainda não está sendo verificada neles.
+Usado pelo API-Server para avisar o usuário que o alerta dele não está ativo.
+*/
+
+use super::notifs_cfg::{is_known_cond_id, NotifsCfgResponse_notif_item};
+use crate::app_realtime::global_vars::DevInfo;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Notificações recusadas ao atualizar um dispositivo: (notif_id, motivo)
+pub type NotifRejections = Vec<(u64, String)>;
+
+#[derive(Debug)]
+pub struct NotifCoverageItem {
+    pub cond_id: String,
+    pub dev_ids: Vec<String>,
+    // "dev_id" -> motivo da recusa. Só tem os dispositivos em que a notificação já foi interpretada.
+    pub checked: HashMap<String, Option<String>>,
+}
+
+#[derive(Debug, Default)]
+pub struct NotifCoverage {
+    pub by_notif: HashMap<u64, NotifCoverageItem>,
+}
+
+impl NotifCoverage {
+    /// Atualiza o índice com a lista que veio do API-Server. Se a lista for completa, as outras notificações são removidas.
+    pub fn set_notifs(&mut self, notifs_list: &[NotifsCfgResponse_notif_item], complete: bool) {
+        if complete {
+            self.by_notif.clear();
+        }
+        for notif in notifs_list.iter() {
+            self.by_notif.insert(
+                notif.notif_id,
+                NotifCoverageItem {
+                    cond_id: notif.cond_id.clone(),
+                    dev_ids: notif.dev_ids.clone(),
+                    checked: HashMap::new(),
+                },
+            );
+        }
+    }
+
+    pub fn remove_notif(&mut self, notif_id: u64) {
+        self.by_notif.remove(&notif_id);
+    }
+
+    /// Registra o resultado da interpretação das notificações de um dispositivo
+    pub fn record_dev_check(
+        &mut self,
+        dev_id: &str,
+        dev_notifs: &[Arc<NotifsCfgResponse_notif_item>],
+        rejected: &NotifRejections,
+    ) {
+        for notif in dev_notifs.iter() {
+            let Some(item) = self.by_notif.get_mut(&notif.notif_id) else {
+                continue;
+            };
+            let reason = rejected
+                .iter()
+                .find(|(notif_id, _)| *notif_id == notif.notif_id)
+                .map(|(_, reason)| reason.clone());
+            item.checked.insert(dev_id.to_owned(), reason);
+        }
+    }
+
+    /// Dispositivos em que a notificação foi aceita e está sendo verificada
+    pub fn active_dev_ids(&self, notif_id: u64) -> Vec<String> {
+        let Some(item) = self.by_notif.get(&notif_id) else {
+            return Vec::new();
+        };
+        let mut dev_ids: Vec<String> = item
+            .checked
+            .iter()
+            .filter(|(_, reason)| reason.is_none())
+            .map(|(dev_id, _)| dev_id.to_owned())
+            .collect();
+        dev_ids.sort();
+        dev_ids
+    }
+
+    /// Situação das notificações em cada dispositivo, filtrando por notificação e/ou por dispositivo
+    pub fn report(
+        &self,
+        notif_id: Option<u64>,
+        dev_id: Option<&str>,
+        devs_info: &HashMap<String, DevInfo>,
+    ) -> Vec<serde_json::Value> {
+        let mut list = Vec::new();
+        for (item_notif_id, item) in self.by_notif.iter() {
+            if notif_id.is_some_and(|x| x != *item_notif_id) {
+                continue;
+            }
+            if dev_id.is_some_and(|x| !item.dev_ids.iter().any(|d| d == x)) {
+                continue;
+            }
+
+            let known_cond_id = is_known_cond_id(&item.cond_id);
+            let mut devices = Vec::with_capacity(item.dev_ids.len());
+            let mut active_count = 0;
+            for item_dev_id in item.dev_ids.iter() {
+                if dev_id.is_some_and(|x| x != item_dev_id) {
+                    continue;
+                }
+                let (status, reason) = match item.checked.get(item_dev_id) {
+                    _ if !known_cond_id => ("unknown_cond_id", None),
+                    Some(Some(reason)) => ("rejected", Some(reason.as_str())),
+                    Some(None) => ("active", None),
+                    None if devs_info.contains_key(item_dev_id) => ("pending", None),
+                    None => ("never_seen", None),
+                };
+                if status == "active" {
+                    active_count += 1;
+                }
+                devices.push(json!({
+                    "dev_id": item_dev_id,
+                    "status": status,
+                    "reason": reason,
+                }));
+            }
+
+            let status = if !known_cond_id {
+                "unknown_cond_id"
+            } else if active_count > 0 {
+                "active"
+            } else {
+                "inactive"
+            };
+            list.push(json!({
+                "notif_id": item_notif_id,
+                "cond_id": item.cond_id,
+                "status": status,
+                "active_devices": active_count,
+                "devices": devices,
+            }));
+        }
+        list.sort_by_key(|x| x["notif_id"].as_u64());
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn co2_notif() -> NotifsCfgResponse_notif_item {
+        NotifsCfgResponse_notif_item {
+            notif_id: 1,
+            cond_id: "DUT_CO2 >".to_owned(),
+            cond_pars: json!({}),
+            dev_ids: vec!["DUT1".to_owned(), "DUT2".to_owned(), "DUT3".to_owned()],
+        }
+    }
+
+    #[test]
+    fn active_dev_ids_skips_rejected_and_unchecked_devices() {
+        let mut coverage = NotifCoverage::default();
+        coverage.set_notifs(&[co2_notif()], true);
+
+        let dev_notifs = [Arc::new(co2_notif())];
+        coverage.record_dev_check("DUT2", &dev_notifs, &vec![]);
+        coverage.record_dev_check("DUT1", &dev_notifs, &vec![]);
+        let rejected = vec![(1, "CO2MAX é obrigatório para a notificação".to_owned())];
+        coverage.record_dev_check("DUT3", &dev_notifs, &rejected);
+
+        assert_eq!(coverage.active_dev_ids(1), vec!["DUT1", "DUT2"]);
+        assert!(coverage.active_dev_ids(2).is_empty());
+
+        coverage.remove_notif(1);
+        assert!(coverage.active_dev_ids(1).is_empty());
+    }
+
+    #[test]
+    fn report_status_per_device() {
+        let mut notif = co2_notif();
+        notif.dev_ids.push("DUT4".to_owned());
+        let unknown = NotifsCfgResponse_notif_item {
+            notif_id: 2,
+            cond_id: "XYZ >".to_owned(),
+            cond_pars: json!({}),
+            dev_ids: vec!["DUT1".to_owned()],
+        };
+        let mut coverage = NotifCoverage::default();
+        coverage.set_notifs(&[notif], true);
+        // Lista parcial não remove a notificação 1
+        coverage.set_notifs(&[unknown], false);
+
+        let dev_notifs = [Arc::new(co2_notif())];
+        coverage.record_dev_check("DUT1", &dev_notifs, &vec![]);
+        let rejected = vec![(1, "CO2MAX é obrigatório para a notificação".to_owned())];
+        coverage.record_dev_check("DUT2", &dev_notifs, &rejected);
+
+        // DUT3 já enviou mensagem mas ainda não foi interpretado, DUT4 nunca foi visto
+        let mut devs_info = HashMap::new();
+        for dev_id in ["DUT1", "DUT2", "DUT3"] {
+            devs_info.insert(dev_id.to_owned(), DevInfo::new(0, dev_id));
+        }
+
+        let list = coverage.report(None, None, &devs_info);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0]["status"], "active");
+        assert_eq!(list[0]["active_devices"], 1);
+        let statuses: Vec<_> = list[0]["devices"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| (x["dev_id"].as_str().unwrap(), x["status"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("DUT1", "active"),
+                ("DUT2", "rejected"),
+                ("DUT3", "pending"),
+                ("DUT4", "never_seen"),
+            ]
+        );
+        assert_eq!(list[0]["devices"][1]["reason"], rejected[0].1);
+        assert_eq!(list[1]["status"], "unknown_cond_id");
+        assert_eq!(list[1]["devices"][0]["status"], "unknown_cond_id");
+
+        // Filtrando pelo dispositivo só vêm as notificações dele, com o dispositivo filtrado
+        let list = coverage.report(None, Some("DUT2"), &devs_info);
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0]["status"], "inactive");
+        assert_eq!(list[0]["devices"].as_array().unwrap().len(), 1);
+
+        let list = coverage.report(Some(2), None, &devs_info);
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0]["notif_id"], 2);
+    }
+}
@@ -0,0 +1,264 @@
+use crate::app_realtime::notifications::dac::on_dac_telemetry::DacTelemetry;
+use crate::app_realtime::notifications::episode::{PeakKind, ViolationEpisode};
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::NotifsCfgResponse_notif_item;
+use crate::app_realtime::notifications::rules::{
+    DetectionSink, NotifRule, RuleSample, StreamDelta,
+};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+/// Janela de violação da corrente: acumula o tempo e a média desde o início do episódio
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct CurrentViolation {
+    pub acc_t: u64,
+    pub sum: f64,
+    pub samples: u64,
+    pub is_notified: bool,
+    pub episode: Option<ViolationEpisode>,
+    // Não é zerado no fim do episódio, o COOLDOWN_MINUTES vale entre episódios
+    pub last_notif_sent: Option<DateTime<Utc>>,
+}
+
+impl CurrentViolation {
+    fn reset(&mut self) {
+        self.acc_t = 0;
+        self.sum = 0.0;
+        self.samples = 0;
+        self.is_notified = false;
+    }
+
+    fn avg(&self) -> Option<f64> {
+        (self.samples > 0).then(|| self.sum / self.samples as f64)
+    }
+}
+
+// Padrão: 2 minutos na condição, uma notificação por episódio
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 2 * 60,
+    cooldown_secs: 0,
+    continuity_secs: 30,
+    renotify_secs: None,
+};
+
+#[derive(Debug, Serialize)]
+pub struct NotifCompressorCurrent {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+
+    pub current_limit: f64,
+    pub no_current_limit: f64,
+    pub timing: NotifTiming,
+
+    pub overload: CurrentViolation,
+    pub no_current: CurrentViolation,
+}
+impl NotifCompressorCurrent {
+    // "COMP_CURR >" correnteCompressorAcimaNominal
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+    ) -> Result<Self, String> {
+        let cond_pars = &updated_notif_data.cond_pars;
+        // Pega o COND_PARS['NOMINAL_CURRENT'] (corrente nominal do compressor em A)
+        let nominal_current = match cond_pars["NOMINAL_CURRENT"].as_f64() {
+            Some(x) if x > 0.0 => x,
+            _ => {
+                return Err("NOMINAL_CURRENT é obrigatório para a notificação".to_owned());
+            }
+        };
+        // OVERLOAD_PERCENT é a tolerância acima da corrente nominal antes de considerar sobrecarga
+        let overload_percent = cond_pars["OVERLOAD_PERCENT"]
+            .as_f64()
+            .unwrap_or(10.0)
+            .max(0.0);
+        // NO_CURRENT_LIMIT é a corrente abaixo da qual o compressor ligado é considerado sem corrente
+        let no_current_limit = cond_pars["NO_CURRENT_LIMIT"]
+            .as_f64()
+            .unwrap_or(0.5)
+            .max(0.0);
+
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
+            current_limit: nominal_current * (1.0 + overload_percent / 100.0),
+            no_current_limit,
+            timing: NotifTiming::from_cond_pars(cond_pars, DEFAULT_TIMING),
+            overload: CurrentViolation::default(),
+            no_current: CurrentViolation::default(),
+        })
+    }
+}
+
+impl NotifRule<DacTelemetry> for NotifCompressorCurrent {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<DacTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.overload = existing.overload.clone();
+        self.no_current = existing.no_current.clone();
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DacTelemetry>, sink: &mut DetectionSink) {
+        let telemetry = sample.telemetry;
+        // Cada notificação pode ter o seu próprio CONTINUITY_SECONDS
+        let Some(StreamDelta {
+            delta_secs,
+            descontinuidade,
+            ..
+        }) = sample.delta("Curr", self.timing.continuity_secs)
+        else {
+            return;
+        };
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        if descontinuidade && (self.overload.acc_t > 0 || self.no_current.acc_t > 0) {
+            // Registra no histórico que os acumuladores da notificação vão ser zerados
+            let detail = serde_json::json!({
+                "stream": "Curr",
+                "overload_secs": self.overload.acc_t,
+                "no_current_secs": self.no_current.acc_t,
+            });
+            sink.push_reset(self.notif_id, "descontinuidade", detail);
+        }
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        let Some(telemetry_curr) = telemetry.Curr else {
+            return;
+        };
+        let telemetry_timestamp = &telemetry.timestamp;
+
+        let is_overload = telemetry_curr > self.current_limit;
+        // Compressor ligado sem corrente indica falha no contator ou no sensor
+        let compressor_on = telemetry.Lcmp.is_some_and(|x| x != 0);
+        let is_no_current = compressor_on && telemetry_curr < self.no_current_limit;
+
+        // Sobrecarga e falta de corrente são avaliadas de forma independente
+        let current_limit = self.current_limit;
+        let no_current_limit = self.no_current_limit;
+        let timing = self.timing;
+        let duration_in_seconds = timing.duration_secs;
+        let notif_id = self.notif_id;
+        let historical_policy = self.historical_policy;
+
+        for (violation, violating, peak_kind, path, normalized_path, limit) in [
+            (
+                &mut self.overload,
+                is_overload,
+                PeakKind::Max,
+                "/COMP_CURR/Sobrecarga",
+                "/COMP_CURR/Sobrecarga/Normalizado",
+                current_limit,
+            ),
+            (
+                &mut self.no_current,
+                is_no_current,
+                PeakKind::Min,
+                "/COMP_CURR/SemCorrente",
+                "/COMP_CURR/SemCorrente/Normalizado",
+                no_current_limit,
+            ),
+        ] {
+            // Quando a corrente volta ao normal, informa o fim do episódio que foi notificado
+            let ended_episode = ViolationEpisode::track(
+                &mut violation.episode,
+                violating,
+                telemetry_timestamp,
+                telemetry_curr,
+                peak_kind,
+                historical_policy.should_send(is_historical),
+            );
+            if let Some(ended_episode) = ended_episode {
+                sink.push(
+                    normalized_path,
+                    ended_episode.normalized_detection(
+                        dev_id,
+                        notif_id,
+                        telemetry_timestamp,
+                        is_historical,
+                    ),
+                );
+            }
+
+            if !violating {
+                violation.reset();
+                continue;
+            }
+            // A condição precisa durar sem interrupção
+            if descontinuidade {
+                violation.acc_t = 0;
+            }
+
+            violation.sum += telemetry_curr;
+            violation.samples += 1;
+
+            // O episódio notificado só é enviado de novo no reenvio do RENOTIFY_HOURS
+            let decision =
+                timing.send_decision(violation.last_notif_sent.as_ref(), violation.is_notified);
+            let renotify = decision == SendDecision::Renotify;
+            if violation.is_notified && !renotify {
+                continue;
+            }
+
+            if !renotify && violation.acc_t < duration_in_seconds {
+                violation.acc_t += delta_secs;
+                continue;
+            }
+
+            // Dentro do intervalo mínimo entre notificações (COOLDOWN_MINUTES)
+            if decision == SendDecision::Blocked {
+                continue;
+            }
+
+            if !historical_policy.should_send(is_historical) {
+                // A detecção cairia no período histórico, então fica como notificada
+                // para não virar um alerta antigo na primeira telemetria ao vivo
+                violation.is_notified = true;
+                continue;
+            }
+            violation.is_notified = true;
+            violation.last_notif_sent = Some(sim_clock::now_utc());
+            ViolationEpisode::mark_notified(&mut violation.episode);
+            sink.push(
+                path,
+                serde_json::json!({
+                    "dev_id": dev_id.to_owned(),
+                    "notif_id": notif_id,
+                    "current_limit": limit,
+                    "peak_current": violation.episode.as_ref().map(|x| x.peak),
+                    "avg_current": violation.avg(),
+                    "window_start": violation.episode.as_ref().map(|x| x.start),
+                    "duration_in_seconds": duration_in_seconds,
+                    "renotification": renotify,
+                    "telemetry_timestamp": *telemetry_timestamp,
+                    "detection_time": sim_clock::now_utc(),
+                    "episode": ViolationEpisode::context(&violation.episode),
+                    "late": is_historical,
+                }),
+            );
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub use dac_curr_compressor::NotifCompressorCurrent;
+
+pub mod dac_curr_compressor;
@@ -0,0 +1,267 @@
+use super::L1_CONTINUITY_SECS;
+use crate::app_realtime::notifications::dac::on_dac_telemetry::DacTelemetry;
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::NotifsCfgResponse_notif_item;
+use crate::app_realtime::notifications::rules::{
+    DetectionSink, NotifRule, RuleSample, StreamDelta,
+};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CompressorTransition {
+    pub timestamp: NaiveDateTime,
+    pub turned_on: bool,
+}
+
+// Padrão: no máximo uma notificação por hora (o DURATION_IN_MINUTES não é usado, a janela é o WINDOW_MINUTES)
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 0,
+    cooldown_secs: 60 * 60,
+    continuity_secs: L1_CONTINUITY_SECS,
+    renotify_secs: None,
+};
+
+#[derive(Debug, Serialize)]
+pub struct NotifCompressorShortCycling {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    pub last_notif_sent: Option<DateTime<Utc>>,
+    pub timing: NotifTiming,
+
+    pub max_cycles_per_hour: u64,
+    pub window_secs: u64,
+
+    pub last_state: Option<bool>,
+    // Transições de liga/desliga do compressor dentro da janela, da mais antiga para a mais recente
+    pub transitions: VecDeque<CompressorTransition>,
+}
+impl NotifCompressorShortCycling {
+    // "COMP_CYCLES >" compressorLigarDesligarMaisQue N vezes por hora
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+    ) -> Result<Self, String> {
+        // Pega o COND_PARS['MAX_CYCLES_PER_HOUR']
+        let max_cycles_per_hour = updated_notif_data.cond_pars["MAX_CYCLES_PER_HOUR"].as_u64();
+        let max_cycles_per_hour = match max_cycles_per_hour {
+            Some(x) if x > 0 => x,
+            _ => {
+                return Err("MAX_CYCLES_PER_HOUR é obrigatório para a notificação".to_owned());
+            }
+        };
+        // WINDOW_MINUTES é o tamanho da janela deslizante usada para contar os ciclos
+        let window_minutes = updated_notif_data.cond_pars["WINDOW_MINUTES"]
+            .as_u64()
+            .unwrap_or(60)
+            .max(1);
+
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
+            last_notif_sent: None,
+            timing: NotifTiming::from_cond_pars(&updated_notif_data.cond_pars, DEFAULT_TIMING),
+            max_cycles_per_hour,
+            window_secs: window_minutes * 60,
+            last_state: None,
+            transitions: VecDeque::new(),
+        })
+    }
+
+    /// Limite de ciclos proporcional ao tamanho da janela
+    fn max_cycles_in_window(&self) -> f64 {
+        (self.max_cycles_per_hour as f64) * (self.window_secs as f64) / 3600.0
+    }
+
+    /// Média das durações (em segundos) dos períodos ligado e desligado entre as transições da janela
+    fn avg_durations(&self) -> (Option<f64>, Option<f64>) {
+        let mut on_total = 0i64;
+        let mut on_count = 0i64;
+        let mut off_total = 0i64;
+        let mut off_count = 0i64;
+        for (prev, next) in self.transitions.iter().zip(self.transitions.iter().skip(1)) {
+            let duration = (next.timestamp - prev.timestamp).num_seconds();
+            if prev.turned_on {
+                on_total += duration;
+                on_count += 1;
+            } else {
+                off_total += duration;
+                off_count += 1;
+            }
+        }
+        let on_avg = (on_count > 0).then(|| on_total as f64 / on_count as f64);
+        let off_avg = (off_count > 0).then(|| off_total as f64 / off_count as f64);
+        (on_avg, off_avg)
+    }
+}
+
+impl NotifRule<DacTelemetry> for NotifCompressorShortCycling {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<DacTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.last_notif_sent = existing.last_notif_sent;
+        self.last_state = existing.last_state;
+        self.transitions = existing.transitions.clone();
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DacTelemetry>, sink: &mut DetectionSink) {
+        let Some(telemetry_l1) = sample.telemetry.Lcmp else {
+            return;
+        };
+        let Some(StreamDelta {
+            descontinuidade, ..
+        }) = sample.delta("Lcmp", self.timing.continuity_secs)
+        else {
+            return;
+        };
+        let telemetry_timestamp = sample.timestamp();
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        // Sem continuidade não dá para saber quantas transições aconteceram no intervalo
+        if descontinuidade {
+            if !self.transitions.is_empty() {
+                // Registra no histórico que as transições da janela vão ser descartadas
+                let detail =
+                    serde_json::json!({ "stream": "Lcmp", "transitions": self.transitions.len() });
+                sink.push_reset(self.notif_id, "descontinuidade", detail);
+            }
+            self.transitions.clear();
+            self.last_state = None;
+        }
+
+        let curr_state = telemetry_l1 != 0;
+        let prev_state = self.last_state.replace(curr_state);
+        if prev_state.is_none() || prev_state == Some(curr_state) {
+            return;
+        }
+
+        self.transitions.push_back(CompressorTransition {
+            timestamp: *telemetry_timestamp,
+            turned_on: curr_state,
+        });
+
+        // Descarta as transições que saíram da janela
+        let window_start = *telemetry_timestamp - TimeDelta::seconds(self.window_secs as i64);
+        while self
+            .transitions
+            .front()
+            .is_some_and(|x| x.timestamp < window_start)
+        {
+            self.transitions.pop_front();
+        }
+
+        // Cada vez que o compressor liga conta como um ciclo
+        let cycles = self.transitions.iter().filter(|x| x.turned_on).count() as u64;
+        if (cycles as f64) <= self.max_cycles_in_window() {
+            return;
+        }
+
+        // Dentro do intervalo mínimo entre notificações (COOLDOWN_MINUTES) a janela continua deslizando,
+        // e como o limite está sendo ultrapassado agora a condição é considerada ativa para o RENOTIFY_HOURS
+        let decision = self
+            .timing
+            .send_decision(self.last_notif_sent.as_ref(), true);
+        if decision == SendDecision::Blocked {
+            return;
+        }
+
+        if !self.historical_policy.should_send(is_historical) {
+            // A detecção cairia no período histórico, então o estado que a dispararia é descartado
+            // para não virar um alerta antigo na primeira telemetria ao vivo
+            self.transitions.clear();
+            return;
+        }
+
+        let (avg_on_secs, avg_off_secs) = self.avg_durations();
+        let window_start = self.transitions.front().map(|x| x.timestamp);
+
+        // Começa uma nova contagem para não notificar de novo a cada transição
+        self.transitions.clear();
+        self.last_notif_sent = Some(sim_clock::now_utc());
+
+        sink.push(
+            "/COMP_CYCLES/Acima",
+            serde_json::json!({
+                "dev_id": dev_id.to_owned(),
+                "notif_id": self.notif_id,
+                "max_cycles_per_hour": self.max_cycles_per_hour,
+                "window_seconds": self.window_secs,
+                "cycles": cycles,
+                "avg_on_seconds": avg_on_secs,
+                "avg_off_seconds": avg_off_secs,
+                "window_start": window_start,
+                "renotification": decision == SendDecision::Renotify,
+                "telemetry_timestamp": *telemetry_timestamp,
+                "detection_time": sim_clock::now_utc(),
+                "late": is_historical,
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app_realtime::notifications::dac::dac_l1::test_helpers::l1_samples;
+    use crate::app_realtime::notifications::dac::notifs_dac::dac_rules_registry;
+    use crate::app_realtime::notifications::rules::test_helpers::*;
+    use crate::app_realtime::sim_clock;
+
+    #[test]
+    fn cycles_above_the_limit_respect_the_cooldown() {
+        let mut set = rule_set(
+            dac_rules_registry(),
+            "COMP_CYCLES >",
+            serde_json::json!({ "MAX_CYCLES_PER_HOUR": 2 }),
+            &None,
+        );
+        sim_clock::set_simulated_now(1_767_261_600_000);
+
+        // O compressor liga 3 vezes em um minuto
+        let detections = run(
+            &mut set,
+            l1_samples(ts("2026-01-01 10:00:00"), &[0, 1, 0, 1, 0, 1]),
+        );
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/COMP_CYCLES/Acima");
+        assert_eq!(detections[0].1["cycles"], 3);
+
+        // Continua ciclando, mas dentro do COOLDOWN_MINUTES padrão de uma hora
+        let detections = run(
+            &mut set,
+            l1_samples(ts("2026-01-01 10:01:00"), &[0, 1, 0, 1, 0, 1, 0, 1]),
+        );
+        assert!(detections.is_empty());
+
+        // Passado o intervalo mínimo, a próxima partida acima do limite é notificada
+        sim_clock::set_simulated_now(1_767_261_600_000 + 61 * 60 * 1000);
+        let detections = run(&mut set, l1_samples(ts("2026-01-01 10:02:20"), &[0, 1]));
+        assert_eq!(detections.len(), 1);
+        sim_clock::set_simulated_now(0);
+    }
+}
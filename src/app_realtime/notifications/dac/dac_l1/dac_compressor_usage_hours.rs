@@ -1,16 +1,33 @@
-use crate::{
-    app_realtime::notifications::notifs_cfg::NotifsCfgResponse_notif_item, global_vars::GlobalVars,
+use super::CompressorUsage;
+use crate::app_realtime::notifications::dac::on_dac_telemetry::DacTelemetry;
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::NotifsCfgResponse_notif_item;
+use crate::app_realtime::notifications::rules::{
+    DetectionSink, NotifRule, RuleSample, StreamDelta,
 };
-use chrono::{NaiveDateTime, NaiveTime, TimeDelta, Utc};
-use std::{collections::HashMap, sync::Arc};
-use tokio::time::Instant;
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, NaiveTime, TimeDelta, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
 
-#[derive(Debug)]
+// Padrão: no máximo uma notificação a cada 24 horas (a troca de dia também libera nova notificação)
+const COMP_TIME_DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 0,
+    cooldown_secs: 24 * 60 * 60,
+    continuity_secs: 30,
+    renotify_secs: None,
+};
+
+#[derive(Debug, Serialize)]
 pub struct NotifCompressorUsedBeforeHour {
     pub notif_id: u64,
-    pub last_notif_sent: Option<Instant>,
+    pub historical_policy: HistoricalPolicy,
+    pub last_notif_sent: Option<DateTime<Utc>>,
 
     pub time_limit: NaiveTime,
+    pub timing: NotifTiming,
 }
 impl NotifCompressorUsedBeforeHour {
     // "COMP_TIME <" compressorEstiverLigadoAntesDe 'HH:mm'
@@ -33,85 +50,126 @@ impl NotifCompressorUsedBeforeHour {
 
         Ok(Self {
             notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                HistoricalPolicy::Skip,
+            ),
             last_notif_sent: None,
             time_limit,
+            timing: NotifTiming::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                COMP_TIME_DEFAULT_TIMING,
+            ),
         })
     }
+}
 
-    pub fn update_new_notifs_parameters(
-        list_old: &HashMap<u64, Self>,
-        list_new: &mut HashMap<u64, Self>,
-    ) {
-        for (notif_id, updated_notif) in list_new.iter_mut() {
-            updated_notif.update_notif_parameters(list_old.get(&notif_id));
-        }
+impl NotifRule<DacTelemetry> for NotifCompressorUsedBeforeHour {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
     }
 
-    pub fn update_notif_parameters(&mut self, existing: Option<&Self>) {
-        if let Some(existing) = existing {
-            self.last_notif_sent = existing.last_notif_sent;
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
 
-            // Se tiver alterado o horário da notificação, deixa enviar nova notificação hoje ainda
-            let changed_time = existing.time_limit != self.time_limit;
-            if changed_time {
-                self.last_notif_sent = None;
-            }
+    fn carry_over(&mut self, existing: &dyn NotifRule<DacTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.last_notif_sent = existing.last_notif_sent;
+
+        // Se tiver alterado o horário da notificação, deixa enviar nova notificação hoje ainda
+        let changed_time = existing.time_limit != self.time_limit;
+        if changed_time {
+            self.last_notif_sent = None;
         }
     }
 
-    pub async fn on_dac_telemetry(
-        &mut self,
-        telemetry_l1: i16,
-        telemetry_timestamp: &NaiveDateTime,
-        new_day: bool,
-        dev_id: &str,
-        globs: &Arc<GlobalVars>,
-    ) -> Result<(), String> {
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    // Só depende do horário da telemetria, a política histórica define se a detecção é enviada
+    fn evaluates_out_of_order(&self) -> bool {
+        true
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DacTelemetry>, sink: &mut DetectionSink) {
+        let Some(telemetry_l1) = sample.telemetry.Lcmp else {
+            return;
+        };
+        // Cada notificação pode ter o seu próprio CONTINUITY_SECONDS
+        let Some(StreamDelta {
+            descontinuidade,
+            new_day,
+            ..
+        }) = sample.delta("Lcmp", self.timing.continuity_secs)
+        else {
+            return;
+        };
+        let telemetry_timestamp = sample.timestamp();
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
         // Se o compressor não estiver ligado não tem nada para conferir
         if telemetry_l1 == 0 {
-            return Ok(());
+            return;
         }
 
         // Quando troca o dia, libera nova notificação
         if new_day && self.last_notif_sent.is_some() {
             self.last_notif_sent = None;
+            let detail = serde_json::json!({ "stream": "Lcmp" });
+            sink.push_reset(self.notif_id, "new_day", detail);
         }
 
-        // Se já tiver enviado uma notificação menos de 24 horas atrás, não precisa nem conferir
-        if let Some(last_notif_sent) = self.last_notif_sent.as_ref() {
-            if last_notif_sent.elapsed().as_secs() < 24 * 60 * 60 {
-                return Ok(());
-            }
+        // Se estiver dentro do intervalo mínimo entre notificações, não precisa nem conferir.
+        // A notificação enviada hoje indica que o compressor continua ligado fora do horário,
+        // a não ser que tenha faltado telemetria (CONTINUITY_SECONDS) desde então.
+        let persisting = self.last_notif_sent.is_some() && !descontinuidade;
+        let decision = self
+            .timing
+            .send_decision(self.last_notif_sent.as_ref(), persisting);
+        if decision == SendDecision::Blocked {
+            return;
         }
 
         if telemetry_timestamp.time() < self.time_limit {
-            self.last_notif_sent = Some(Instant::now());
-            let result = globs
-                .to_notifs_queue
-                .send((
-                    "/COMP_TIME/AntesDoHorario",
-                    serde_json::json!({
-                        "dev_id": dev_id.to_owned(),
-                        "notif_id": self.notif_id,
-                        "time_limit": self.time_limit.format("%H:%M:%S").to_string(),
-                        "telemetry_timestamp": *telemetry_timestamp,
-                        "detection_time": Utc::now(),
-                    }),
-                ))
-                .await;
-            result.map_err(|err| crate::log_err("[216]", err)).ok();
-        }
-
-        return Ok(());
+            if !self.historical_policy.should_send(is_historical) {
+                // Telemetria histórica com política de não enviar detecções
+                return;
+            }
+            self.last_notif_sent = Some(sim_clock::now_utc());
+            sink.push(
+                "/COMP_TIME/AntesDoHorario",
+                serde_json::json!({
+                    "dev_id": dev_id.to_owned(),
+                    "notif_id": self.notif_id,
+                    "time_limit": self.time_limit.format("%H:%M:%S").to_string(),
+                    "renotification": decision == SendDecision::Renotify,
+                    "telemetry_timestamp": *telemetry_timestamp,
+                    "detection_time": sim_clock::now_utc(),
+                    "late": is_historical,
+                }),
+            );
+        }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct NotifCompressorUsedAfterHour {
     pub notif_id: u64,
-    pub last_notif_sent: Option<Instant>,
+    pub historical_policy: HistoricalPolicy,
+    pub last_notif_sent: Option<DateTime<Utc>>,
 
     pub time_limit: NaiveTime,
+    pub timing: NotifTiming,
 }
 impl NotifCompressorUsedAfterHour {
     // "COMP_TIME >" compressorEstiverLigadoDepoisDe 'HH:mm'
@@ -135,75 +193,449 @@ impl NotifCompressorUsedAfterHour {
 
         Ok(Self {
             notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                HistoricalPolicy::Skip,
+            ),
             last_notif_sent: None,
             time_limit,
+            timing: NotifTiming::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                COMP_TIME_DEFAULT_TIMING,
+            ),
         })
     }
+}
 
-    pub fn update_new_notifs_parameters(
-        list_old: &HashMap<u64, Self>,
-        list_new: &mut HashMap<u64, Self>,
-    ) {
-        for (notif_id, updated_notif) in list_new.iter_mut() {
-            updated_notif.update_notif_parameters(list_old.get(&notif_id));
-        }
+impl NotifRule<DacTelemetry> for NotifCompressorUsedAfterHour {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
     }
 
-    pub fn update_notif_parameters(&mut self, existing: Option<&Self>) {
-        if let Some(existing) = existing {
-            self.last_notif_sent = existing.last_notif_sent;
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
 
-            // Se tiver alterado o horário da notificação, deixa enviar nova notificação hoje ainda
-            let changed_time = existing.time_limit != self.time_limit;
-            if changed_time {
-                self.last_notif_sent = None;
-            }
+    fn carry_over(&mut self, existing: &dyn NotifRule<DacTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.last_notif_sent = existing.last_notif_sent;
+
+        // Se tiver alterado o horário da notificação, deixa enviar nova notificação hoje ainda
+        let changed_time = existing.time_limit != self.time_limit;
+        if changed_time {
+            self.last_notif_sent = None;
         }
     }
 
-    pub async fn on_dac_telemetry(
-        &mut self,
-        telemetry_l1: i16,
-        telemetry_timestamp: &NaiveDateTime,
-        new_day: bool,
-        dev_id: &str,
-        globs: &Arc<GlobalVars>,
-    ) -> Result<(), String> {
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    // Só depende do horário da telemetria, a política histórica define se a detecção é enviada
+    fn evaluates_out_of_order(&self) -> bool {
+        true
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DacTelemetry>, sink: &mut DetectionSink) {
+        let Some(telemetry_l1) = sample.telemetry.Lcmp else {
+            return;
+        };
+        // Cada notificação pode ter o seu próprio CONTINUITY_SECONDS
+        let Some(StreamDelta {
+            descontinuidade,
+            new_day,
+            ..
+        }) = sample.delta("Lcmp", self.timing.continuity_secs)
+        else {
+            return;
+        };
+        let telemetry_timestamp = sample.timestamp();
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
         // Se o compressor não estiver ligado não tem nada para conferir
         if telemetry_l1 == 0 {
-            return Ok(());
+            return;
         }
 
         // Quando troca o dia, libera nova notificação
         if new_day && self.last_notif_sent.is_some() {
             self.last_notif_sent = None;
+            let detail = serde_json::json!({ "stream": "Lcmp" });
+            sink.push_reset(self.notif_id, "new_day", detail);
         }
 
-        // Se já tiver enviado uma notificação menos de 24 horas atrás, não precisa nem conferir
-        if let Some(last_notif_sent) = self.last_notif_sent.as_ref() {
-            if last_notif_sent.elapsed().as_secs() < 24 * 60 * 60 {
-                return Ok(());
-            }
+        // Se estiver dentro do intervalo mínimo entre notificações, não precisa nem conferir.
+        // A notificação enviada hoje indica que o compressor continua ligado fora do horário,
+        // a não ser que tenha faltado telemetria (CONTINUITY_SECONDS) desde então.
+        let persisting = self.last_notif_sent.is_some() && !descontinuidade;
+        let decision = self
+            .timing
+            .send_decision(self.last_notif_sent.as_ref(), persisting);
+        if decision == SendDecision::Blocked {
+            return;
         }
 
         if telemetry_timestamp.time() > self.time_limit {
-            self.last_notif_sent = Some(Instant::now());
-            let result = globs
-                .to_notifs_queue
-                .send((
-                    "/COMP_TIME/DepoisDoHorario",
-                    serde_json::json!({
-                        "dev_id": dev_id.to_owned(),
-                        "notif_id": self.notif_id,
-                        "time_limit": self.time_limit.format("%H:%M:%S").to_string(),
-                        "telemetry_timestamp": *telemetry_timestamp,
-                        "detection_time": Utc::now(),
-                    }),
-                ))
-                .await;
-            result.map_err(|err| crate::log_err("[216]", err)).ok();
-        }
-
-        return Ok(());
+            if !self.historical_policy.should_send(is_historical) {
+                // Telemetria histórica com política de não enviar detecções
+                return;
+            }
+            self.last_notif_sent = Some(sim_clock::now_utc());
+            sink.push(
+                "/COMP_TIME/DepoisDoHorario",
+                serde_json::json!({
+                    "dev_id": dev_id.to_owned(),
+                    "notif_id": self.notif_id,
+                    "time_limit": self.time_limit.format("%H:%M:%S").to_string(),
+                    "renotification": decision == SendDecision::Renotify,
+                    "telemetry_timestamp": *telemetry_timestamp,
+                    "detection_time": sim_clock::now_utc(),
+                    "late": is_historical,
+                }),
+            );
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotifCompressorContinuousRuntime {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    pub is_notified: bool, // Já notificou o ciclo de funcionamento atual
+    pub usage: CompressorUsage,
+
+    pub runtime_limit_secs: u64,
+}
+impl NotifCompressorContinuousRuntime {
+    // "COMP_DUR >" compressorFicarLigadoPorMaisDe X minutos
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+    ) -> Result<Self, String> {
+        // Pega o COND_PARS['RUNTIME_LIMIT_MINUTES']
+        let runtime_limit_minutes = updated_notif_data.cond_pars["RUNTIME_LIMIT_MINUTES"].as_u64();
+        let runtime_limit_minutes = match runtime_limit_minutes {
+            Some(x) if x > 0 => x,
+            _ => {
+                return Err("RUNTIME_LIMIT_MINUTES é obrigatório para a notificação".to_owned());
+            }
+        };
+
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
+            is_notified: false,
+            usage: CompressorUsage::default(),
+            runtime_limit_secs: runtime_limit_minutes * 60,
+        })
+    }
+}
+
+impl NotifRule<DacTelemetry> for NotifCompressorContinuousRuntime {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<DacTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.usage = existing.usage.clone();
+        // Se tiver alterado o limite, deixa avaliar o ciclo atual de novo
+        if existing.runtime_limit_secs == self.runtime_limit_secs {
+            self.is_notified = existing.is_notified;
+        }
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DacTelemetry>, sink: &mut DetectionSink) {
+        let Some(telemetry_l1) = sample.telemetry.Lcmp else {
+            return;
+        };
+        let telemetry_timestamp = sample.timestamp();
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        // O totalizador é atualizado mesmo se a política não avaliar a telemetria
+        let update = self.usage.update(telemetry_l1, telemetry_timestamp);
+        if update.reset_run_secs > 0 {
+            // Registra no histórico que o totalizador foi zerado
+            let detail =
+                serde_json::json!({ "stream": "Lcmp", "accumulated_secs": update.reset_run_secs });
+            sink.push_reset(self.notif_id, "descontinuidade", detail);
+        }
+        let run_secs = self.usage.run_secs;
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        // O compressor desligou (ou perdeu a continuidade), libera nova notificação
+        if run_secs == 0 {
+            self.is_notified = false;
+            return;
+        }
+
+        if self.is_notified || run_secs <= self.runtime_limit_secs {
+            return;
+        }
+
+        if !self.historical_policy.should_send(is_historical) {
+            // A detecção cairia no período histórico, então fica como notificada
+            // para não virar um alerta antigo na primeira telemetria ao vivo
+            self.is_notified = true;
+            return;
+        }
+        self.is_notified = true;
+        let run_start = *telemetry_timestamp - TimeDelta::seconds(run_secs as i64);
+        sink.push(
+            "/COMP_DUR/Acima",
+            serde_json::json!({
+                "dev_id": dev_id.to_owned(),
+                "notif_id": self.notif_id,
+                "runtime_limit_seconds": self.runtime_limit_secs,
+                "runtime_seconds": run_secs,
+                "run_start": run_start,
+                "telemetry_timestamp": *telemetry_timestamp,
+                "detection_time": sim_clock::now_utc(),
+                "late": is_historical,
+            }),
+        );
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotifCompressorDailyUsage {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    pub is_notified: bool, // Já notificou o dia atual
+    pub usage: CompressorUsage,
+
+    pub usage_limit_secs: u64,
+}
+impl NotifCompressorDailyUsage {
+    // "COMP_USE D>" compressorFicarLigadoNoDiaPorMaisDe Y horas
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+    ) -> Result<Self, String> {
+        // Pega o COND_PARS['USAGE_LIMIT_HOURS']
+        let usage_limit_hours = updated_notif_data.cond_pars["USAGE_LIMIT_HOURS"].as_f64();
+        let usage_limit_hours = match usage_limit_hours {
+            Some(x) if x > 0.0 && x <= 24.0 => x,
+            _ => {
+                return Err("USAGE_LIMIT_HOURS é obrigatório para a notificação".to_owned());
+            }
+        };
+
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
+            is_notified: false,
+            usage: CompressorUsage::default(),
+            usage_limit_secs: (usage_limit_hours * 3600.0).round() as u64,
+        })
+    }
+}
+
+impl NotifRule<DacTelemetry> for NotifCompressorDailyUsage {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<DacTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.usage = existing.usage.clone();
+        // Se tiver alterado o limite, deixa enviar nova notificação hoje ainda
+        if existing.usage_limit_secs == self.usage_limit_secs {
+            self.is_notified = existing.is_notified;
+        }
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DacTelemetry>, sink: &mut DetectionSink) {
+        let Some(telemetry_l1) = sample.telemetry.Lcmp else {
+            return;
+        };
+        let telemetry_timestamp = sample.timestamp();
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        // O totalizador é atualizado mesmo se a política não avaliar a telemetria
+        let update = self.usage.update(telemetry_l1, telemetry_timestamp);
+        let new_day = update.new_day;
+        if update.reset_day_usage_secs > 0 {
+            // Registra no histórico que o totalizador foi zerado
+            let detail = serde_json::json!({ "stream": "Lcmp", "accumulated_secs": update.reset_day_usage_secs });
+            sink.push_reset(self.notif_id, "new_day", detail);
+        }
+        let day_usage_secs = self.usage.day_usage_secs;
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        // Quando troca o dia, libera nova notificação
+        if new_day {
+            self.is_notified = false;
+        }
+
+        if self.is_notified || day_usage_secs <= self.usage_limit_secs {
+            return;
+        }
+
+        if !self.historical_policy.should_send(is_historical) {
+            // A detecção cairia no período histórico, então fica como notificada
+            // para não virar um alerta antigo na primeira telemetria ao vivo
+            self.is_notified = true;
+            return;
+        }
+        self.is_notified = true;
+        sink.push(
+            "/COMP_USE/AcimaDiario",
+            serde_json::json!({
+                "dev_id": dev_id.to_owned(),
+                "notif_id": self.notif_id,
+                "usage_limit_seconds": self.usage_limit_secs,
+                "usage_seconds": day_usage_secs,
+                "day": telemetry_timestamp.date(),
+                "telemetry_timestamp": *telemetry_timestamp,
+                "detection_time": sim_clock::now_utc(),
+                "late": is_historical,
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app_realtime::notifications::dac::dac_l1::test_helpers::l1_samples;
+    use crate::app_realtime::notifications::dac::notifs_dac::dac_rules_registry;
+    use crate::app_realtime::notifications::rules::test_helpers::*;
+
+    #[test]
+    fn daily_usage_notifies_once_per_day() {
+        // 0.01 hora = 36 segundos
+        let mut set = rule_set(
+            dac_rules_registry(),
+            "COMP_USE D>",
+            serde_json::json!({ "USAGE_LIMIT_HOURS": 0.01 }),
+            &None,
+        );
+
+        let detections = run(&mut set, l1_samples(ts("2026-01-01 10:00:00"), &[1; 8]));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/COMP_USE/AcimaDiario");
+        assert_eq!(detections[0].1["usage_seconds"], 40);
+        assert_eq!(
+            detections[0].1["telemetry_timestamp"],
+            serde_json::json!(ts("2026-01-01 10:00:40"))
+        );
+
+        // No dia seguinte o totalizador recomeça e libera nova notificação
+        let detections = run(&mut set, l1_samples(ts("2026-01-02 10:00:00"), &[1; 8]));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].1["day"], "2026-01-02");
+    }
+
+    #[test]
+    fn daily_usage_does_not_count_the_gap_without_telemetry() {
+        let mut set = rule_set(
+            dac_rules_registry(),
+            "COMP_USE D>",
+            serde_json::json!({ "USAGE_LIMIT_HOURS": 0.01 }),
+            &None,
+        );
+
+        let detections = run(&mut set, l1_samples(ts("2026-01-01 10:00:00"), &[1, 1]));
+        assert!(detections.is_empty());
+
+        // As duas horas sem telemetria não entram no uso do dia
+        let detections = run(&mut set, l1_samples(ts("2026-01-01 12:00:00"), &[1; 4]));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].1["usage_seconds"], 40);
+        assert_eq!(
+            detections[0].1["telemetry_timestamp"],
+            serde_json::json!(ts("2026-01-01 12:00:30"))
+        );
+    }
+
+    #[test]
+    fn continuous_runtime_notifies_each_cycle_once() {
+        let mut set = rule_set(
+            dac_rules_registry(),
+            "COMP_DUR >",
+            serde_json::json!({ "RUNTIME_LIMIT_MINUTES": 1 }),
+            &None,
+        );
+
+        let detections = run(&mut set, l1_samples(ts("2026-01-01 10:00:00"), &[1; 10]));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/COMP_DUR/Acima");
+        assert_eq!(detections[0].1["runtime_seconds"], 70);
+        assert_eq!(
+            detections[0].1["run_start"],
+            serde_json::json!(ts("2026-01-01 10:00:00"))
+        );
+
+        // O compressor desliga e liga de novo: o novo ciclo precisa passar do limite outra vez
+        let mut samples = vec![0];
+        samples.extend([1; 7]);
+        let detections = run(&mut set, l1_samples(ts("2026-01-01 10:01:40"), &samples));
+        assert!(detections.is_empty());
+        let detections = run(&mut set, l1_samples(ts("2026-01-01 10:03:00"), &[1]));
+        assert_eq!(detections.len(), 1);
+    }
+
+    #[test]
+    fn used_before_hour_notifies_once_a_day() {
+        let mut set = rule_set(
+            dac_rules_registry(),
+            "COMP_TIME <",
+            serde_json::json!({ "TIME_LIMIT": "08:00" }),
+            &None,
+        );
+
+        let detections = run(&mut set, l1_samples(ts("2026-01-01 07:00:00"), &[1; 6]));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/COMP_TIME/AntesDoHorario");
+
+        // Depois do horário não tem o que notificar
+        let detections = run(&mut set, l1_samples(ts("2026-01-01 09:00:00"), &[1; 6]));
+        assert!(detections.is_empty());
+
+        // A troca de dia libera nova notificação mesmo dentro do COOLDOWN_MINUTES
+        let detections = run(&mut set, l1_samples(ts("2026-01-02 06:00:00"), &[1; 6]));
+        assert_eq!(detections.len(), 1);
     }
 }
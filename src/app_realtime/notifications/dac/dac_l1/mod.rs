@@ -1,53 +1,133 @@
 use super::super::get_telemetry_delta;
-use super::notifs_dac::NotifsDac;
-use super::on_dac_telemetry::DacTelemetry;
-use crate::global_vars::GlobalVars;
 use chrono::NaiveDateTime;
-use std::sync::Arc;
+use serde::Serialize;
 
+pub use dac_compressor_short_cycling::NotifCompressorShortCycling;
+pub use dac_compressor_usage_hours::{
+    NotifCompressorContinuousRuntime, NotifCompressorDailyUsage, NotifCompressorUsedAfterHour,
+    NotifCompressorUsedBeforeHour,
+};
+
+pub mod dac_compressor_short_cycling;
 pub mod dac_compressor_usage_hours;
 
-#[derive(Debug)]
-pub struct LastDacL1 {
-    pub timestamp: NaiveDateTime,
+// Os totalizadores de uso do compressor são do dispositivo, então usam a continuidade padrão
+pub const L1_CONTINUITY_SECS: u64 = 30;
+
+/// Totalizadores de uso do compressor, calculados a partir da sequência das telemetrias do L1
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct CompressorUsage {
+    pub timestamp: Option<NaiveDateTime>,
+    pub lcmp: i16,
+    pub run_secs: u64,       // Tempo que o compressor está ligado sem interrupção
+    pub day_usage_secs: u64, // Tempo acumulado de compressor ligado no dia da telemetria
+}
+
+/// Resultado da atualização dos totalizadores por uma telemetria
+#[derive(Debug, Default)]
+pub struct CompressorUsageUpdate {
+    pub new_day: bool,
+    // Valores dos totalizadores que foram zerados
+    pub reset_run_secs: u64,
+    pub reset_day_usage_secs: u64,
+}
+
+impl CompressorUsage {
+    /// Atualiza os totalizadores com uma telemetria mais recente que a última processada
+    pub fn update(&mut self, lcmp: i16, timestamp: &NaiveDateTime) -> CompressorUsageUpdate {
+        let (delta_secs, descontinuidade, new_day) =
+            get_telemetry_delta(timestamp, self.timestamp.as_ref(), L1_CONTINUITY_SECS);
+        let mut result = CompressorUsageUpdate {
+            new_day,
+            ..Default::default()
+        };
+
+        if new_day {
+            result.reset_day_usage_secs = self.day_usage_secs;
+            self.day_usage_secs = 0;
+        }
+        // O intervalo desde a última telemetria conta como uso se o compressor estava ligado.
+        // Sem continuidade não dá para saber se ficou ligado no intervalo sem telemetria.
+        let was_on = self.timestamp.is_some() && self.lcmp != 0;
+        if was_on && !descontinuidade {
+            self.day_usage_secs += delta_secs;
+        }
+        if lcmp == 0 {
+            self.run_secs = 0;
+        } else if was_on && !descontinuidade {
+            self.run_secs += delta_secs;
+        } else {
+            result.reset_run_secs = self.run_secs;
+            self.run_secs = 0;
+        }
+
+        self.timestamp = Some(*timestamp);
+        self.lcmp = lcmp;
+        result
+    }
 }
 
-pub async fn on_dac_telemetry(
-    telemetry: &DacTelemetry,
-    dev_alerts: &mut NotifsDac,
-    dev_id: &str,
-    globs: &Arc<GlobalVars>,
-) {
-    // Todas as verificações aqui são baseadas no L1, se não tiver pode interromper
-    let Some(telemetry_l1) = telemetry.Lcmp else {
-        return;
-    };
-    let telemetry_timestamp = &telemetry.timestamp;
-
-    let prev_value_timestamp = dev_alerts.last_l1.as_ref().map(|x| &x.timestamp);
-    let (delta_secs, descontinuidade, new_day) =
-        get_telemetry_delta(telemetry_timestamp, prev_value_timestamp, 30);
-
-    // Atualiza o dev_alerts.last_l1
-    if let Some(last_l1) = dev_alerts.last_l1.as_mut() {
-        last_l1.timestamp = telemetry_timestamp.to_owned();
-    } else {
-        dev_alerts.last_l1 = Some(LastDacL1 {
-            timestamp: telemetry_timestamp.to_owned(),
-        });
+/// Funções usadas nos testes das regras do L1
+#[cfg(test)]
+pub mod test_helpers {
+    use crate::app_realtime::notifications::dac::on_dac_telemetry::DacTelemetry;
+    use chrono::{NaiveDateTime, TimeDelta};
+
+    /// Uma leitura do L1 a cada 10 s a partir do horário informado
+    pub fn l1_samples(start: NaiveDateTime, lcmp: &[i16]) -> Vec<DacTelemetry> {
+        lcmp.iter()
+            .enumerate()
+            .map(|(i, value)| DacTelemetry {
+                timestamp: start + TimeDelta::seconds(10 * i as i64),
+                Lcmp: Some(*value),
+                Tamb: None,
+                Tsuc: None,
+                Tliq: None,
+                Psuc: None,
+                Pliq: None,
+                Tsc: None,
+                Tsh: None,
+                Curr: None,
+            })
+            .collect()
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_realtime::notifications::rules::test_helpers::ts;
+
+    #[test]
+    fn day_usage_skips_the_gap_without_telemetry() {
+        let mut usage = CompressorUsage::default();
+        usage.update(1, &ts("2026-01-01 10:00:00"));
+        usage.update(1, &ts("2026-01-01 10:00:10"));
+        assert_eq!(usage.day_usage_secs, 10);
+        assert_eq!(usage.run_secs, 10);
+
+        // Duas horas sem telemetria: o intervalo não conta como uso e o ciclo recomeça
+        let update = usage.update(1, &ts("2026-01-01 12:00:10"));
+        assert_eq!(update.reset_run_secs, 10);
+        assert_eq!(usage.day_usage_secs, 10);
+        assert_eq!(usage.run_secs, 0);
 
-    for (_, row) in dev_alerts.notif_compressor_used_before_time.iter_mut() {
-        row.on_dac_telemetry(telemetry_l1, telemetry_timestamp, new_day, dev_id, globs)
-            .await
-            .map_err(|err| crate::log_err("[130]", err))
-            .ok();
+        usage.update(0, &ts("2026-01-01 12:00:20"));
+        usage.update(0, &ts("2026-01-01 12:00:30"));
+        assert_eq!(usage.day_usage_secs, 20);
+        assert_eq!(usage.run_secs, 0);
     }
 
-    for (_, row) in dev_alerts.notif_compressor_used_after_time.iter_mut() {
-        row.on_dac_telemetry(telemetry_l1, telemetry_timestamp, new_day, dev_id, globs)
-            .await
-            .map_err(|err| crate::log_err("[130]", err))
-            .ok();
+    #[test]
+    fn day_usage_restarts_on_the_new_day() {
+        let mut usage = CompressorUsage::default();
+        usage.update(1, &ts("2026-01-01 23:59:40"));
+        usage.update(1, &ts("2026-01-01 23:59:50"));
+        let update = usage.update(1, &ts("2026-01-02 00:00:00"));
+        assert!(update.new_day);
+        assert_eq!(update.reset_day_usage_secs, 10);
+        // O intervalo que atravessa a meia-noite conta no dia novo, e o ciclo continua
+        assert_eq!(usage.day_usage_secs, 10);
+        assert_eq!(usage.run_secs, 20);
     }
 }
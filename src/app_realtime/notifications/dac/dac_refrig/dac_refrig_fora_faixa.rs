@@ -0,0 +1,301 @@
+use crate::app_realtime::notifications::dac::on_dac_telemetry::DacTelemetry;
+use crate::app_realtime::notifications::episode::{PeakKind, ViolationEpisode};
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::NotifsCfgResponse_notif_item;
+use crate::app_realtime::notifications::rules::{
+    DetectionSink, NotifRule, RuleSample, StreamDelta,
+};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+/// Grandeza do ciclo de refrigeração monitorada pela notificação
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum DacRefrigVar {
+    Tsh,  // Superaquecimento
+    Tsc,  // Subresfriamento
+    Psuc, // Pressão de sucção
+    Pliq, // Pressão de líquido
+    Tamb, // Temperatura ambiente (externa)
+}
+
+impl DacRefrigVar {
+    fn from_cond_var(cond_var: &str) -> Option<Self> {
+        match cond_var {
+            "DAC_TSH" => Some(DacRefrigVar::Tsh),
+            "DAC_TSC" => Some(DacRefrigVar::Tsc),
+            "DAC_PSUC" => Some(DacRefrigVar::Psuc),
+            "DAC_PLIQ" => Some(DacRefrigVar::Pliq),
+            "DAC_TAMB" => Some(DacRefrigVar::Tamb),
+            _ => None,
+        }
+    }
+
+    /// Nome do campo na telemetria do DAC
+    pub fn field(&self) -> &'static str {
+        match self {
+            DacRefrigVar::Tsh => "Tsh",
+            DacRefrigVar::Tsc => "Tsc",
+            DacRefrigVar::Psuc => "Psuc",
+            DacRefrigVar::Pliq => "Pliq",
+            DacRefrigVar::Tamb => "Tamb",
+        }
+    }
+
+    fn detection_path(&self) -> &'static str {
+        match self {
+            DacRefrigVar::Tsh => "/DAC_TSH/ForaDaFaixa",
+            DacRefrigVar::Tsc => "/DAC_TSC/ForaDaFaixa",
+            DacRefrigVar::Psuc => "/DAC_PSUC/ForaDaFaixa",
+            DacRefrigVar::Pliq => "/DAC_PLIQ/ForaDaFaixa",
+            DacRefrigVar::Tamb => "/DAC_TAMB/ForaDaFaixa",
+        }
+    }
+
+    fn normalized_path(&self) -> &'static str {
+        match self {
+            DacRefrigVar::Tsh => "/DAC_TSH/ForaDaFaixa/Normalizado",
+            DacRefrigVar::Tsc => "/DAC_TSC/ForaDaFaixa/Normalizado",
+            DacRefrigVar::Psuc => "/DAC_PSUC/ForaDaFaixa/Normalizado",
+            DacRefrigVar::Pliq => "/DAC_PLIQ/ForaDaFaixa/Normalizado",
+            DacRefrigVar::Tamb => "/DAC_TAMB/ForaDaFaixa/Normalizado",
+        }
+    }
+}
+
+// Padrão: 5 minutos fora da faixa, uma notificação por episódio
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 5 * 60,
+    cooldown_secs: 0,
+    continuity_secs: 30,
+    renotify_secs: None,
+};
+
+#[derive(Debug, Serialize)]
+pub struct NotifDacRefrigOutOfBand {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    pub last_notif_sent: Option<DateTime<Utc>>,
+    pub acc_t: u64,
+    pub is_notified: bool, // Já notificou o episódio atual
+    pub episode: Option<ViolationEpisode>,
+
+    pub variable: DacRefrigVar,
+    pub limit_min: Option<f64>,
+    pub limit_max: Option<f64>,
+    pub timing: NotifTiming,
+    pub only_compressor_on: bool,
+}
+impl NotifDacRefrigOutOfBand {
+    // "DAC_TSH <>", "DAC_TSC <>", "DAC_PSUC <", "DAC_PLIQ >", "DAC_TAMB >", ...
+    // O operador define quais limites são obrigatórios: "<" usa LIMIT_MIN, ">" usa LIMIT_MAX e "<>" usa os dois
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+    ) -> Result<Self, String> {
+        let Some((cond_var, cond_op)) = updated_notif_data.cond_id.split_once(' ') else {
+            return Err(format!(
+                "[136] COND_ID inválido: '{}'",
+                updated_notif_data.cond_id
+            ));
+        };
+        let Some(variable) = DacRefrigVar::from_cond_var(cond_var) else {
+            return Err(format!(
+                "[136] COND_ID inválido: '{}'",
+                updated_notif_data.cond_id
+            ));
+        };
+
+        let cond_pars = &updated_notif_data.cond_pars;
+        let (limit_min, limit_max) = match cond_op {
+            "<" => (cond_pars["LIMIT_MIN"].as_f64(), None),
+            ">" => (None, cond_pars["LIMIT_MAX"].as_f64()),
+            "<>" => (
+                cond_pars["LIMIT_MIN"].as_f64(),
+                cond_pars["LIMIT_MAX"].as_f64(),
+            ),
+            _ => {
+                return Err(format!(
+                    "[137] COND_ID inválido: '{}'",
+                    updated_notif_data.cond_id
+                ));
+            }
+        };
+        if (cond_op != ">" && limit_min.is_none()) || (cond_op != "<" && limit_max.is_none()) {
+            return Err("LIMIT_MIN/LIMIT_MAX é obrigatório para a notificação".to_owned());
+        }
+        if let (Some(min), Some(max)) = (limit_min, limit_max) {
+            if min > max {
+                return Err("LIMIT_MIN não pode ser maior que LIMIT_MAX".to_owned());
+            }
+        }
+
+        // Superaquecimento, subresfriamento e pressões só fazem sentido com o compressor ligado
+        let only_compressor_on = cond_pars["ONLY_COMPRESSOR_ON"]
+            .as_bool()
+            .unwrap_or(variable != DacRefrigVar::Tamb);
+
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
+            last_notif_sent: None,
+            acc_t: 0,
+            is_notified: false,
+            episode: None,
+            variable,
+            limit_min,
+            limit_max,
+            timing: NotifTiming::from_cond_pars(cond_pars, DEFAULT_TIMING),
+            only_compressor_on,
+        })
+    }
+}
+
+impl NotifRule<DacTelemetry> for NotifDacRefrigOutOfBand {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<DacTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        // Se tiver alterado a grandeza monitorada, o estado anterior não serve mais
+        if existing.variable != self.variable {
+            return;
+        }
+        self.last_notif_sent = existing.last_notif_sent;
+        self.acc_t = existing.acc_t;
+        self.is_notified = existing.is_notified;
+        self.episode = existing.episode.clone();
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DacTelemetry>, sink: &mut DetectionSink) {
+        let telemetry = sample.telemetry;
+        // Cada notificação pode ter o seu próprio CONTINUITY_SECONDS
+        let Some(StreamDelta {
+            delta_secs,
+            descontinuidade,
+            ..
+        }) = sample.delta(self.variable.field(), self.timing.continuity_secs)
+        else {
+            // Sem leitura da grandeza nesta amostra
+            return;
+        };
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        if descontinuidade && self.acc_t > 0 {
+            // Registra no histórico que o acumulador da notificação vai ser zerado
+            let detail = serde_json::json!({ "stream": self.variable.field(), "accumulated_secs": self.acc_t });
+            sink.push_reset(self.notif_id, "descontinuidade", detail);
+        }
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        let Some(value) = sample.value(self.variable.field()) else {
+            return;
+        };
+
+        // Com o compressor desligado (ou sem informação) a condição não é avaliada
+        let compressor_on = telemetry.Lcmp.is_some_and(|x| x != 0);
+        let can_evaluate = compressor_on || !self.only_compressor_on;
+
+        let below = self.limit_min.is_some_and(|min| value < min);
+        let above = self.limit_max.is_some_and(|max| value > max);
+        let violating = can_evaluate && (below || above);
+        let peak_kind = if below { PeakKind::Min } else { PeakKind::Max };
+
+        // Quando a grandeza volta para a faixa, informa o fim do episódio que foi notificado
+        let ended_episode = ViolationEpisode::track(
+            &mut self.episode,
+            violating,
+            &telemetry.timestamp,
+            value,
+            peak_kind,
+            self.historical_policy.should_send(is_historical),
+        );
+        if let Some(ended_episode) = ended_episode {
+            sink.push(
+                self.variable.normalized_path(),
+                ended_episode.normalized_detection(
+                    dev_id,
+                    self.notif_id,
+                    &telemetry.timestamp,
+                    is_historical,
+                ),
+            );
+        }
+
+        // A condição precisa durar sem interrupção
+        if !violating || descontinuidade {
+            self.acc_t = 0;
+            if !violating {
+                self.is_notified = false;
+            }
+            return;
+        }
+
+        // O episódio notificado só é enviado de novo no reenvio do RENOTIFY_HOURS
+        let decision = self
+            .timing
+            .send_decision(self.last_notif_sent.as_ref(), self.is_notified);
+        let renotify = decision == SendDecision::Renotify;
+        if self.is_notified && !renotify {
+            return;
+        }
+
+        if !renotify && self.acc_t < self.timing.duration_secs {
+            self.acc_t += delta_secs;
+            return;
+        }
+
+        // Dentro do intervalo mínimo entre notificações (COOLDOWN_MINUTES)
+        if decision == SendDecision::Blocked {
+            return;
+        }
+
+        if !self.historical_policy.should_send(is_historical) {
+            // A detecção cairia no período histórico, então fica como notificada
+            // para não virar um alerta antigo na primeira telemetria ao vivo
+            self.is_notified = true;
+            return;
+        }
+        self.is_notified = true;
+        self.last_notif_sent = Some(sim_clock::now_utc());
+        ViolationEpisode::mark_notified(&mut self.episode);
+        sink.push(
+            self.variable.detection_path(),
+            serde_json::json!({
+                "dev_id": dev_id.to_owned(),
+                "notif_id": self.notif_id,
+                "value": value,
+                "limit_min": self.limit_min,
+                "limit_max": self.limit_max,
+                "duration_in_seconds": self.timing.duration_secs,
+                "renotification": renotify,
+                "compressor_on": compressor_on,
+                "telemetry_timestamp": telemetry.timestamp,
+                "detection_time": sim_clock::now_utc(),
+                "episode": ViolationEpisode::context(&self.episode),
+                "late": is_historical,
+            }),
+        );
+    }
+}
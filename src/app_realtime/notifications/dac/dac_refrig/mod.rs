@@ -0,0 +1,3 @@
+pub use dac_refrig_fora_faixa::NotifDacRefrigOutOfBand;
+
+pub mod dac_refrig_fora_faixa;
@@ -1,4 +1,6 @@
+pub mod dac_curr;
 pub mod dac_l1;
+pub mod dac_refrig;
 pub mod notifs_dac;
 pub mod on_dac_telemetry;
 
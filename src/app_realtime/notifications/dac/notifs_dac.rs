@@ -1,136 +1,140 @@
-use super::dac_l1::dac_compressor_usage_hours::{
-    NotifCompressorUsedAfterHour, NotifCompressorUsedBeforeHour,
+use super::dac_curr::NotifCompressorCurrent;
+use super::dac_l1::{
+    CompressorUsage, NotifCompressorContinuousRuntime, NotifCompressorDailyUsage,
+    NotifCompressorShortCycling, NotifCompressorUsedAfterHour, NotifCompressorUsedBeforeHour,
 };
-use super::dac_l1::LastDacL1;
+use super::dac_refrig::NotifDacRefrigOutOfBand;
+use super::on_dac_telemetry::DacTelemetry;
 use crate::app_realtime::global_vars::DevInfo;
-use crate::app_realtime::notifications::notifs_cfg::NotifsCfgResponse_notif_item;
+use crate::app_realtime::notifications::coverage::NotifRejections;
+use crate::app_realtime::notifications::expression::NotifExpression;
+use crate::app_realtime::notifications::notifs_cfg::{
+    DutAutomationConfig, NotifsCfgResponse_notif_item,
+};
+use crate::app_realtime::notifications::rules::{parse_rules_list, RuleRegistry, RuleSet};
+use serde::Serialize;
 use std::sync::atomic::Ordering;
-use std::{collections::HashMap, sync::Arc};
-
-#[derive(Debug)]
-pub struct NotifsDac {
-    pub notif_compressor_used_before_time: HashMap<u64, NotifCompressorUsedBeforeHour>,
-    pub notif_compressor_used_after_time: HashMap<u64, NotifCompressorUsedAfterHour>,
-    pub last_l1: Option<LastDacL1>,
-}
-impl NotifsDac {
-    pub fn remove_notif_id(&mut self, notif_id: u64) {
-        self.notif_compressor_used_before_time.remove(&notif_id);
-        self.notif_compressor_used_after_time.remove(&notif_id);
-    }
-}
+use std::sync::Arc;
+use std::sync::OnceLock;
 
-fn parse_dac_notifs_list(
-    updated_dev_notifs: &Vec<Arc<NotifsCfgResponse_notif_item>>,
-) -> Option<(
-    HashMap<u64, NotifCompressorUsedBeforeHour>,
-    HashMap<u64, NotifCompressorUsedAfterHour>,
-)> {
-    let mut notif_compressor_used_before_time = HashMap::new();
-    let mut notif_compressor_used_after_time = HashMap::new();
+// Ciclo de refrigeração: o operador define quais limites são obrigatórios
+const DAC_REFRIG_COND_IDS: &[&str] = &[
+    "DAC_TSH <",
+    "DAC_TSH >",
+    "DAC_TSH <>",
+    "DAC_TSC <",
+    "DAC_TSC >",
+    "DAC_TSC <>",
+    "DAC_PSUC <",
+    "DAC_PSUC >",
+    "DAC_PSUC <>",
+    "DAC_PLIQ <",
+    "DAC_PLIQ >",
+    "DAC_PLIQ <>",
+    "DAC_TAMB <",
+    "DAC_TAMB >",
+    "DAC_TAMB <>",
+];
 
-    for notif in updated_dev_notifs.iter() {
-        match notif.cond_id.as_str() {
-            "COMP_TIME <" => {
-                match NotifCompressorUsedBeforeHour::from_notif_cfg(notif) {
-                    Ok(notif) => {
-                        notif_compressor_used_before_time.insert(notif.notif_id, notif);
-                    }
-                    Err(_err) => {} // Ignora silenciosamente
-                };
-            }
-            "COMP_TIME >" => {
-                match NotifCompressorUsedAfterHour::from_notif_cfg(notif) {
-                    Ok(notif) => {
-                        notif_compressor_used_after_time.insert(notif.notif_id, notif);
-                    }
-                    Err(_err) => {} // Ignora silenciosamente
-                };
-            }
-            _ => {
-                // Ignore
-            }
+/// Regras de notificação do DAC (ver notifications/rules.rs)
+pub fn dac_rules_registry() -> &'static RuleRegistry<DacTelemetry> {
+    static REGISTRY: OnceLock<RuleRegistry<DacTelemetry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut registry = RuleRegistry::new();
+        registry.register("COMP_TIME <", |notif, _| {
+            Ok(Box::new(NotifCompressorUsedBeforeHour::from_notif_cfg(
+                notif,
+            )?))
+        });
+        registry.register("COMP_TIME >", |notif, _| {
+            Ok(Box::new(NotifCompressorUsedAfterHour::from_notif_cfg(
+                notif,
+            )?))
+        });
+        registry.register("COMP_CYCLES >", |notif, _| {
+            Ok(Box::new(NotifCompressorShortCycling::from_notif_cfg(
+                notif,
+            )?))
+        });
+        registry.register("COMP_DUR >", |notif, _| {
+            Ok(Box::new(NotifCompressorContinuousRuntime::from_notif_cfg(
+                notif,
+            )?))
+        });
+        registry.register("COMP_USE D>", |notif, _| {
+            Ok(Box::new(NotifCompressorDailyUsage::from_notif_cfg(notif)?))
+        });
+        registry.register("COMP_CURR >", |notif, _| {
+            Ok(Box::new(NotifCompressorCurrent::from_notif_cfg(notif)?))
+        });
+        for cond_id in DAC_REFRIG_COND_IDS {
+            registry.register(cond_id, |notif, _| {
+                Ok(Box::new(NotifDacRefrigOutOfBand::from_notif_cfg(notif)?))
+            });
         }
-    }
+        registry.register("DAC_EXPR", |notif, automation_cfg| {
+            let rule = NotifExpression::from_notif_cfg::<DacTelemetry>(
+                notif,
+                automation_cfg,
+                "/DAC_EXPR/Detectado",
+            )?;
+            Ok(Box::new(rule))
+        });
+        registry
+    })
+}
 
-    let is_empty =
-        notif_compressor_used_before_time.is_empty() && notif_compressor_used_after_time.is_empty();
+pub fn is_dac_cond_id(cond_id: &str) -> bool {
+    dac_rules_registry().contains(cond_id)
+}
 
-    if is_empty {
-        return None;
+#[derive(Debug, Serialize)]
+pub struct NotifsDac {
+    // Totalizadores do dispositivo, consultados pelo "getDacCompressorUsage"
+    pub compressor_usage: CompressorUsage,
+    pub rules: RuleSet<DacTelemetry>,
+}
+impl NotifsDac {
+    pub fn remove_notif_id(&mut self, notif_id: u64) {
+        self.rules.remove_notif_id(notif_id);
     }
-
-    Some((
-        notif_compressor_used_before_time,
-        notif_compressor_used_after_time,
-    ))
 }
 
 pub async fn update_notifs_dac(
     dev_info: &mut DevInfo,
     updated_dev_notifs: &Option<&Vec<Arc<NotifsCfgResponse_notif_item>>>,
+    updated_dev_sched: Option<Arc<DutAutomationConfig>>,
     // O parâmetro "partial_update" indica se a lista "updated_dev_notifs" é completa ou parcial.
     // Se for parcial, vamos atualizar as notificações que estiverem na lista sem mexer nas outras.
     // Se for completa, vamos remover as notificações que não estiverem na lista.
     partial_update: bool,
+    // Notificações recusadas pelo "from_notif_cfg" (ex.: falta TUSEMAX na programação)
+    rejected: &mut NotifRejections,
 ) {
-    let Some(updated_dev_notifs) = updated_dev_notifs else {
-        if !partial_update {
-            // Já que não tem nenhuma notificação monitorando o dispositivo, limpa a lista
-            dev_info.has_notifs_dac.store(false, Ordering::Relaxed);
-            *dev_info.notifs_dac.write().await = None;
-        }
-        return;
-    };
-
     // Interpreta a resposta do API-Server (faz parse do JSON)
-    let Some((mut notif_compressor_used_before_time, mut notif_compressor_used_after_time)) =
-        parse_dac_notifs_list(updated_dev_notifs)
-    else {
+    let rules = updated_dev_notifs
+        .map(|notifs| parse_rules_list(dac_rules_registry(), notifs, &updated_dev_sched, rejected))
+        .unwrap_or_default();
+
+    if rules.is_empty() {
         if !partial_update {
             // Já que não tem nenhuma notificação monitorando o dispositivo, limpa a lista
             dev_info.has_notifs_dac.store(false, Ordering::Relaxed);
             *dev_info.notifs_dac.write().await = None;
         }
         return;
-    };
+    }
 
     // Pega o "dev_info.notifs_dac" em modo "write" para atualizar
     let mut notifs_dac = dev_info.notifs_dac.write().await;
 
     if let Some(existente) = notifs_dac.as_mut() {
-        NotifCompressorUsedBeforeHour::update_new_notifs_parameters(
-            &existente.notif_compressor_used_before_time,
-            &mut notif_compressor_used_before_time,
-        );
-
-        NotifCompressorUsedAfterHour::update_new_notifs_parameters(
-            &existente.notif_compressor_used_after_time,
-            &mut notif_compressor_used_after_time,
-        );
-
-        if partial_update {
-            for (notif_id, notif) in notif_compressor_used_before_time.into_iter() {
-                existente
-                    .notif_compressor_used_before_time
-                    .insert(notif_id, notif);
-            }
-            for (notif_id, notif) in notif_compressor_used_after_time.into_iter() {
-                existente
-                    .notif_compressor_used_after_time
-                    .insert(notif_id, notif);
-            }
-        } else {
-            existente.notif_compressor_used_before_time = notif_compressor_used_before_time;
-            existente.notif_compressor_used_after_time = notif_compressor_used_after_time;
-        }
+        existente.rules.update_rules(rules, partial_update);
     } else {
-        let notifs_dac_new = NotifsDac {
-            notif_compressor_used_before_time,
-            notif_compressor_used_after_time,
-            last_l1: None,
-        };
-        *notifs_dac = Some(notifs_dac_new);
+        *notifs_dac = Some(NotifsDac {
+            compressor_usage: CompressorUsage::default(),
+            rules: RuleSet::new(rules),
+        });
     };
 
     dev_info.has_notifs_dac.store(true, Ordering::Relaxed);
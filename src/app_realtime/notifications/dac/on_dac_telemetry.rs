@@ -1,9 +1,9 @@
-use super::dac_l1;
 use super::notifs_dac::NotifsDac;
+use crate::app_realtime::notifications::rules::RuleTelemetry;
 use crate::global_vars::GlobalVars;
 use crate::helpers::telemetry_payloads::{
-    parse_json_props::get_i16_array_optional,
-    telemetry_formats::{get_json_sampling_time, get_json_timestamp_with_gmt},
+    parse_json_props::{get_float_array_optional, get_i16_array_optional},
+    telemetry_formats::{get_json_sampling_time, get_json_saved_data, get_json_timestamp_with_gmt},
 };
 use chrono::{NaiveDateTime, TimeDelta};
 use std::sync::Arc;
@@ -12,11 +12,13 @@ pub async fn on_dac_telemetry(
     payload_json: &serde_json::Value,
     dev_alerts: &mut NotifsDac,
     dev_id: &str,
+    ts_correction: Option<TimeDelta>,
     globs: &Arc<GlobalVars>,
 ) {
-    // Se a telemetria for histórica acho que posso ignorar.
+    // Telemetria histórica (enviada depois pelo gateway): cada notificação decide como tratar
+    let is_historical = get_json_saved_data(payload_json);
 
-    let telemetry_list = match DacTelemetry::parse_from_json_as_vec(payload_json) {
+    let mut telemetry_list = match DacTelemetry::parse_from_json_as_vec(payload_json) {
         Err(err) => {
             crate::write_to_log_file_v2(
                 "ERROR",
@@ -28,15 +30,45 @@ pub async fn on_dac_telemetry(
         Ok(x) => x,
     };
 
+    // Dispositivo com desvio de relógio: usa o horário corrigido pelo servidor
+    if let Some(ts_correction) = ts_correction {
+        for telemetry in telemetry_list.iter_mut() {
+            telemetry.timestamp -= ts_correction;
+        }
+    }
+
     for telemetry in telemetry_list {
-        // Notificações sobre o L1
-        dac_l1::on_dac_telemetry(&telemetry, dev_alerts, dev_id, globs).await;
+        // Totalizadores de uso do compressor. A telemetria fora de ordem não altera os totalizadores.
+        if let Some(telemetry_l1) = telemetry.Lcmp {
+            let usage = &mut dev_alerts.compressor_usage;
+            if usage
+                .timestamp
+                .is_none_or(|last| telemetry.timestamp > last)
+            {
+                usage.update(telemetry_l1, &telemetry.timestamp);
+            }
+        }
+
+        // Regras registradas no "dac_rules_registry"
+        dev_alerts
+            .rules
+            .on_telemetry(&telemetry, dev_id, is_historical, globs)
+            .await;
     }
 }
 
+#[derive(Debug)]
 pub struct DacTelemetry {
     pub timestamp: NaiveDateTime,
     pub Lcmp: Option<i16>,
+    pub Tamb: Option<f64>,
+    pub Tsuc: Option<f64>,
+    pub Tliq: Option<f64>,
+    pub Psuc: Option<f64>,
+    pub Pliq: Option<f64>,
+    pub Tsc: Option<f64>,
+    pub Tsh: Option<f64>,
+    pub Curr: Option<f64>,
 }
 
 impl DacTelemetry {
@@ -45,6 +77,14 @@ impl DacTelemetry {
         let sampling_time = get_json_sampling_time(&payload_json).unwrap_or(1);
 
         let Lcmp = get_i16_array_optional(&payload_json["Lcmp"]);
+        let Tamb = get_float_array_optional(&payload_json["Tamb"]);
+        let Tsuc = get_float_array_optional(&payload_json["Tsuc"]);
+        let Tliq = get_float_array_optional(&payload_json["Tliq"]);
+        let Psuc = get_float_array_optional(&payload_json["Psuc"]);
+        let Pliq = get_float_array_optional(&payload_json["Pliq"]);
+        let Tsc = get_float_array_optional(&payload_json["Tsc"]);
+        let Tsh = get_float_array_optional(&payload_json["Tsh"]);
+        let Curr = get_float_array_optional(&payload_json["Curr"]);
 
         let vec_len = Lcmp.as_ref().and_then(|v| Some(v.len())).unwrap_or(0);
 
@@ -58,6 +98,14 @@ impl DacTelemetry {
 
             let telemetry = DacTelemetry {
                 Lcmp: Lcmp.as_ref().and_then(|x| x.get(i).and_then(|x| *x)),
+                Tamb: Tamb.as_ref().and_then(|x| x.get(i).and_then(|x| *x)),
+                Tsuc: Tsuc.as_ref().and_then(|x| x.get(i).and_then(|x| *x)),
+                Tliq: Tliq.as_ref().and_then(|x| x.get(i).and_then(|x| *x)),
+                Psuc: Psuc.as_ref().and_then(|x| x.get(i).and_then(|x| *x)),
+                Pliq: Pliq.as_ref().and_then(|x| x.get(i).and_then(|x| *x)),
+                Tsc: Tsc.as_ref().and_then(|x| x.get(i).and_then(|x| *x)),
+                Tsh: Tsh.as_ref().and_then(|x| x.get(i).and_then(|x| *x)),
+                Curr: Curr.as_ref().and_then(|x| x.get(i).and_then(|x| *x)),
                 timestamp: telemetry_timestamp,
             };
             vec.push(telemetry);
@@ -65,3 +113,34 @@ impl DacTelemetry {
         Ok(vec)
     }
 }
+
+impl RuleTelemetry for DacTelemetry {
+    fn timestamp(&self) -> &NaiveDateTime {
+        &self.timestamp
+    }
+
+    fn get_field(&self, name: &str) -> Option<f64> {
+        match name {
+            "Lcmp" => self.Lcmp.map(|x| x as f64),
+            "Tamb" => self.Tamb,
+            "Tsuc" => self.Tsuc,
+            "Tliq" => self.Tliq,
+            "Psuc" => self.Psuc,
+            "Pliq" => self.Pliq,
+            "Tsc" => self.Tsc,
+            "Tsh" => self.Tsh,
+            "Curr" => self.Curr,
+            _ => None,
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &[
+            "Lcmp", "Tamb", "Tsuc", "Tliq", "Psuc", "Pliq", "Tsc", "Tsh", "Curr",
+        ]
+    }
+
+    fn continuity_secs(_field: &str) -> u64 {
+        30
+    }
+}
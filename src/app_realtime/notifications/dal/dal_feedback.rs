@@ -0,0 +1,134 @@
+use super::on_dal_telemetry::DalTelemetry;
+use crate::app_realtime::notifications::episode::SustainedCondition;
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::NotifsCfgResponse_notif_item;
+use crate::app_realtime::notifications::rules::{DetectionSink, NotifRule, RuleSample};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+// Padrão: 60 segundos com o feedback divergente, uma notificação por ocorrência
+// (o tempo é configurado pelo MISMATCH_SECONDS no lugar do DURATION_IN_MINUTES, e o CONTINUITY_SECONDS não é usado)
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 60,
+    cooldown_secs: 0,
+    continuity_secs: 0,
+    renotify_secs: None,
+};
+
+#[derive(Debug, Serialize)]
+pub struct NotifDalFeedbackMismatch {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    // Uma condição por canal do DAL
+    pub channels: Vec<SustainedCondition>,
+
+    pub timing: NotifTiming,
+}
+impl NotifDalFeedbackMismatch {
+    // "DAL_FEEDBACK !=" feedbackDiferenteDoComandoDoRele por mais de X segundos
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+    ) -> Result<Self, String> {
+        let mut timing = NotifTiming::from_cond_pars(&updated_notif_data.cond_pars, DEFAULT_TIMING);
+        // MISMATCH_SECONDS é o tempo em segundos com o feedback divergente do relé para disparar o alerta
+        if let Some(mismatch_seconds) = updated_notif_data.cond_pars["MISMATCH_SECONDS"].as_u64() {
+            timing.duration_secs = mismatch_seconds;
+        }
+
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
+            channels: Vec::new(),
+            timing,
+        })
+    }
+}
+
+impl NotifRule<DalTelemetry> for NotifDalFeedbackMismatch {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<DalTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.channels = existing.channels.clone();
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DalTelemetry>, sink: &mut DetectionSink) {
+        let (Some(telemetry_relays), Some(telemetry_feedback)) = (
+            sample.telemetry.Relays.as_deref(),
+            sample.telemetry.Feedback.as_deref(),
+        ) else {
+            return;
+        };
+        let telemetry_timestamp = sample.timestamp();
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        let channels_count = telemetry_relays.len().min(telemetry_feedback.len());
+        if self.channels.len() != channels_count {
+            self.channels
+                .resize(channels_count, SustainedCondition::default());
+        }
+
+        for (channel, condition) in self.channels.iter_mut().enumerate() {
+            // Canal sem leitura de relé ou de feedback nesta amostra mantém o estado anterior
+            let (Some(relay), Some(feedback)) =
+                (telemetry_relays[channel], telemetry_feedback[channel])
+            else {
+                continue;
+            };
+            let Some((elapsed_secs, decision)) =
+                condition.update(relay != feedback, telemetry_timestamp, &self.timing)
+            else {
+                continue;
+            };
+
+            if !self.historical_policy.should_send(is_historical) {
+                // A detecção cairia no período histórico, então fica como notificada
+                // para não virar um alerta antigo na primeira telemetria ao vivo
+                condition.is_notified = true;
+                continue;
+            }
+            condition.mark_notified();
+            sink.push(
+                "/DAL/FeedbackDivergente",
+                serde_json::json!({
+                    "dev_id": dev_id.to_owned(),
+                    "notif_id": self.notif_id,
+                    "channel": channel,
+                    "relay": relay,
+                    "feedback": feedback,
+                    "mismatch_since": condition.since,
+                    "elapsed_seconds": elapsed_secs,
+                    "mismatch_seconds": self.timing.duration_secs,
+                    "renotification": decision == SendDecision::Renotify,
+                    "telemetry_timestamp": *telemetry_timestamp,
+                    "detection_time": sim_clock::now_utc(),
+                    "late": is_historical,
+                }),
+            );
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use super::on_dal_telemetry::DalTelemetry;
+use crate::app_realtime::notifications::dam::dam_modo::is_auto_mode;
+use crate::app_realtime::notifications::episode::SustainedCondition;
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::NotifsCfgResponse_notif_item;
+use crate::app_realtime::notifications::rules::{DetectionSink, NotifRule, RuleSample};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+// Padrão: 30 minutos fora do modo automático, uma notificação por ocorrência
+// (o CONTINUITY_SECONDS não é usado, a condição é medida pelo timestamp das telemetrias)
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 30 * 60,
+    cooldown_secs: 0,
+    continuity_secs: 0,
+    renotify_secs: None,
+};
+
+#[derive(Debug, Serialize)]
+pub struct NotifDalManualMode {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    // Uma condição por canal do DAL
+    pub channels: Vec<SustainedCondition>,
+
+    pub timing: NotifTiming,
+}
+impl NotifDalManualMode {
+    // "DAL_MODE !AUTO" canalForaDoModoAutomatico por mais de X minutos
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
+            channels: Vec::new(),
+            timing: NotifTiming::from_cond_pars(&updated_notif_data.cond_pars, DEFAULT_TIMING),
+        })
+    }
+}
+
+impl NotifRule<DalTelemetry> for NotifDalManualMode {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<DalTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.channels = existing.channels.clone();
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DalTelemetry>, sink: &mut DetectionSink) {
+        let Some(telemetry_mode) = sample.telemetry.Mode.as_deref() else {
+            return;
+        };
+        let telemetry_timestamp = sample.timestamp();
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        if self.channels.len() != telemetry_mode.len() {
+            self.channels
+                .resize(telemetry_mode.len(), SustainedCondition::default());
+        }
+
+        for (channel, (condition, mode)) in self.channels.iter_mut().zip(telemetry_mode).enumerate()
+        {
+            // Canal sem informação de modo nesta amostra mantém o estado anterior
+            let Some(mode) = mode.as_deref() else {
+                continue;
+            };
+            let Some((elapsed_secs, decision)) =
+                condition.update(!is_auto_mode(mode), telemetry_timestamp, &self.timing)
+            else {
+                continue;
+            };
+
+            if !self.historical_policy.should_send(is_historical) {
+                // A detecção cairia no período histórico, então fica como notificada
+                // para não virar um alerta antigo na primeira telemetria ao vivo
+                condition.is_notified = true;
+                continue;
+            }
+            condition.mark_notified();
+            sink.push(
+                "/DAL/ModoManual",
+                serde_json::json!({
+                    "dev_id": dev_id.to_owned(),
+                    "notif_id": self.notif_id,
+                    "channel": channel,
+                    "mode": mode,
+                    "manual_since": condition.since,
+                    "elapsed_seconds": elapsed_secs,
+                    "duration_in_seconds": self.timing.duration_secs,
+                    "renotification": decision == SendDecision::Renotify,
+                    "telemetry_timestamp": *telemetry_timestamp,
+                    "detection_time": sim_clock::now_utc(),
+                    "late": is_historical,
+                }),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app_realtime::notifications::dal::notifs_dal::dal_rules_registry;
+    use crate::app_realtime::notifications::dal::on_dal_telemetry::DalTelemetry;
+    use crate::app_realtime::notifications::rules::test_helpers::*;
+
+    fn mode_sample(timestamp: &str, modes: &[Option<&str>]) -> DalTelemetry {
+        DalTelemetry {
+            timestamp: ts(timestamp),
+            State: None,
+            Mode: Some(modes.iter().map(|x| x.map(|x| x.to_owned())).collect()),
+            Relays: None,
+            Feedback: None,
+        }
+    }
+
+    #[test]
+    fn channels_are_tracked_independently() {
+        let mut set = rule_set(
+            dal_rules_registry(),
+            "DAL_MODE !AUTO",
+            serde_json::json!({ "DURATION_IN_MINUTES": 10 }),
+            &None,
+        );
+        let detections = run(
+            &mut set,
+            [
+                mode_sample("2026-01-01 10:00:00", &[Some("Manual"), Some("Auto")]),
+                mode_sample("2026-01-01 10:05:00", &[Some("Manual"), Some("Manual")]),
+                // Canal sem modo informado mantém o estado anterior
+                mode_sample("2026-01-01 10:10:00", &[Some("Manual"), None]),
+            ],
+        );
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DAL/ModoManual");
+        assert_eq!(detections[0].1["channel"], 0);
+
+        let detections = run(
+            &mut set,
+            [mode_sample(
+                "2026-01-01 10:15:00",
+                &[Some("Manual"), Some("Manual")],
+            )],
+        );
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].1["channel"], 1);
+        assert_eq!(detections[0].1["manual_since"], "2026-01-01T10:05:00");
+    }
+}
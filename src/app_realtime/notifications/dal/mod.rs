@@ -0,0 +1,7 @@
+pub mod dal_feedback;
+pub mod dal_modo;
+pub mod notifs_dal;
+pub mod on_dal_telemetry;
+
+pub use notifs_dal::NotifsDal;
+pub use on_dal_telemetry::on_dal_telemetry;
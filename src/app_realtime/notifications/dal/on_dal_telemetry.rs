@@ -0,0 +1,95 @@
+use super::notifs_dal::NotifsDal;
+use crate::app_realtime::notifications::dam::dam_estado::AutomStateTelemetry;
+use crate::app_realtime::notifications::rules::RuleTelemetry;
+use crate::global_vars::GlobalVars;
+use crate::helpers::telemetry_payloads::{
+    parse_json_props::get_bool_array_optional,
+    telemetry_formats::{get_json_saved_data, get_json_timestamp_with_gmt},
+};
+use chrono::{NaiveDateTime, TimeDelta};
+use std::sync::Arc;
+
+pub async fn on_dal_telemetry(
+    payload_json: &serde_json::Value,
+    dev_alerts: &mut NotifsDal,
+    dev_id: &str,
+    ts_correction: Option<TimeDelta>,
+    globs: &Arc<GlobalVars>,
+) {
+    // Telemetria histórica (enviada depois pelo gateway): cada notificação decide como tratar
+    let is_historical = get_json_saved_data(payload_json);
+
+    let mut telemetry = match DalTelemetry::parse_from_json(payload_json) {
+        Err(err) => {
+            crate::write_to_log_file_v2("ERROR", &format!("[109] {err} {}", payload_json), false);
+            return;
+        }
+        Ok(x) => x,
+    };
+
+    // Dispositivo com desvio de relógio: usa o horário corrigido pelo servidor
+    if let Some(ts_correction) = ts_correction {
+        telemetry.timestamp -= ts_correction;
+    }
+
+    // Os valores mais antigos que o último processado não são avaliados, corromperiam a sequência de estados
+    dev_alerts
+        .rules
+        .on_telemetry(&telemetry, dev_id, is_historical, globs)
+        .await;
+}
+
+#[derive(Debug)]
+pub struct DalTelemetry {
+    pub timestamp: NaiveDateTime,
+    pub State: Option<String>,
+    // Um valor por canal do DAL
+    pub Mode: Option<Vec<Option<String>>>,
+    pub Relays: Option<Vec<Option<bool>>>,
+    pub Feedback: Option<Vec<Option<bool>>>,
+}
+
+impl DalTelemetry {
+    pub fn parse_from_json(payload_json: &serde_json::Value) -> Result<Self, String> {
+        let (payload_timestamp, _gmt) = get_json_timestamp_with_gmt(payload_json)?;
+
+        let mode = payload_json["Mode"].as_array().map(|list| {
+            list.iter()
+                .map(|x| x.as_str().map(|x| x.to_owned()))
+                .collect()
+        });
+
+        Ok(DalTelemetry {
+            timestamp: payload_timestamp,
+            State: payload_json["State"].as_str().map(|x| x.to_owned()),
+            Mode: mode,
+            Relays: get_bool_array_optional(&payload_json["Relays"]),
+            Feedback: get_bool_array_optional(&payload_json["Feedback"]),
+        })
+    }
+}
+
+impl RuleTelemetry for DalTelemetry {
+    fn timestamp(&self) -> &NaiveDateTime {
+        &self.timestamp
+    }
+
+    // O DAL não tem campos numéricos, as regras usam os valores de cada canal
+    fn get_field(&self, _name: &str) -> Option<f64> {
+        None
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &[]
+    }
+
+    fn continuity_secs(_field: &str) -> u64 {
+        30
+    }
+}
+
+impl AutomStateTelemetry for DalTelemetry {
+    fn state(&self) -> Option<&str> {
+        self.State.as_deref()
+    }
+}
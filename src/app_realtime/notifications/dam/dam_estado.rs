@@ -0,0 +1,206 @@
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::{
+    AutomationSchedule, DutAutomationConfig, NotifsCfgResponse_notif_item,
+};
+use crate::app_realtime::notifications::rules::{
+    DetectionSink, NotifRule, RuleSample, RuleTelemetry,
+};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+/// Telemetria de dispositivo de automação que informa o estado (DAM e DAL)
+pub trait AutomStateTelemetry: RuleTelemetry {
+    fn state(&self) -> Option<&str>;
+}
+
+// Padrão: no máximo uma notificação por hora, para não notificar cada troca de estado.
+// A troca de estado é um evento, então não tem DURATION_IN_MINUTES, CONTINUITY_SECONDS nem RENOTIFY_HOURS.
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 0,
+    cooldown_secs: 60 * 60,
+    continuity_secs: 0,
+    renotify_secs: None,
+};
+
+/// Troca de estado de um dispositivo de automação (DAM ou DAL) fora do horário de funcionamento da unidade
+#[derive(Debug, Serialize)]
+pub struct NotifAutomStateOutsideSchedule {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    pub last_notif_sent: Option<DateTime<Utc>>,
+    pub last_state: Option<String>,
+
+    pub notif_path: &'static str,
+    pub timing: NotifTiming,
+    #[serde(skip)]
+    pub schedule: Option<Arc<AutomationSchedule>>,
+}
+impl NotifAutomStateOutsideSchedule {
+    // "DAM_STATE !SCHED" / "DAL_STATE !SCHED" estadoAlteradoForaDaProgramacao
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+        automation_cfg: &Option<Arc<DutAutomationConfig>>,
+        notif_path: &'static str,
+    ) -> Result<Self, String> {
+        let Some(automation_cfg) = automation_cfg.as_ref() else {
+            return Err("A notificação exige que o dispositivo tenha programação".to_owned());
+        };
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                HistoricalPolicy::Late,
+            ),
+            last_notif_sent: None,
+            last_state: None,
+            notif_path,
+            timing: NotifTiming::from_cond_pars(&updated_notif_data.cond_pars, DEFAULT_TIMING),
+            schedule: Some(automation_cfg.schedule.clone()),
+        })
+    }
+}
+
+impl<T: AutomStateTelemetry + 'static> NotifRule<T> for NotifAutomStateOutsideSchedule {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<T>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.last_notif_sent = existing.last_notif_sent;
+        self.last_state = existing.last_state.clone();
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<T>, sink: &mut DetectionSink) {
+        let Some(telemetry_state) = sample.telemetry.state() else {
+            return;
+        };
+        let telemetry_timestamp = sample.timestamp();
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        // A sequência de estados é atualizada mesmo quando a telemetria não é avaliada
+        let prev_state = self.last_state.replace(telemetry_state.to_owned());
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        // Só interessa quando o estado muda
+        let Some(prev_state) = prev_state.as_deref() else {
+            return;
+        };
+        if prev_state == telemetry_state {
+            return;
+        }
+
+        // Dia sem programação definida também é considerado fora do horário de funcionamento
+        let Some(schedule) = self.schedule.as_ref() else {
+            return;
+        };
+        let inside_schedule = schedule
+            .get_for(&telemetry_timestamp.date())
+            .is_some_and(|prog| prog.is_inside_sched(telemetry_timestamp.time()));
+        if inside_schedule {
+            return;
+        }
+
+        // Dentro do intervalo mínimo entre notificações (COOLDOWN_MINUTES)
+        if self
+            .timing
+            .send_decision(self.last_notif_sent.as_ref(), false)
+            == SendDecision::Blocked
+        {
+            return;
+        }
+
+        if !self.historical_policy.should_send(is_historical) {
+            // Telemetria histórica com política de não enviar detecções
+            return;
+        }
+        self.last_notif_sent = Some(sim_clock::now_utc());
+        sink.push(
+            self.notif_path,
+            serde_json::json!({
+                "dev_id": dev_id.to_owned(),
+                "notif_id": self.notif_id,
+                "prev_state": prev_state,
+                "state": telemetry_state,
+                "telemetry_timestamp": *telemetry_timestamp,
+                "detection_time": sim_clock::now_utc(),
+                "late": is_historical,
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app_realtime::notifications::dam::notifs_dam::dam_rules_registry;
+    use crate::app_realtime::notifications::dam::on_dam_telemetry::DamTelemetry;
+    use crate::app_realtime::notifications::rules::test_helpers::*;
+    use crate::app_realtime::sim_clock;
+
+    fn state_sample(timestamp: &str, state: &str) -> DamTelemetry {
+        DamTelemetry {
+            timestamp: ts(timestamp),
+            State: Some(state.to_owned()),
+            Mode: None,
+        }
+    }
+
+    #[test]
+    fn cooldown_uses_the_server_clock() {
+        let mut set = rule_set(
+            dam_rules_registry(),
+            "DAM_STATE !SCHED",
+            serde_json::json!({}),
+            &automation_cfg(None, None, Some(("08:00", "18:00"))),
+        );
+        sim_clock::set_simulated_now(1_767_261_600_000);
+
+        // Dentro do horário de funcionamento a troca de estado não é notificada
+        let detections = run(
+            &mut set,
+            [
+                state_sample("2026-01-01 10:00:00", "Enabled"),
+                state_sample("2026-01-01 10:05:00", "Disabled"),
+            ],
+        );
+        assert!(detections.is_empty());
+
+        // As telemetrias estão horas separadas, mas chegaram dentro do COOLDOWN_MINUTES do servidor
+        let detections = run(
+            &mut set,
+            [
+                state_sample("2026-01-01 19:00:00", "Enabled"),
+                state_sample("2026-01-01 21:00:00", "Disabled"),
+                state_sample("2026-01-01 23:00:00", "Enabled"),
+            ],
+        );
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DAM/EstadoForaDaProgramacao");
+        assert_eq!(detections[0].1["prev_state"], "Disabled");
+        assert_eq!(detections[0].1["state"], "Enabled");
+
+        sim_clock::set_simulated_now(1_767_261_600_000 + 61 * 60 * 1000);
+        let detections = run(&mut set, [state_sample("2026-01-01 23:01:00", "Disabled")]);
+        assert_eq!(detections.len(), 1);
+        sim_clock::set_simulated_now(0);
+    }
+}
@@ -0,0 +1,194 @@
+use super::on_dam_telemetry::DamTelemetry;
+use crate::app_realtime::notifications::episode::SustainedCondition;
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::NotifsCfgResponse_notif_item;
+use crate::app_realtime::notifications::rules::{DetectionSink, NotifRule, RuleSample};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+/// Indica se o modo informado pelo dispositivo é o automático ("Auto", "AUTO", ...)
+pub fn is_auto_mode(mode: &str) -> bool {
+    mode.eq_ignore_ascii_case("auto")
+}
+
+// Padrão: 30 minutos fora do modo automático, uma notificação por ocorrência
+// (o CONTINUITY_SECONDS não é usado, a condição é medida pelo timestamp das telemetrias)
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 30 * 60,
+    cooldown_secs: 0,
+    continuity_secs: 0,
+    renotify_secs: None,
+};
+
+#[derive(Debug, Serialize)]
+pub struct NotifDamManualMode {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    pub condition: SustainedCondition,
+
+    pub timing: NotifTiming,
+}
+impl NotifDamManualMode {
+    // "DAM_MODE !AUTO" automacaoForaDoModoAutomatico por mais de X minutos
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
+            condition: SustainedCondition::default(),
+            timing: NotifTiming::from_cond_pars(&updated_notif_data.cond_pars, DEFAULT_TIMING),
+        })
+    }
+}
+
+impl NotifRule<DamTelemetry> for NotifDamManualMode {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<DamTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.condition = existing.condition.clone();
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DamTelemetry>, sink: &mut DetectionSink) {
+        let Some(telemetry_mode) = sample.telemetry.Mode.as_deref() else {
+            return;
+        };
+        let telemetry_timestamp = sample.timestamp();
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        let is_manual = !is_auto_mode(telemetry_mode);
+        let Some((elapsed_secs, decision)) =
+            self.condition
+                .update(is_manual, telemetry_timestamp, &self.timing)
+        else {
+            return;
+        };
+
+        if !self.historical_policy.should_send(is_historical) {
+            // A detecção cairia no período histórico, então fica como notificada
+            // para não virar um alerta antigo na primeira telemetria ao vivo
+            self.condition.is_notified = true;
+            return;
+        }
+        self.condition.mark_notified();
+        sink.push(
+            "/DAM/ModoManual",
+            serde_json::json!({
+                "dev_id": dev_id.to_owned(),
+                "notif_id": self.notif_id,
+                "mode": telemetry_mode,
+                "manual_since": self.condition.since,
+                "elapsed_seconds": elapsed_secs,
+                "duration_in_seconds": self.timing.duration_secs,
+                "renotification": decision == SendDecision::Renotify,
+                "telemetry_timestamp": *telemetry_timestamp,
+                "detection_time": sim_clock::now_utc(),
+                "late": is_historical,
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app_realtime::notifications::dam::notifs_dam::dam_rules_registry;
+    use crate::app_realtime::notifications::dam::on_dam_telemetry::DamTelemetry;
+    use crate::app_realtime::notifications::rules::test_helpers::*;
+
+    fn mode_sample(timestamp: &str, mode: &str) -> DamTelemetry {
+        DamTelemetry {
+            timestamp: ts(timestamp),
+            State: None,
+            Mode: Some(mode.to_owned()),
+        }
+    }
+
+    #[test]
+    fn manual_mode_is_notified_once_per_occurrence() {
+        let mut set = rule_set(
+            dam_rules_registry(),
+            "DAM_MODE !AUTO",
+            serde_json::json!({}),
+            &None,
+        );
+        let detections = run(
+            &mut set,
+            [
+                mode_sample("2026-01-01 10:00:00", "Auto"),
+                mode_sample("2026-01-01 10:01:00", "Manual"),
+                mode_sample("2026-01-01 10:30:00", "Manual"),
+            ],
+        );
+        assert!(detections.is_empty());
+
+        // Padrão de 30 minutos contados pelo timestamp das telemetrias, sem CONTINUITY_SECONDS
+        let detections = run(
+            &mut set,
+            [
+                mode_sample("2026-01-01 10:31:00", "Manual"),
+                mode_sample("2026-01-01 11:30:00", "Manual"),
+            ],
+        );
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DAM/ModoManual");
+        assert_eq!(detections[0].1["elapsed_seconds"], 30 * 60);
+        assert_eq!(detections[0].1["manual_since"], "2026-01-01T10:01:00");
+
+        // A volta para o automático ("AUTO" em maiúsculas) encerra a ocorrência
+        let detections = run(
+            &mut set,
+            [
+                mode_sample("2026-01-01 11:31:00", "AUTO"),
+                mode_sample("2026-01-01 11:32:00", "Manual"),
+                mode_sample("2026-01-01 12:02:00", "Manual"),
+            ],
+        );
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].1["manual_since"], "2026-01-01T11:32:00");
+    }
+
+    #[test]
+    fn out_of_order_sample_does_not_restart_the_condition() {
+        let mut set = rule_set(
+            dam_rules_registry(),
+            "DAM_MODE !AUTO",
+            serde_json::json!({ "DURATION_IN_MINUTES": 10 }),
+            &None,
+        );
+        let detections = run(
+            &mut set,
+            [
+                mode_sample("2026-01-01 10:00:00", "Manual"),
+                mode_sample("2026-01-01 09:55:00", "Auto"),
+                mode_sample("2026-01-01 10:10:00", "Manual"),
+            ],
+        );
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].1["manual_since"], "2026-01-01T10:00:00");
+    }
+}
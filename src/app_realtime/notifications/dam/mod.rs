@@ -0,0 +1,7 @@
+pub mod dam_estado;
+pub mod dam_modo;
+pub mod notifs_dam;
+pub mod on_dam_telemetry;
+
+pub use notifs_dam::NotifsDam;
+pub use on_dam_telemetry::on_dam_telemetry;
@@ -0,0 +1,82 @@
+use super::dam_estado::AutomStateTelemetry;
+use super::notifs_dam::NotifsDam;
+use crate::app_realtime::notifications::rules::RuleTelemetry;
+use crate::global_vars::GlobalVars;
+use crate::helpers::telemetry_payloads::telemetry_formats::{
+    get_json_saved_data, get_json_timestamp_with_gmt,
+};
+use chrono::{NaiveDateTime, TimeDelta};
+use std::sync::Arc;
+
+pub async fn on_dam_telemetry(
+    payload_json: &serde_json::Value,
+    dev_alerts: &mut NotifsDam,
+    dev_id: &str,
+    ts_correction: Option<TimeDelta>,
+    globs: &Arc<GlobalVars>,
+) {
+    // Telemetria histórica (enviada depois pelo gateway): cada notificação decide como tratar
+    let is_historical = get_json_saved_data(payload_json);
+
+    let mut telemetry = match DamTelemetry::parse_from_json(payload_json) {
+        Err(err) => {
+            crate::write_to_log_file_v2("ERROR", &format!("[108] {err} {}", payload_json), false);
+            return;
+        }
+        Ok(x) => x,
+    };
+
+    // Dispositivo com desvio de relógio: usa o horário corrigido pelo servidor
+    if let Some(ts_correction) = ts_correction {
+        telemetry.timestamp -= ts_correction;
+    }
+
+    dev_alerts
+        .rules
+        .on_telemetry(&telemetry, dev_id, is_historical, globs)
+        .await;
+}
+
+#[derive(Debug)]
+pub struct DamTelemetry {
+    pub timestamp: NaiveDateTime,
+    pub State: Option<String>,
+    pub Mode: Option<String>,
+}
+
+impl DamTelemetry {
+    pub fn parse_from_json(payload_json: &serde_json::Value) -> Result<Self, String> {
+        let (payload_timestamp, _gmt) = get_json_timestamp_with_gmt(payload_json)?;
+
+        Ok(DamTelemetry {
+            timestamp: payload_timestamp,
+            State: payload_json["State"].as_str().map(|x| x.to_owned()),
+            Mode: payload_json["Mode"].as_str().map(|x| x.to_owned()),
+        })
+    }
+}
+
+impl RuleTelemetry for DamTelemetry {
+    fn timestamp(&self) -> &NaiveDateTime {
+        &self.timestamp
+    }
+
+    // O DAM não tem campos numéricos, as regras usam o modo e o estado
+    fn get_field(&self, _name: &str) -> Option<f64> {
+        None
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &[]
+    }
+
+    fn continuity_secs(_field: &str) -> u64 {
+        30
+    }
+}
+
+impl AutomStateTelemetry for DamTelemetry {
+    fn state(&self) -> Option<&str> {
+        self.State.as_deref()
+    }
+}
@@ -0,0 +1,234 @@
+use super::get_pulses_to_liters;
+use crate::app_realtime::notifications::dma::on_dma_telemetry::DmaTelemetry;
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::NotifsCfgResponse_notif_item;
+use crate::app_realtime::notifications::rules::{DetectionSink, NotifRule, RuleSample};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+// Padrão: uma notificação por dia (o DURATION_IN_MINUTES e o CONTINUITY_SECONDS não são usados)
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 0,
+    cooldown_secs: 0,
+    continuity_secs: 0,
+    renotify_secs: None,
+};
+
+#[derive(Debug, Serialize)]
+pub struct NotifDmaDailyUsage {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    pub last_notif_sent: Option<DateTime<Utc>>,
+    pub timing: NotifTiming,
+    pub is_notified: bool, // Já notificou o dia atual
+
+    pub day: Option<NaiveDate>,
+    pub day_pulses: u64, // Pulsos acumulados no dia
+
+    pub daily_limit_liters: f64,
+    pub pulses_to_liters: f64,
+}
+impl NotifDmaDailyUsage {
+    // "DMA_USE D>" consumoDiarioAcimaDe X litros
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+    ) -> Result<Self, String> {
+        let cond_pars = &updated_notif_data.cond_pars;
+        // Pega o COND_PARS['DAILY_LIMIT_LITERS']
+        let daily_limit_liters = match cond_pars["DAILY_LIMIT_LITERS"].as_f64() {
+            Some(x) if x > 0.0 => x,
+            _ => {
+                return Err("DAILY_LIMIT_LITERS é obrigatório para a notificação".to_owned());
+            }
+        };
+
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(cond_pars, HistoricalPolicy::Late),
+            last_notif_sent: None,
+            timing: NotifTiming::from_cond_pars(cond_pars, DEFAULT_TIMING),
+            is_notified: false,
+            day: None,
+            day_pulses: 0,
+            daily_limit_liters,
+            pulses_to_liters: get_pulses_to_liters(cond_pars),
+        })
+    }
+
+    /// Soma os pulsos no total do dia. Retorna false se a telemetria não entrar no total.
+    fn add_day_pulses(
+        &mut self,
+        pulses: u64,
+        sample: &RuleSample<DmaTelemetry>,
+        sink: &mut DetectionSink,
+    ) -> bool {
+        let date = sample.timestamp().date();
+        // Os pulsos de uma telemetria fora de ordem só entram no total se forem do mesmo dia
+        if sample.out_of_order {
+            if self.day != Some(date) {
+                return false;
+            }
+            self.day_pulses += pulses;
+            return true;
+        }
+
+        // Os pulsos são do intervalo de amostragem, então somam no total do dia mesmo com descontinuidade
+        if self.day != Some(date) {
+            if self.day_pulses > 0 {
+                // Registra no histórico o total do dia zerado
+                let detail =
+                    serde_json::json!({ "stream": "pulses", "day_pulses": self.day_pulses });
+                sink.push_reset(self.notif_id, "new_day", detail);
+            }
+            self.day = Some(date);
+            self.day_pulses = 0;
+            // Quando troca o dia, libera nova notificação
+            self.is_notified = false;
+        }
+        self.day_pulses += pulses;
+        true
+    }
+}
+
+impl NotifRule<DmaTelemetry> for NotifDmaDailyUsage {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<DmaTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.last_notif_sent = existing.last_notif_sent;
+        self.day = existing.day;
+        self.day_pulses = existing.day_pulses;
+        // Se tiver alterado o limite, deixa enviar nova notificação hoje ainda
+        if existing.daily_limit_liters == self.daily_limit_liters {
+            self.is_notified = existing.is_notified;
+        }
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    // Os pulsos de uma telemetria atrasada do mesmo dia entram no total
+    fn evaluates_out_of_order(&self) -> bool {
+        true
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DmaTelemetry>, sink: &mut DetectionSink) {
+        let Some(telemetry_pulses) = sample.telemetry.pulses else {
+            return;
+        };
+        let telemetry_timestamp = sample.timestamp();
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        // O total do dia é atualizado antes da política histórica para não perder os pulsos
+        if !self.add_day_pulses(telemetry_pulses, sample, sink) {
+            return;
+        }
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        let day_liters = (self.day_pulses as f64) * self.pulses_to_liters;
+        if day_liters <= self.daily_limit_liters {
+            return;
+        }
+
+        // O dia notificado só é enviado de novo no reenvio do RENOTIFY_HOURS
+        let decision = self
+            .timing
+            .send_decision(self.last_notif_sent.as_ref(), self.is_notified);
+        if decision == SendDecision::Blocked || (self.is_notified && decision == SendDecision::Send)
+        {
+            return;
+        }
+
+        if !self.historical_policy.should_send(is_historical) {
+            // A detecção cairia no período histórico, então fica como notificada
+            // para não virar um alerta antigo na primeira telemetria ao vivo
+            self.is_notified = true;
+            return;
+        }
+        self.is_notified = true;
+        self.last_notif_sent = Some(sim_clock::now_utc());
+        sink.push(
+            "/DMA/ConsumoDiario",
+            serde_json::json!({
+                "dev_id": dev_id.to_owned(),
+                "notif_id": self.notif_id,
+                "daily_limit_liters": self.daily_limit_liters,
+                "day_liters": day_liters,
+                "renotification": decision == SendDecision::Renotify,
+                "day": telemetry_timestamp.date(),
+                "telemetry_timestamp": *telemetry_timestamp,
+                "detection_time": sim_clock::now_utc(),
+                "late": is_historical,
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app_realtime::notifications::dma::notifs_dma::dma_rules_registry;
+    use crate::app_realtime::notifications::dma::on_dma_telemetry::DmaTelemetry;
+    use crate::app_realtime::notifications::rules::test_helpers::*;
+
+    fn pulses_sample(timestamp: &str, pulses: u64) -> DmaTelemetry {
+        DmaTelemetry {
+            timestamp: ts(timestamp),
+            pulses: Some(pulses),
+            sampling_time: 60,
+        }
+    }
+
+    #[test]
+    fn out_of_order_pulses_only_count_on_the_same_day() {
+        let mut set = rule_set(
+            dma_rules_registry(),
+            "DMA_USE D>",
+            serde_json::json!({ "DAILY_LIMIT_LITERS": 100 }),
+            &None,
+        );
+
+        let detections = run(
+            &mut set,
+            [
+                pulses_sample("2026-01-02 10:00:00", 40),
+                pulses_sample("2026-01-02 10:01:00", 40),
+            ],
+        );
+        assert!(detections.is_empty());
+
+        // Telemetria atrasada do dia anterior: não entra no total de hoje
+        let detections = run(&mut set, [pulses_sample("2026-01-01 23:59:00", 50)]);
+        assert!(detections.is_empty());
+        assert_eq!(set.rules[&1].state_json()["day_pulses"], 80);
+
+        // Telemetria atrasada do mesmo dia: entra no total e a detecção é marcada como atrasada
+        let detections = run(&mut set, [pulses_sample("2026-01-02 09:59:00", 30)]);
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DMA/ConsumoDiario");
+        assert_eq!(detections[0].1["day_liters"], 110.0);
+        assert_eq!(detections[0].1["late"], true);
+
+        // Já notificado hoje
+        let detections = run(&mut set, [pulses_sample("2026-01-02 10:02:00", 40)]);
+        assert!(detections.is_empty());
+        assert_eq!(set.rules[&1].state_json()["day_pulses"], 150);
+    }
+}
@@ -0,0 +1,331 @@
+use super::get_pulses_to_liters;
+use crate::app_realtime::notifications::dma::on_dma_telemetry::DmaTelemetry;
+use crate::app_realtime::notifications::episode::{PeakKind, ViolationEpisode};
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::NotifsCfgResponse_notif_item;
+use crate::app_realtime::notifications::rules::{
+    DetectionSink, NotifRule, RuleSample, StreamDelta,
+};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, NaiveTime, TimeDelta, Timelike, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+// Padrão: 60 minutos de vazão contínua, uma notificação por episódio.
+// CONTINUITY_SECONDS 0 usa a continuidade calculada pelo intervalo de amostragem do DMA.
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 60 * 60,
+    cooldown_secs: 0,
+    continuity_secs: 0,
+    renotify_secs: None,
+};
+
+#[derive(Debug, Serialize)]
+pub struct NotifDmaLeak {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    pub last_notif_sent: Option<DateTime<Utc>>,
+    pub acc_t: u64,
+    pub is_notified: bool, // Já notificou o episódio atual
+    pub episode: Option<ViolationEpisode>,
+
+    pub window_start: NaiveTime,
+    pub window_end: NaiveTime,
+    pub timing: NotifTiming,
+    pub pulses_to_liters: f64,
+}
+impl NotifDmaLeak {
+    // "DMA_LEAK >" vazãoContinuaNoPeriodo 'HH:mm' - 'HH:mm' (período em que o consumo deveria ser zero)
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+    ) -> Result<Self, String> {
+        let cond_pars = &updated_notif_data.cond_pars;
+
+        // Pega o COND_PARS['WINDOW_START'] e o COND_PARS['WINDOW_END']
+        let parse_time = |name: &str| -> Result<NaiveTime, String> {
+            let Some(time) = cond_pars[name].as_str() else {
+                return Err(format!("{name} é obrigatório para a notificação"));
+            };
+            NaiveTime::parse_from_str(time, "%H:%M")
+                .map_err(|err| format!("[134] {name} é inválido: '{time}' {err}"))
+        };
+        let window_start = parse_time("WINDOW_START")?;
+        // Os 59 segundos são adicionados pois o período é definido no formato "22:00 - 05:59"
+        let (window_end, _) =
+            parse_time("WINDOW_END")?.overflowing_add_signed(TimeDelta::seconds(59));
+
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
+            last_notif_sent: None,
+            acc_t: 0,
+            is_notified: false,
+            episode: None,
+            window_start,
+            window_end,
+            timing: NotifTiming::from_cond_pars(cond_pars, DEFAULT_TIMING),
+            pulses_to_liters: get_pulses_to_liters(cond_pars),
+        })
+    }
+
+    /// O período pode passar da meia-noite (ex.: "22:00 - 05:59")
+    fn is_inside_window(&self, time: NaiveTime) -> bool {
+        let time = time.num_seconds_from_midnight();
+        let start = self.window_start.num_seconds_from_midnight();
+        let end = self.window_end.num_seconds_from_midnight();
+        if start <= end {
+            time >= start && time <= end
+        } else {
+            time >= start || time <= end
+        }
+    }
+}
+
+impl NotifRule<DmaTelemetry> for NotifDmaLeak {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<DmaTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.last_notif_sent = existing.last_notif_sent;
+        self.acc_t = existing.acc_t;
+        self.is_notified = existing.is_notified;
+        self.episode = existing.episode.clone();
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DmaTelemetry>, sink: &mut DetectionSink) {
+        let telemetry = sample.telemetry;
+        // Se faltar mais de uma telemetria, considera que houve descontinuidade
+        let continuity_secs = match self.timing.continuity_secs {
+            0 => telemetry.pulses_continuity_secs(),
+            x => x,
+        };
+        let Some(StreamDelta {
+            delta_secs,
+            descontinuidade,
+            ..
+        }) = sample.delta("pulses", continuity_secs)
+        else {
+            return;
+        };
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        if descontinuidade && self.acc_t > 0 {
+            // Registra no histórico que o acumulador da notificação vai ser zerado
+            let detail = serde_json::json!({ "stream": "pulses", "accumulated_secs": self.acc_t });
+            sink.push_reset(self.notif_id, "descontinuidade", detail);
+        }
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        // Sem continuidade a vazão precisa ser acumulada de novo. O episódio que ainda não foi notificado
+        // também recomeça, assim o início do vazamento informado corresponde ao tempo acumulado.
+        if descontinuidade {
+            self.acc_t = 0;
+            if self.episode.as_ref().is_some_and(|x| !x.notified) {
+                self.episode = None;
+            }
+        }
+
+        let flow_lpm = telemetry.flow_lpm(self.pulses_to_liters).unwrap_or(0.0);
+        let has_flow = flow_lpm > 0.0;
+        let violating = has_flow && self.is_inside_window(telemetry.timestamp.time());
+
+        // Quando a vazão para (ou termina o período), informa o fim do episódio que foi notificado
+        let ended_episode = ViolationEpisode::track(
+            &mut self.episode,
+            violating,
+            &telemetry.timestamp,
+            flow_lpm,
+            PeakKind::Max,
+            self.historical_policy.should_send(is_historical),
+        );
+        if let Some(ended_episode) = ended_episode {
+            sink.push(
+                "/DMA/Vazamento/Normalizado",
+                ended_episode.normalized_detection(
+                    dev_id,
+                    self.notif_id,
+                    &telemetry.timestamp,
+                    is_historical,
+                ),
+            );
+        }
+
+        if !violating {
+            self.acc_t = 0;
+            self.is_notified = false;
+            return;
+        }
+
+        // O episódio notificado só é enviado de novo no reenvio do RENOTIFY_HOURS
+        let decision = self
+            .timing
+            .send_decision(self.last_notif_sent.as_ref(), self.is_notified);
+        let renotify = decision == SendDecision::Renotify;
+        if self.is_notified && !renotify {
+            return;
+        }
+
+        // Os pulsos são do intervalo de amostragem, então o intervalo desde a última telemetria já conta.
+        // A vazão precisa ser contínua, o intervalo depois da falta de telemetria não conta.
+        if !descontinuidade {
+            self.acc_t += delta_secs;
+        }
+        if !renotify && self.acc_t < self.timing.duration_secs {
+            return;
+        }
+
+        // Dentro do intervalo mínimo entre notificações (COOLDOWN_MINUTES)
+        if decision == SendDecision::Blocked {
+            return;
+        }
+
+        if !self.historical_policy.should_send(is_historical) {
+            // A detecção cairia no período histórico, então fica como notificada
+            // para não virar um alerta antigo na primeira telemetria ao vivo
+            self.is_notified = true;
+            return;
+        }
+        self.is_notified = true;
+        self.last_notif_sent = Some(sim_clock::now_utc());
+        ViolationEpisode::mark_notified(&mut self.episode);
+        sink.push(
+            "/DMA/Vazamento",
+            serde_json::json!({
+                "dev_id": dev_id.to_owned(),
+                "notif_id": self.notif_id,
+                "window_start": self.window_start.format("%H:%M").to_string(),
+                "window_end": self.window_end.format("%H:%M").to_string(),
+                "duration_in_seconds": self.timing.duration_secs,
+                "renotification": renotify,
+                "flow_lpm": flow_lpm,
+                "leak_start": self.episode.as_ref().map(|x| x.start),
+                "telemetry_timestamp": telemetry.timestamp,
+                "detection_time": sim_clock::now_utc(),
+                "episode": ViolationEpisode::context(&self.episode),
+                "late": is_historical,
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app_realtime::notifications::dma::notifs_dma::dma_rules_registry;
+    use crate::app_realtime::notifications::dma::on_dma_telemetry::DmaTelemetry;
+    use crate::app_realtime::notifications::rules::test_helpers::*;
+    use crate::app_realtime::notifications::rules::RuleSet;
+    use chrono::{NaiveDateTime, TimeDelta};
+
+    /// Uma telemetria por minuto (intervalo de amostragem de 60 s) a partir do horário informado
+    fn pulses_samples(start: NaiveDateTime, pulses: &[u64]) -> Vec<DmaTelemetry> {
+        pulses
+            .iter()
+            .enumerate()
+            .map(|(i, value)| DmaTelemetry {
+                timestamp: start + TimeDelta::minutes(i as i64),
+                pulses: Some(*value),
+                sampling_time: 60,
+            })
+            .collect()
+    }
+
+    fn leak_rules() -> RuleSet<DmaTelemetry> {
+        rule_set(
+            dma_rules_registry(),
+            "DMA_LEAK >",
+            serde_json::json!({
+                "WINDOW_START": "22:00",
+                "WINDOW_END": "05:59",
+                "DURATION_IN_MINUTES": 5,
+            }),
+            &None,
+        )
+    }
+
+    #[test]
+    fn leak_window_wraps_midnight() {
+        let mut set = leak_rules();
+
+        // Fora do período a vazão é consumo normal
+        let detections = run(
+            &mut set,
+            pulses_samples(ts("2026-01-01 12:00:00"), &[3; 10]),
+        );
+        assert!(detections.is_empty());
+
+        // A vazão começa às 23:58 e completa os 5 minutos às 00:03 do dia seguinte
+        let detections = run(&mut set, pulses_samples(ts("2026-01-01 23:58:00"), &[3; 6]));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DMA/Vazamento");
+        assert_eq!(
+            detections[0].1["telemetry_timestamp"],
+            serde_json::json!(ts("2026-01-02 00:03:00"))
+        );
+        assert_eq!(
+            detections[0].1["leak_start"],
+            serde_json::json!(ts("2026-01-01 23:58:00"))
+        );
+        assert_eq!(detections[0].1["flow_lpm"], 3.0);
+
+        // A vazão para: informa o fim do episódio notificado
+        let detections = run(&mut set, pulses_samples(ts("2026-01-02 00:04:00"), &[3, 0]));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DMA/Vazamento/Normalizado");
+
+        // 05:59 ainda está dentro do período, 06:00 já não
+        let detections = run(&mut set, pulses_samples(ts("2026-01-02 05:54:00"), &[2; 6]));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(
+            detections[0].1["telemetry_timestamp"],
+            serde_json::json!(ts("2026-01-02 05:59:00"))
+        );
+        let detections = run(
+            &mut set,
+            pulses_samples(ts("2026-01-02 06:00:00"), &[2; 10]),
+        );
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DMA/Vazamento/Normalizado");
+    }
+
+    #[test]
+    fn leak_restarts_after_missing_telemetry() {
+        let mut set = leak_rules();
+
+        let detections = run(&mut set, pulses_samples(ts("2026-01-02 01:00:00"), &[3; 4]));
+        assert!(detections.is_empty());
+
+        // Faltaram telemetrias: o tempo e o episódio recomeçam na volta
+        let detections = run(&mut set, pulses_samples(ts("2026-01-02 01:10:00"), &[3; 6]));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(
+            detections[0].1["telemetry_timestamp"],
+            serde_json::json!(ts("2026-01-02 01:15:00"))
+        );
+        assert_eq!(
+            detections[0].1["leak_start"],
+            serde_json::json!(ts("2026-01-02 01:10:00"))
+        );
+    }
+}
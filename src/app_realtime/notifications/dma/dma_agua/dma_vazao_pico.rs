@@ -0,0 +1,157 @@
+use super::get_pulses_to_liters;
+use crate::app_realtime::notifications::dma::on_dma_telemetry::DmaTelemetry;
+use crate::app_realtime::notifications::episode::{PeakKind, ViolationEpisode};
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::NotifsCfgResponse_notif_item;
+use crate::app_realtime::notifications::rules::{DetectionSink, NotifRule, RuleSample};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+// Padrão: notifica na primeira leitura acima do limite, uma notificação por episódio
+// (o DURATION_IN_MINUTES e o CONTINUITY_SECONDS não são usados)
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 0,
+    cooldown_secs: 0,
+    continuity_secs: 0,
+    renotify_secs: None,
+};
+
+#[derive(Debug, Serialize)]
+pub struct NotifDmaBurst {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    pub last_notif_sent: Option<DateTime<Utc>>,
+    pub timing: NotifTiming,
+    pub episode: Option<ViolationEpisode>,
+
+    pub flow_limit_lpm: f64,
+    pub pulses_to_liters: f64,
+}
+impl NotifDmaBurst {
+    // "DMA_BURST >" vazãoAcimaDe X litros por minuto
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+    ) -> Result<Self, String> {
+        let cond_pars = &updated_notif_data.cond_pars;
+        // Pega o COND_PARS['FLOW_LIMIT_LPM']
+        let flow_limit_lpm = match cond_pars["FLOW_LIMIT_LPM"].as_f64() {
+            Some(x) if x > 0.0 => x,
+            _ => {
+                return Err("FLOW_LIMIT_LPM é obrigatório para a notificação".to_owned());
+            }
+        };
+
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
+            last_notif_sent: None,
+            timing: NotifTiming::from_cond_pars(cond_pars, DEFAULT_TIMING),
+            episode: None,
+            flow_limit_lpm,
+            pulses_to_liters: get_pulses_to_liters(cond_pars),
+        })
+    }
+}
+
+impl NotifRule<DmaTelemetry> for NotifDmaBurst {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<DmaTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.last_notif_sent = existing.last_notif_sent;
+        self.episode = existing.episode.clone();
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DmaTelemetry>, sink: &mut DetectionSink) {
+        let telemetry = sample.telemetry;
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        let Some(flow_lpm) = telemetry.flow_lpm(self.pulses_to_liters) else {
+            return;
+        };
+        let violating = flow_lpm > self.flow_limit_lpm;
+
+        // Quando a vazão volta ao normal, informa o fim do episódio que foi notificado
+        let ended_episode = ViolationEpisode::track(
+            &mut self.episode,
+            violating,
+            &telemetry.timestamp,
+            flow_lpm,
+            PeakKind::Max,
+            self.historical_policy.should_send(is_historical),
+        );
+        if let Some(ended_episode) = ended_episode {
+            sink.push(
+                "/DMA/VazaoPico/Normalizado",
+                ended_episode.normalized_detection(
+                    dev_id,
+                    self.notif_id,
+                    &telemetry.timestamp,
+                    is_historical,
+                ),
+            );
+        }
+
+        if !violating {
+            return;
+        }
+
+        // Notifica só uma vez por episódio (e no reenvio do RENOTIFY_HOURS), fora do COOLDOWN_MINUTES
+        let already_notified = self.episode.as_ref().is_some_and(|x| x.notified);
+        let decision = self
+            .timing
+            .send_decision(self.last_notif_sent.as_ref(), already_notified);
+        if decision == SendDecision::Blocked || (already_notified && decision == SendDecision::Send)
+        {
+            return;
+        }
+
+        if !self.historical_policy.should_send(is_historical) {
+            // Telemetria histórica com política de não enviar detecções
+            return;
+        }
+        self.last_notif_sent = Some(sim_clock::now_utc());
+        ViolationEpisode::mark_notified(&mut self.episode);
+        sink.push(
+            "/DMA/VazaoPico",
+            serde_json::json!({
+                "dev_id": dev_id.to_owned(),
+                "notif_id": self.notif_id,
+                "flow_limit_lpm": self.flow_limit_lpm,
+                "flow_lpm": flow_lpm,
+                "renotification": decision == SendDecision::Renotify,
+                "pulses": telemetry.pulses,
+                "sampling_time": telemetry.sampling_time,
+                "telemetry_timestamp": telemetry.timestamp,
+                "detection_time": sim_clock::now_utc(),
+                "episode": ViolationEpisode::context(&self.episode),
+                "late": is_historical,
+            }),
+        );
+    }
+}
@@ -0,0 +1,15 @@
+pub use dma_consumo_diario::NotifDmaDailyUsage;
+pub use dma_vazamento::NotifDmaLeak;
+pub use dma_vazao_pico::NotifDmaBurst;
+
+pub mod dma_consumo_diario;
+pub mod dma_vazamento;
+pub mod dma_vazao_pico;
+
+/// Fator de conversão de pulsos do medidor para litros, COND_PARS['PULSES_TO_LITERS'] (padrão 1 pulso = 1 litro)
+pub fn get_pulses_to_liters(cond_pars: &serde_json::Value) -> f64 {
+    cond_pars["PULSES_TO_LITERS"]
+        .as_f64()
+        .filter(|x| *x > 0.0)
+        .unwrap_or(1.0)
+}
@@ -0,0 +1,6 @@
+pub mod dma_agua;
+pub mod notifs_dma;
+pub mod on_dma_telemetry;
+
+pub use notifs_dma::NotifsDma;
+pub use on_dma_telemetry::on_dma_telemetry;
@@ -0,0 +1,83 @@
+use super::dma_agua::{NotifDmaBurst, NotifDmaDailyUsage, NotifDmaLeak};
+use super::on_dma_telemetry::DmaTelemetry;
+use crate::app_realtime::global_vars::DevInfo;
+use crate::app_realtime::notifications::coverage::NotifRejections;
+use crate::app_realtime::notifications::notifs_cfg::{
+    DutAutomationConfig, NotifsCfgResponse_notif_item,
+};
+use crate::app_realtime::notifications::rules::{parse_rules_list, RuleRegistry, RuleSet};
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::OnceLock;
+
+/// Regras de notificação do DMA (ver notifications/rules.rs)
+pub fn dma_rules_registry() -> &'static RuleRegistry<DmaTelemetry> {
+    static REGISTRY: OnceLock<RuleRegistry<DmaTelemetry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut registry = RuleRegistry::new();
+        registry.register("DMA_LEAK >", |notif, _| {
+            Ok(Box::new(NotifDmaLeak::from_notif_cfg(notif)?))
+        });
+        registry.register("DMA_USE D>", |notif, _| {
+            Ok(Box::new(NotifDmaDailyUsage::from_notif_cfg(notif)?))
+        });
+        registry.register("DMA_BURST >", |notif, _| {
+            Ok(Box::new(NotifDmaBurst::from_notif_cfg(notif)?))
+        });
+        registry
+    })
+}
+
+pub fn is_dma_cond_id(cond_id: &str) -> bool {
+    dma_rules_registry().contains(cond_id)
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotifsDma {
+    pub rules: RuleSet<DmaTelemetry>,
+}
+impl NotifsDma {
+    pub fn remove_notif_id(&mut self, notif_id: u64) {
+        self.rules.remove_notif_id(notif_id);
+    }
+}
+
+pub async fn update_notifs_dma(
+    dev_info: &mut DevInfo,
+    updated_dev_notifs: &Option<&Vec<Arc<NotifsCfgResponse_notif_item>>>,
+    updated_dev_sched: Option<Arc<DutAutomationConfig>>,
+    // O parâmetro "partial_update" indica se a lista "updated_dev_notifs" é completa ou parcial.
+    // Se for parcial, vamos atualizar as notificações que estiverem na lista sem mexer nas outras.
+    // Se for completa, vamos remover as notificações que não estiverem na lista.
+    partial_update: bool,
+    // Notificações recusadas pelo "from_notif_cfg" (ex.: falta TUSEMAX na programação)
+    rejected: &mut NotifRejections,
+) {
+    // Interpreta a resposta do API-Server (faz parse do JSON)
+    let rules = updated_dev_notifs
+        .map(|notifs| parse_rules_list(dma_rules_registry(), notifs, &updated_dev_sched, rejected))
+        .unwrap_or_default();
+
+    if rules.is_empty() {
+        if !partial_update {
+            // Já que não tem nenhuma notificação monitorando o dispositivo, limpa a lista
+            dev_info.has_notifs_dma.store(false, Ordering::Relaxed);
+            *dev_info.notifs_dma.write().await = None;
+        }
+        return;
+    }
+
+    // Pega o "dev_info.notifs_dma" em modo "write" para atualizar
+    let mut notifs_dma = dev_info.notifs_dma.write().await;
+
+    if let Some(existente) = notifs_dma.as_mut() {
+        existente.rules.update_rules(rules, partial_update);
+    } else {
+        *notifs_dma = Some(NotifsDma {
+            rules: RuleSet::new(rules),
+        });
+    };
+
+    dev_info.has_notifs_dma.store(true, Ordering::Relaxed);
+}
@@ -0,0 +1,101 @@
+use super::notifs_dma::NotifsDma;
+use crate::app_realtime::notifications::rules::RuleTelemetry;
+use crate::global_vars::GlobalVars;
+use crate::helpers::telemetry_payloads::{
+    parse_json_props::get_i64_optional,
+    telemetry_formats::{get_json_sampling_time, get_json_saved_data, get_json_timestamp_with_gmt},
+};
+use chrono::{NaiveDateTime, TimeDelta};
+use std::sync::Arc;
+
+pub async fn on_dma_telemetry(
+    payload_json: &serde_json::Value,
+    dev_alerts: &mut NotifsDma,
+    dev_id: &str,
+    ts_correction: Option<TimeDelta>,
+    globs: &Arc<GlobalVars>,
+) {
+    // Telemetria histórica (enviada depois pelo gateway): cada notificação decide como tratar
+    let is_historical = get_json_saved_data(payload_json);
+
+    let mut telemetry = match DmaTelemetry::parse_from_json(payload_json) {
+        Err(err) => {
+            crate::write_to_log_file_v2("ERROR", &format!("[103] {err} {}", payload_json), false);
+            return;
+        }
+        Ok(x) => x,
+    };
+
+    // Dispositivo com desvio de relógio: usa o horário corrigido pelo servidor
+    if let Some(ts_correction) = ts_correction {
+        telemetry.timestamp -= ts_correction;
+    }
+
+    // Todas as verificações são baseadas nos pulsos, se não tiver pode interromper
+    if telemetry.pulses.is_none() {
+        return;
+    }
+
+    // Notificações sobre o consumo de água
+    dev_alerts
+        .rules
+        .on_telemetry(&telemetry, dev_id, is_historical, globs)
+        .await;
+}
+
+#[derive(Debug)]
+pub struct DmaTelemetry {
+    pub timestamp: NaiveDateTime,
+    pub pulses: Option<u64>, // Pulsos contados pelo medidor durante o intervalo de amostragem
+    pub sampling_time: i64,  // Intervalo de amostragem em segundos
+}
+
+impl DmaTelemetry {
+    pub fn parse_from_json(payload_json: &serde_json::Value) -> Result<Self, String> {
+        let (payload_timestamp, _gmt) = get_json_timestamp_with_gmt(payload_json)?;
+        let sampling_time = get_json_sampling_time(payload_json)
+            .filter(|x| *x > 0)
+            .unwrap_or(60);
+
+        let pulses = get_i64_optional(&payload_json["pulses"]).and_then(|x| u64::try_from(x).ok());
+
+        Ok(DmaTelemetry {
+            timestamp: payload_timestamp,
+            pulses,
+            sampling_time,
+        })
+    }
+
+    /// Vazão média do intervalo de amostragem em litros por minuto
+    pub fn flow_lpm(&self, pulses_to_liters: f64) -> Option<f64> {
+        let pulses = self.pulses?;
+        Some((pulses as f64) * pulses_to_liters * 60.0 / (self.sampling_time as f64))
+    }
+
+    /// Se faltar mais de uma telemetria, considera que houve descontinuidade
+    pub fn pulses_continuity_secs(&self) -> u64 {
+        (2 * self.sampling_time).max(60) as u64
+    }
+}
+
+impl RuleTelemetry for DmaTelemetry {
+    fn timestamp(&self) -> &NaiveDateTime {
+        &self.timestamp
+    }
+
+    fn get_field(&self, name: &str) -> Option<f64> {
+        match name {
+            "pulses" => self.pulses.map(|x| x as f64),
+            _ => None,
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["pulses"]
+    }
+
+    // O intervalo depende do "sampling_time" da telemetria (ver "pulses_continuity_secs")
+    fn continuity_secs(_field: &str) -> u64 {
+        60
+    }
+}
@@ -0,0 +1,271 @@
+use super::on_dmt_telemetry::DmtTelemetry;
+use crate::app_realtime::notifications::episode::SustainedCondition;
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::{
+    AutomationSchedule, DutAutomationConfig, NotifsCfgResponse_notif_item,
+};
+use crate::app_realtime::notifications::rules::{DetectionSink, NotifRule, RuleSample};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+// Padrão: 15 minutos no estado inesperado, uma notificação por ocorrência
+// (o CONTINUITY_SECONDS não é usado, a condição é medida pelo timestamp das telemetrias)
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 15 * 60,
+    cooldown_secs: 0,
+    continuity_secs: 0,
+    renotify_secs: None,
+};
+
+#[derive(Debug, Serialize)]
+pub struct NotifDmtFeedbackUnexpected {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    // Equipamento ligado fora da programação
+    pub running_outside: SustainedCondition,
+    // Equipamento desligado dentro da programação (não partiu)
+    pub stopped_inside: SustainedCondition,
+
+    pub channel: usize,
+    pub check_outside: bool,
+    pub check_inside: bool,
+    pub timing: NotifTiming,
+    #[serde(skip)]
+    pub schedule: Option<Arc<AutomationSchedule>>,
+}
+impl NotifDmtFeedbackUnexpected {
+    // "DMT_FEEDBACK !SCHED" feedbackDiferenteDoEsperadoPelaProgramacao por mais de X minutos
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+        automation_cfg: &Option<Arc<DutAutomationConfig>>,
+    ) -> Result<Self, String> {
+        let Some(automation_cfg) = automation_cfg.as_ref() else {
+            return Err("A notificação exige que o dispositivo tenha programação".to_owned());
+        };
+        let cond_pars = &updated_notif_data.cond_pars;
+        // CHANNEL é o canal de feedback monitorado (1 a 4, equivalente a F1..F4)
+        let channel = match cond_pars["CHANNEL"].as_u64() {
+            Some(x) if (1..=4).contains(&x) => x as usize,
+            _ => {
+                return Err("CHANNEL (1 a 4) é obrigatório para a notificação".to_owned());
+            }
+        };
+        // EXPECTED define quais desvios são notificados: "OFF_OUTSIDE", "ON_INSIDE" ou "BOTH"
+        let (check_outside, check_inside) = match cond_pars["EXPECTED"].as_str() {
+            None | Some("BOTH") => (true, true),
+            Some("OFF_OUTSIDE") => (true, false),
+            Some("ON_INSIDE") => (false, true),
+            Some(expected) => {
+                return Err(format!("EXPECTED inválido: '{expected}'"));
+            }
+        };
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
+            running_outside: SustainedCondition::default(),
+            stopped_inside: SustainedCondition::default(),
+            channel,
+            check_outside,
+            check_inside,
+            timing: NotifTiming::from_cond_pars(cond_pars, DEFAULT_TIMING),
+            schedule: Some(automation_cfg.schedule.clone()),
+        })
+    }
+}
+
+impl NotifRule<DmtTelemetry> for NotifDmtFeedbackUnexpected {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<DmtTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        // Se tiver alterado o canal monitorado, o estado anterior não serve mais
+        if existing.channel != self.channel {
+            return;
+        }
+        self.running_outside = existing.running_outside.clone();
+        self.stopped_inside = existing.stopped_inside.clone();
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DmtTelemetry>, sink: &mut DetectionSink) {
+        let telemetry = sample.telemetry;
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        let Some(schedule) = self.schedule.as_ref() else {
+            return;
+        };
+        let Some(is_on) = telemetry.get_channel(self.channel) else {
+            // Sem leitura do canal nesta amostra
+            return;
+        };
+
+        // Dia sem programação definida: o equipamento deveria ficar desligado
+        let telemetry_timestamp = &telemetry.timestamp;
+        let inside_schedule = schedule
+            .get_for(&telemetry_timestamp.date())
+            .is_some_and(|prog| prog.is_inside_sched(telemetry_timestamp.time()));
+
+        let channel = self.channel;
+        let notif_id = self.notif_id;
+        let timing = self.timing;
+        let historical_policy = self.historical_policy;
+
+        for (condition, active, path) in [
+            (
+                &mut self.running_outside,
+                self.check_outside && is_on && !inside_schedule,
+                "/DMT/LigadoForaDaProgramacao",
+            ),
+            (
+                &mut self.stopped_inside,
+                self.check_inside && !is_on && inside_schedule,
+                "/DMT/DesligadoDentroDaProgramacao",
+            ),
+        ] {
+            let Some((elapsed_secs, decision)) =
+                condition.update(active, telemetry_timestamp, &timing)
+            else {
+                continue;
+            };
+
+            if !historical_policy.should_send(is_historical) {
+                // A detecção cairia no período histórico, então fica como notificada
+                // para não virar um alerta antigo na primeira telemetria ao vivo
+                condition.is_notified = true;
+                continue;
+            }
+            condition.mark_notified();
+            sink.push(
+                path,
+                serde_json::json!({
+                    "dev_id": dev_id.to_owned(),
+                    "notif_id": notif_id,
+                    "channel": format!("F{channel}"),
+                    "feedback": is_on,
+                    "inside_schedule": inside_schedule,
+                    "since": condition.since,
+                    "elapsed_seconds": elapsed_secs,
+                    "duration_in_seconds": timing.duration_secs,
+                    "renotification": decision == SendDecision::Renotify,
+                    "telemetry_timestamp": *telemetry_timestamp,
+                    "detection_time": sim_clock::now_utc(),
+                    "late": is_historical,
+                }),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NotifDmtFeedbackUnexpected;
+    use crate::app_realtime::notifications::dmt::notifs_dmt::dmt_rules_registry;
+    use crate::app_realtime::notifications::dmt::on_dmt_telemetry::DmtTelemetry;
+    use crate::app_realtime::notifications::rules::test_helpers::*;
+
+    fn f2_sample(timestamp: &str, is_on: bool) -> DmtTelemetry {
+        DmtTelemetry {
+            timestamp: ts(timestamp),
+            feedback: vec![None, Some(is_on), None, None],
+        }
+    }
+
+    #[test]
+    fn running_outside_and_stopped_inside_the_schedule() {
+        let mut set = rule_set(
+            dmt_rules_registry(),
+            "DMT_FEEDBACK !SCHED",
+            serde_json::json!({ "CHANNEL": 2 }),
+            &automation_cfg(None, None, Some(("08:00", "18:00"))),
+        );
+        // Padrão de 15 minutos ligado fora da programação
+        let detections = run(
+            &mut set,
+            [
+                f2_sample("2026-01-01 07:40:00", true),
+                f2_sample("2026-01-01 07:54:00", true),
+            ],
+        );
+        assert!(detections.is_empty());
+        let detections = run(&mut set, [f2_sample("2026-01-01 07:55:00", true)]);
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DMT/LigadoForaDaProgramacao");
+        assert_eq!(detections[0].1["channel"], "F2");
+        assert_eq!(detections[0].1["since"], "2026-01-01T07:40:00");
+
+        // Dentro da programação ligado é o esperado; desligado por 15 minutos é notificado
+        let detections = run(
+            &mut set,
+            [
+                f2_sample("2026-01-01 08:00:00", true),
+                f2_sample("2026-01-01 09:00:00", false),
+                f2_sample("2026-01-01 09:15:00", false),
+                f2_sample("2026-01-01 09:30:00", false),
+            ],
+        );
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DMT/DesligadoDentroDaProgramacao");
+        assert_eq!(detections[0].1["inside_schedule"], true);
+    }
+
+    #[test]
+    fn expected_selects_the_checked_deviation() {
+        let mut set = rule_set(
+            dmt_rules_registry(),
+            "DMT_FEEDBACK !SCHED",
+            serde_json::json!({ "CHANNEL": 2, "EXPECTED": "ON_INSIDE" }),
+            &automation_cfg(None, None, Some(("08:00", "18:00"))),
+        );
+        let detections = run(
+            &mut set,
+            [
+                f2_sample("2026-01-01 06:00:00", true),
+                f2_sample("2026-01-01 07:00:00", true),
+            ],
+        );
+        assert!(detections.is_empty());
+    }
+
+    #[test]
+    fn invalid_cond_pars_are_rejected() {
+        let cfg = automation_cfg(None, None, Some(("08:00", "18:00")));
+        for cond_pars in [
+            serde_json::json!({}),
+            serde_json::json!({ "CHANNEL": 5 }),
+            serde_json::json!({ "CHANNEL": 1, "EXPECTED": "ALWAYS" }),
+        ] {
+            let notif = notif_item(1, "DMT_FEEDBACK !SCHED", cond_pars);
+            assert!(NotifDmtFeedbackUnexpected::from_notif_cfg(&notif, &cfg).is_err());
+        }
+        // Sem programação não tem como saber o estado esperado
+        let notif = notif_item(
+            1,
+            "DMT_FEEDBACK !SCHED",
+            serde_json::json!({ "CHANNEL": 1 }),
+        );
+        assert!(NotifDmtFeedbackUnexpected::from_notif_cfg(&notif, &None).is_err());
+    }
+}
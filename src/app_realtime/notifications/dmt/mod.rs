@@ -0,0 +1,6 @@
+pub mod dmt_feedback;
+pub mod notifs_dmt;
+pub mod on_dmt_telemetry;
+
+pub use notifs_dmt::NotifsDmt;
+pub use on_dmt_telemetry::on_dmt_telemetry;
@@ -0,0 +1,84 @@
+use super::dmt_feedback::NotifDmtFeedbackUnexpected;
+use super::on_dmt_telemetry::DmtTelemetry;
+use crate::app_realtime::global_vars::DevInfo;
+use crate::app_realtime::notifications::coverage::NotifRejections;
+use crate::app_realtime::notifications::notifs_cfg::{
+    AutomationSchedule, DutAutomationConfig, NotifsCfgResponse_notif_item,
+};
+use crate::app_realtime::notifications::rules::{parse_rules_list, RuleRegistry, RuleSet};
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::OnceLock;
+
+/// Regras de notificação do DMT (ver notifications/rules.rs)
+pub fn dmt_rules_registry() -> &'static RuleRegistry<DmtTelemetry> {
+    static REGISTRY: OnceLock<RuleRegistry<DmtTelemetry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut registry = RuleRegistry::new();
+        registry.register("DMT_FEEDBACK !SCHED", |notif, automation_cfg| {
+            let rule = NotifDmtFeedbackUnexpected::from_notif_cfg(notif, automation_cfg)?;
+            Ok(Box::new(rule))
+        });
+        registry
+    })
+}
+
+pub fn is_dmt_cond_id(cond_id: &str) -> bool {
+    dmt_rules_registry().contains(cond_id)
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotifsDmt {
+    pub rules: RuleSet<DmtTelemetry>,
+    #[serde(skip)]
+    pub schedule: Option<Arc<AutomationSchedule>>,
+}
+impl NotifsDmt {
+    pub fn remove_notif_id(&mut self, notif_id: u64) {
+        self.rules.remove_notif_id(notif_id);
+    }
+}
+
+pub async fn update_notifs_dmt(
+    dev_info: &mut DevInfo,
+    updated_dev_notifs: &Option<&Vec<Arc<NotifsCfgResponse_notif_item>>>,
+    updated_dev_sched: Option<Arc<DutAutomationConfig>>,
+    // O parâmetro "partial_update" indica se a lista "updated_dev_notifs" é completa ou parcial.
+    // Se for parcial, vamos atualizar as notificações que estiverem na lista sem mexer nas outras.
+    // Se for completa, vamos remover as notificações que não estiverem na lista.
+    partial_update: bool,
+    // Notificações recusadas pelo "from_notif_cfg" (ex.: falta TUSEMAX na programação)
+    rejected: &mut NotifRejections,
+) {
+    // Interpreta a resposta do API-Server (faz parse do JSON)
+    let rules = updated_dev_notifs
+        .map(|notifs| parse_rules_list(dmt_rules_registry(), notifs, &updated_dev_sched, rejected))
+        .unwrap_or_default();
+
+    if rules.is_empty() {
+        if !partial_update {
+            // Já que não tem nenhuma notificação monitorando o dispositivo, limpa a lista
+            dev_info.has_notifs_dmt.store(false, Ordering::Relaxed);
+            *dev_info.notifs_dmt.write().await = None;
+        }
+        return;
+    }
+
+    let schedule = updated_dev_sched.map(|x| x.schedule.clone());
+
+    // Pega o "dev_info.notifs_dmt" em modo "write" para atualizar
+    let mut notifs_dmt = dev_info.notifs_dmt.write().await;
+
+    if let Some(existente) = notifs_dmt.as_mut() {
+        existente.schedule = schedule;
+        existente.rules.update_rules(rules, partial_update);
+    } else {
+        *notifs_dmt = Some(NotifsDmt {
+            rules: RuleSet::new(rules),
+            schedule,
+        });
+    };
+
+    dev_info.has_notifs_dmt.store(true, Ordering::Relaxed);
+}
@@ -0,0 +1,92 @@
+use super::notifs_dmt::NotifsDmt;
+use crate::app_realtime::notifications::rules::RuleTelemetry;
+use crate::global_vars::GlobalVars;
+use crate::helpers::telemetry_payloads::{
+    parse_json_props::{get_bool_array_optional, get_bool_optional},
+    telemetry_formats::{get_json_saved_data, get_json_timestamp_with_gmt},
+};
+use chrono::{NaiveDateTime, TimeDelta};
+use std::sync::Arc;
+
+pub async fn on_dmt_telemetry(
+    payload_json: &serde_json::Value,
+    dev_alerts: &mut NotifsDmt,
+    dev_id: &str,
+    ts_correction: Option<TimeDelta>,
+    globs: &Arc<GlobalVars>,
+) {
+    // Telemetria histórica (enviada depois pelo gateway): cada notificação decide como tratar
+    let is_historical = get_json_saved_data(payload_json);
+
+    let mut telemetry = match DmtTelemetry::parse_from_json(payload_json) {
+        Err(err) => {
+            crate::write_to_log_file_v2("ERROR", &format!("[110] {err} {}", payload_json), false);
+            return;
+        }
+        Ok(x) => x,
+    };
+
+    // Dispositivo com desvio de relógio: usa o horário corrigido pelo servidor
+    if let Some(ts_correction) = ts_correction {
+        telemetry.timestamp -= ts_correction;
+    }
+
+    // Os valores mais antigos que o último processado não são avaliados, corromperiam a sequência de estados
+    dev_alerts
+        .rules
+        .on_telemetry(&telemetry, dev_id, is_historical, globs)
+        .await;
+}
+
+#[derive(Debug)]
+pub struct DmtTelemetry {
+    pub timestamp: NaiveDateTime,
+    pub feedback: Vec<Option<bool>>, // F1..F4, o índice 0 é o canal F1
+}
+
+impl DmtTelemetry {
+    pub fn parse_from_json(payload_json: &serde_json::Value) -> Result<Self, String> {
+        let (payload_timestamp, _gmt) = get_json_timestamp_with_gmt(payload_json)?;
+
+        // O pacote de telemetrias (TelemetryPackDMT) usa o array "Feedback", a telemetria individual usa F1..F4
+        let feedback = match get_bool_array_optional(&payload_json["Feedback"]) {
+            Some(feedback) => feedback,
+            None => ["F1", "F2", "F3", "F4"]
+                .iter()
+                .map(|prop| get_bool_optional(&payload_json[*prop]))
+                .collect(),
+        };
+
+        Ok(DmtTelemetry {
+            timestamp: payload_timestamp,
+            feedback,
+        })
+    }
+
+    /// Estado do canal de feedback (1 a 4)
+    pub fn get_channel(&self, channel: usize) -> Option<bool> {
+        self.feedback
+            .get(channel.checked_sub(1)?)
+            .copied()
+            .flatten()
+    }
+}
+
+impl RuleTelemetry for DmtTelemetry {
+    fn timestamp(&self) -> &NaiveDateTime {
+        &self.timestamp
+    }
+
+    // Os canais de feedback são lidos com "get_channel"
+    fn get_field(&self, _name: &str) -> Option<f64> {
+        None
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &[]
+    }
+
+    fn continuity_secs(_field: &str) -> u64 {
+        30
+    }
+}
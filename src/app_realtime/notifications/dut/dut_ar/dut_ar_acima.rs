@@ -0,0 +1,357 @@
+use crate::app_realtime::sim_clock;
+use crate::{
+    app_realtime::notifications::dut::on_dut_telemetry::DutTelemetry,
+    app_realtime::notifications::episode::{schedule_window_context, PeakKind, ViolationEpisode},
+    app_realtime::notifications::historical::HistoricalPolicy,
+    app_realtime::notifications::notifs_cfg::{
+        AutomationSchedule, DutAutomationConfig, NotifsCfgResponse_notif_item,
+    },
+    app_realtime::notifications::rules::{DetectionSink, NotifRule, RuleSample, StreamDelta},
+    app_realtime::notifications::timing::{NotifTiming, SendDecision},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+/// Grandeza de qualidade do ar monitorada pela notificação
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum DutAirVar {
+    Co2,  // "DUT_CO2", limite CO2MAX da programação do DUT
+    Tvoc, // "DUT_TVOC", limite TVOC_LIMIT da notificação ou TVOCMAX da programação
+}
+
+impl DutAirVar {
+    /// Nome do campo na telemetria do DUT
+    pub fn field(&self) -> &'static str {
+        match self {
+            DutAirVar::Co2 => "eCO2",
+            DutAirVar::Tvoc => "TVOC",
+        }
+    }
+
+    /// Nome do limite na detecção
+    pub fn limit_name(&self) -> &'static str {
+        match self {
+            DutAirVar::Co2 => "CO2MAX",
+            DutAirVar::Tvoc => "TVOC_LIMIT",
+        }
+    }
+
+    pub fn limit_from_cfg(
+        &self,
+        updated_notif_data: &NotifsCfgResponse_notif_item,
+        automation_cfg: &Option<Arc<DutAutomationConfig>>,
+    ) -> Result<f64, String> {
+        let limit = match self {
+            DutAirVar::Co2 => automation_cfg.as_ref().and_then(|x| x.co2max),
+            // TVOC_LIMIT na notificação tem prioridade sobre o TVOCMAX da programação do DUT
+            DutAirVar::Tvoc => updated_notif_data.cond_pars["TVOC_LIMIT"]
+                .as_f64()
+                .or_else(|| automation_cfg.as_ref().and_then(|x| x.tvocmax)),
+        };
+        match (limit, self) {
+            (Some(x), _) => Ok(x),
+            (None, DutAirVar::Co2) => Err("CO2MAX é obrigatório para a notificação".to_owned()),
+            (None, DutAirVar::Tvoc) => {
+                Err("TVOC_LIMIT ou TVOCMAX é obrigatório para a notificação".to_owned())
+            }
+        }
+    }
+
+    fn notif_path(&self) -> &'static str {
+        match self {
+            DutAirVar::Co2 => "/DUT_CO2/Acima",
+            DutAirVar::Tvoc => "/DUT_TVOC/Acima",
+        }
+    }
+
+    fn normalized_path(&self) -> &'static str {
+        match self {
+            DutAirVar::Co2 => "/DUT_CO2/Acima/Normalizado",
+            DutAirVar::Tvoc => "/DUT_TVOC/Acima/Normalizado",
+        }
+    }
+
+    pub(super) fn end_of_day_path(&self) -> &'static str {
+        match self {
+            DutAirVar::Co2 => "/DUT_CO2/AcimaDiario",
+            DutAirVar::Tvoc => "/DUT_TVOC/AcimaDiario",
+        }
+    }
+}
+
+// Padrão: 10 minutos acima do limite e no máximo uma notificação a cada 24 horas
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 10 * 60,
+    cooldown_secs: 24 * 60 * 60,
+    continuity_secs: 3 * 60,
+    renotify_secs: None,
+};
+
+#[derive(Debug, Serialize)]
+pub struct NotifDutAirHigh {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    pub last_notif_sent: Option<DateTime<Utc>>,
+    pub acc_t: u64,
+    pub episode: Option<ViolationEpisode>,
+    pub variable: DutAirVar,
+    pub limit: f64,
+    pub timing: NotifTiming,
+    #[serde(skip)]
+    pub schedule: Option<Arc<AutomationSchedule>>,
+}
+impl NotifDutAirHigh {
+    // "DUT_CO2 >" / "DUT_TVOC >"
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+        automation_cfg: &Option<Arc<DutAutomationConfig>>,
+        variable: DutAirVar,
+    ) -> Result<Self, String> {
+        let limit = variable.limit_from_cfg(updated_notif_data, automation_cfg)?;
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
+            acc_t: 0,
+            episode: None,
+            last_notif_sent: None,
+            variable,
+            limit,
+            timing: NotifTiming::from_cond_pars(&updated_notif_data.cond_pars, DEFAULT_TIMING),
+            schedule: automation_cfg.as_ref().map(|x| x.schedule.clone()),
+        })
+    }
+}
+
+impl NotifRule<DutTelemetry> for NotifDutAirHigh {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<DutTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        if existing.variable != self.variable {
+            return;
+        }
+        self.last_notif_sent = existing.last_notif_sent;
+        self.acc_t = existing.acc_t;
+        self.episode = existing.episode.clone();
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DutTelemetry>, sink: &mut DetectionSink) {
+        let field = self.variable.field();
+        let Some(telemetry_value) = sample.value(field) else {
+            return;
+        };
+        // Cada notificação pode ter o seu próprio CONTINUITY_SECONDS
+        let Some(StreamDelta {
+            delta_secs,
+            descontinuidade,
+            new_day,
+        }) = sample.delta(field, self.timing.continuity_secs)
+        else {
+            return;
+        };
+        let telemetry_timestamp = sample.timestamp();
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+        // { label: 'DUT_CO2.nivelCO2.estiverAcimaLimite', value: '>', unit: null, describe: (val) => !req? `Nível de CO2 estiver acima do limite` : 'notificacao.nivelCO2AcimaLimite' },
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        // Sem continuidade a condição precisa durar todo o tempo de novo
+        if new_day || descontinuidade {
+            if self.acc_t > 0 {
+                // Registra no histórico que o acumulador da notificação vai ser zerado
+                let reason = if new_day {
+                    "new_day"
+                } else {
+                    "descontinuidade"
+                };
+                let detail = serde_json::json!({ "stream": field, "accumulated_secs": self.acc_t });
+                sink.push_reset(self.notif_id, reason, detail);
+            }
+            self.acc_t = 0;
+        }
+
+        // O fim do episódio notificado é informado mesmo durante o intervalo entre notificações
+        let ended_episode = ViolationEpisode::track(
+            &mut self.episode,
+            telemetry_value > self.limit,
+            telemetry_timestamp,
+            telemetry_value,
+            PeakKind::Max,
+            self.historical_policy.should_send(is_historical),
+        );
+        if let Some(ended_episode) = ended_episode {
+            sink.push(
+                self.variable.normalized_path(),
+                ended_episode.normalized_detection(
+                    dev_id,
+                    self.notif_id,
+                    telemetry_timestamp,
+                    is_historical,
+                ),
+            );
+        }
+
+        // Se estiver dentro do intervalo mínimo entre notificações, não precisa nem conferir
+        let persisting = self.episode.as_ref().is_some_and(|x| x.notified);
+        let decision = self
+            .timing
+            .send_decision(self.last_notif_sent.as_ref(), persisting);
+        if decision == SendDecision::Blocked {
+            return;
+        }
+
+        if telemetry_value > self.limit {
+            if self.acc_t > self.timing.duration_secs {
+                if !self.historical_policy.should_send(is_historical) {
+                    // A detecção cairia no período histórico, então o estado que a dispararia é descartado
+                    // para não virar um alerta antigo na primeira telemetria ao vivo
+                    self.acc_t = 0;
+                    return;
+                }
+                self.last_notif_sent = Some(sim_clock::now_utc());
+                ViolationEpisode::mark_notified(&mut self.episode);
+                let mut detection = serde_json::json!({
+                    "dev_id": dev_id.to_owned(),
+                    "notif_id": self.notif_id,
+                    "renotification": decision == SendDecision::Renotify,
+                    "telemetry_timestamp": *telemetry_timestamp,
+                    "detection_time": sim_clock::now_utc(),
+                    "episode": ViolationEpisode::context(&self.episode),
+                    "schedule": schedule_window_context(self.schedule.as_deref(), telemetry_timestamp),
+                    "late": is_historical,
+                });
+                detection[self.variable.limit_name()] = self.limit.into();
+                sink.push(self.variable.notif_path(), detection);
+            } else {
+                self.acc_t += delta_secs;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app_realtime::notifications::dut::notifs_dut::dut_rules_registry;
+    use crate::app_realtime::notifications::dut::on_dut_telemetry::DutTelemetry;
+    use crate::app_realtime::notifications::notifs_cfg::DutAutomationConfig;
+    use crate::app_realtime::notifications::rules::test_helpers::*;
+    use crate::app_realtime::notifications::rules::{parse_rules_list, RuleSet};
+    use chrono::TimeDelta;
+    use std::sync::Arc;
+
+    fn air_cfg(co2max: Option<f64>, tvocmax: Option<f64>) -> Option<Arc<DutAutomationConfig>> {
+        let cfg = automation_cfg(None, None, None).unwrap();
+        Some(Arc::new(DutAutomationConfig {
+            tusemax: None,
+            tusemin: None,
+            co2max,
+            tvocmax,
+            schedule: cfg.schedule.clone(),
+        }))
+    }
+
+    /// Uma leitura de TVOC a cada 60 s a partir das 10:00
+    fn tvoc_samples(values: &[i16]) -> Vec<DutTelemetry> {
+        let start = ts("2026-01-01 10:00:00");
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| DutTelemetry {
+                timestamp: start + TimeDelta::seconds(60 * i as i64),
+                Temperature: None,
+                eCO2: None,
+                Humidity: None,
+                TVOC: Some(*value),
+            })
+            .collect()
+    }
+
+    fn tvoc_rules(cond_pars: serde_json::Value, tvocmax: Option<f64>) -> RuleSet<DutTelemetry> {
+        rule_set(
+            dut_rules_registry(),
+            "DUT_TVOC >",
+            cond_pars,
+            &air_cfg(None, tvocmax),
+        )
+    }
+
+    #[test]
+    fn tvoc_above_limit_for_the_duration() {
+        let mut set = tvoc_rules(serde_json::json!({ "DURATION_IN_MINUTES": 3 }), Some(500.0));
+        // Acumula 60 s a cada leitura, o alerta sai quando passa de 3 minutos
+        let detections = run(&mut set, tvoc_samples(&[600; 6]));
+        assert_eq!(detections.len(), 1);
+        let (path, detection) = &detections[0];
+        assert_eq!(*path, "/DUT_TVOC/Acima");
+        assert_eq!(detection["TVOC_LIMIT"], 500.0);
+        assert!(detection.get("CO2MAX").is_none());
+        assert_eq!(detection["telemetry_timestamp"], "2026-01-01T10:05:00");
+    }
+
+    #[test]
+    fn tvoc_limit_in_the_notification_overrides_the_schedule() {
+        let mut set = tvoc_rules(
+            serde_json::json!({ "TVOC_LIMIT": 800.0, "DURATION_IN_MINUTES": 3 }),
+            Some(500.0),
+        );
+        // Acima do TVOCMAX mas abaixo do TVOC_LIMIT da notificação
+        assert!(run(&mut set, tvoc_samples(&[600; 6])).is_empty());
+        assert_eq!(set.rules[&1].state_json()["acc_t"], 0);
+    }
+
+    #[test]
+    fn tvoc_episode_ends_with_normalized_detection() {
+        let mut set = tvoc_rules(serde_json::json!({ "DURATION_IN_MINUTES": 1 }), Some(500.0));
+        let detections = run(&mut set, tvoc_samples(&[600, 600, 900, 600, 600, 400]));
+        let paths: Vec<_> = detections.iter().map(|(path, _)| *path).collect();
+        assert_eq!(paths, ["/DUT_TVOC/Acima", "/DUT_TVOC/Acima/Normalizado"]);
+        assert_eq!(detections[1].1["peak_value"], 900.0);
+    }
+
+    #[test]
+    fn co2_does_not_read_the_tvoc_field() {
+        let mut set = rule_set(
+            dut_rules_registry(),
+            "DUT_CO2 >",
+            serde_json::json!({ "DURATION_IN_MINUTES": 3 }),
+            &air_cfg(Some(1000.0), None),
+        );
+        assert!(run(&mut set, tvoc_samples(&[2000; 6])).is_empty());
+    }
+
+    #[test]
+    fn missing_tvoc_limit_is_rejected() {
+        let notifs = [notif_item(1, "DUT_TVOC >", serde_json::json!({}))];
+        let mut rejected = Vec::new();
+        let rules = parse_rules_list(
+            dut_rules_registry(),
+            &notifs,
+            &air_cfg(Some(1000.0), None),
+            &mut rejected,
+        );
+        assert!(rules.is_empty());
+        assert_eq!(rejected.len(), 1);
+        assert!(rejected[0].1.contains("TVOC_LIMIT"), "{}", rejected[0].1);
+    }
+}
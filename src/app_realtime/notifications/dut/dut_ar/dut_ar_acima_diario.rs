@@ -0,0 +1,167 @@
+use crate::app_realtime::sim_clock;
+use crate::{
+    app_realtime::notifications::dut::dut_ar::DutAirVar,
+    app_realtime::notifications::dut::on_dut_telemetry::DutTelemetry,
+    app_realtime::notifications::episode::{schedule_window_context, PeakKind, ViolationEpisode},
+    app_realtime::notifications::historical::HistoricalPolicy,
+    app_realtime::notifications::notifs_cfg::{
+        AutomationSchedule, DutAutomationConfig, NotifsCfgResponse_notif_item,
+    },
+    app_realtime::notifications::rules::{DetectionSink, NotifRule, RuleSample, StreamDelta},
+    app_realtime::notifications::timing::{NotifTiming, SendDecision},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+// Padrão: 10 minutos acima do limite no dia, sem intervalo mínimo entre notificações
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 10 * 60,
+    cooldown_secs: 0,
+    continuity_secs: 3 * 60,
+    renotify_secs: None,
+};
+
+#[derive(Debug, Serialize)]
+pub struct NotifDutAirHighEndOfDay {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    pub last_notif_sent: Option<DateTime<Utc>>,
+    pub acc_t: u64,
+    pub episode: Option<ViolationEpisode>,
+    pub variable: DutAirVar,
+    pub limit: f64,
+    pub timing: NotifTiming,
+    #[serde(skip)]
+    pub schedule: Option<Arc<AutomationSchedule>>,
+}
+impl NotifDutAirHighEndOfDay {
+    // "DUT_CO2 D>" / "DUT_TVOC D>"
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+        automation_cfg: &Option<Arc<DutAutomationConfig>>,
+        variable: DutAirVar,
+    ) -> Result<Self, String> {
+        let limit = variable.limit_from_cfg(updated_notif_data, automation_cfg)?;
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                HistoricalPolicy::Late,
+            ),
+            acc_t: 0,
+            episode: None,
+            last_notif_sent: None,
+            variable,
+            limit,
+            timing: NotifTiming::from_cond_pars(&updated_notif_data.cond_pars, DEFAULT_TIMING),
+            schedule: automation_cfg.as_ref().map(|x| x.schedule.clone()),
+        })
+    }
+}
+
+impl NotifRule<DutTelemetry> for NotifDutAirHighEndOfDay {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<DutTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        if existing.variable != self.variable {
+            return;
+        }
+        self.last_notif_sent = existing.last_notif_sent;
+        self.acc_t = existing.acc_t;
+        self.episode = existing.episode.clone();
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DutTelemetry>, sink: &mut DetectionSink) {
+        let field = self.variable.field();
+        let Some(telemetry_value) = sample.value(field) else {
+            return;
+        };
+        // Cada notificação pode ter o seu próprio CONTINUITY_SECONDS
+        let Some(StreamDelta {
+            delta_secs,
+            descontinuidade,
+            new_day,
+        }) = sample.delta(field, self.timing.continuity_secs)
+        else {
+            return;
+        };
+        let telemetry_timestamp = sample.timestamp();
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+        // { label: 'DUT_CO2.nivelCO2.estiverAcimaLimiteDiario', value: 'D>', unit: null, describe: (val) => !req? `Nível de CO2 estiver acima do limite` : 'notificacao.nivelCO2AcimaLimite' },
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        if new_day {
+            if self.acc_t > 0 {
+                // Registra no histórico que o acumulador da notificação vai ser zerado
+                let detail = serde_json::json!({ "stream": field, "accumulated_secs": self.acc_t });
+                sink.push_reset(self.notif_id, "new_day", detail);
+            }
+            self.acc_t = 0;
+        }
+
+        // Episódio atual acima do limite, usado só para dar contexto à detecção (não tem aviso de normalização)
+        ViolationEpisode::track(
+            &mut self.episode,
+            telemetry_value > self.limit,
+            telemetry_timestamp,
+            telemetry_value,
+            PeakKind::Max,
+            true,
+        );
+
+        // Se estiver dentro do intervalo mínimo entre notificações (COOLDOWN_MINUTES), não precisa nem conferir
+        if self
+            .timing
+            .send_decision(self.last_notif_sent.as_ref(), false)
+            == SendDecision::Blocked
+        {
+            return;
+        }
+
+        if telemetry_value > self.limit {
+            if self.acc_t > self.timing.duration_secs {
+                if !self.historical_policy.should_send(is_historical) {
+                    // A detecção cairia no período histórico, então o estado que a dispararia é descartado
+                    // para não virar um alerta antigo na primeira telemetria ao vivo
+                    self.acc_t = 0;
+                    return;
+                }
+                self.last_notif_sent = Some(sim_clock::now_utc());
+                let mut detection = serde_json::json!({
+                    "dev_id": dev_id.to_owned(),
+                    "notif_id": self.notif_id,
+                    "telemetry_timestamp": *telemetry_timestamp,
+                    "detection_time": sim_clock::now_utc(),
+                    "episode": ViolationEpisode::context(&self.episode),
+                    "schedule": schedule_window_context(self.schedule.as_deref(), telemetry_timestamp),
+                    "late": is_historical,
+                });
+                detection[self.variable.limit_name()] = self.limit.into();
+                sink.push(self.variable.end_of_day_path(), detection);
+            } else if !descontinuidade {
+                // O intervalo sem telemetria não conta no total do dia
+                self.acc_t += delta_secs;
+            }
+        }
+    }
+}
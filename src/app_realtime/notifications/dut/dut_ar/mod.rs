@@ -0,0 +1,5 @@
+pub use dut_ar_acima::{DutAirVar, NotifDutAirHigh};
+pub use dut_ar_acima_diario::NotifDutAirHighEndOfDay;
+
+pub mod dut_ar_acima;
+pub mod dut_ar_acima_diario;
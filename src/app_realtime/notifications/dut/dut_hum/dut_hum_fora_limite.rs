@@ -0,0 +1,380 @@
+use crate::app_realtime::notifications::dut::on_dut_telemetry::DutTelemetry;
+use crate::app_realtime::notifications::episode::{
+    schedule_window_context, PeakKind, ViolationEpisode,
+};
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::{
+    AutomationSchedule, DutAutomationConfig, NotifsCfgResponse_notif_item,
+};
+use crate::app_realtime::notifications::rules::{
+    DetectionSink, NotifRule, RuleSample, StreamDelta,
+};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+/// Limite da umidade monitorado pela notificação
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum HumLimitKind {
+    Max, // "DUT_HUM >" umidade acima do HUM_MAX
+    Min, // "DUT_HUM <" umidade abaixo do HUM_MIN
+}
+
+impl HumLimitKind {
+    fn cond_par(&self) -> &'static str {
+        match self {
+            HumLimitKind::Max => "HUM_MAX",
+            HumLimitKind::Min => "HUM_MIN",
+        }
+    }
+
+    fn notif_path(&self) -> &'static str {
+        match self {
+            HumLimitKind::Max => "/DUT_HUM/Acima",
+            HumLimitKind::Min => "/DUT_HUM/Abaixo",
+        }
+    }
+
+    fn normalized_path(&self) -> &'static str {
+        match self {
+            HumLimitKind::Max => "/DUT_HUM/Acima/Normalizado",
+            HumLimitKind::Min => "/DUT_HUM/Abaixo/Normalizado",
+        }
+    }
+
+    fn peak_kind(&self) -> PeakKind {
+        match self {
+            HumLimitKind::Max => PeakKind::Max,
+            HumLimitKind::Min => PeakKind::Min,
+        }
+    }
+
+    fn is_violating(&self, humidity: f64, limit: f64) -> bool {
+        match self {
+            HumLimitKind::Max => humidity > limit,
+            HumLimitKind::Min => humidity < limit,
+        }
+    }
+}
+
+// Padrão: 10 minutos fora do limite, uma notificação por episódio
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 10 * 60,
+    cooldown_secs: 0,
+    continuity_secs: 30,
+    renotify_secs: None,
+};
+
+#[derive(Debug, Serialize)]
+pub struct NotifDutHumOutOfLimit {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    pub last_notif_sent: Option<DateTime<Utc>>,
+    pub acc_t: u64,
+    pub episode: Option<ViolationEpisode>,
+    pub limit_kind: HumLimitKind,
+    pub humidity_limit: f64,
+    pub timing: NotifTiming,
+    #[serde(skip)]
+    pub schedule: Option<Arc<AutomationSchedule>>,
+}
+
+impl NotifDutHumOutOfLimit {
+    // "DUT_HUM >" / "DUT_HUM <"
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+        automation_cfg: &Option<Arc<DutAutomationConfig>>,
+        limit_kind: HumLimitKind,
+    ) -> Result<Self, String> {
+        // HUM_MAX / HUM_MIN é a umidade relativa (%) máxima / mínima permitida
+        let cond_par = limit_kind.cond_par();
+        let humidity_limit = match updated_notif_data.cond_pars[cond_par].as_f64() {
+            Some(x) => x,
+            None => {
+                return Err(format!("{cond_par} é obrigatório para a notificação"));
+            }
+        };
+
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
+            last_notif_sent: None,
+            acc_t: 0,
+            episode: None,
+            limit_kind,
+            humidity_limit,
+            timing: NotifTiming::from_cond_pars(&updated_notif_data.cond_pars, DEFAULT_TIMING),
+            schedule: automation_cfg.as_ref().map(|x| x.schedule.clone()),
+        })
+    }
+}
+
+impl NotifRule<DutTelemetry> for NotifDutHumOutOfLimit {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<DutTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        // Se tiver trocado o COND_ID (acima/abaixo), o estado anterior não serve mais
+        if existing.limit_kind != self.limit_kind {
+            return;
+        }
+        self.last_notif_sent = existing.last_notif_sent;
+        self.acc_t = existing.acc_t;
+        self.episode = existing.episode.clone();
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DutTelemetry>, sink: &mut DetectionSink) {
+        let Some(telemetry_humidity) = sample.value("Humidity") else {
+            return;
+        };
+        // Cada notificação pode ter o seu próprio CONTINUITY_SECONDS
+        let Some(StreamDelta {
+            delta_secs,
+            descontinuidade,
+            new_day,
+        }) = sample.delta("Humidity", self.timing.continuity_secs)
+        else {
+            return;
+        };
+        let telemetry_timestamp = sample.timestamp();
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        if new_day {
+            if self.acc_t > 0 {
+                // Registra no histórico que o acumulador da notificação vai ser zerado
+                let detail =
+                    serde_json::json!({ "stream": "Humidity", "accumulated_secs": self.acc_t });
+                sink.push_reset(self.notif_id, "new_day", detail);
+            }
+            self.acc_t = 0;
+        }
+
+        let violating = self
+            .limit_kind
+            .is_violating(telemetry_humidity, self.humidity_limit);
+
+        // Quando a umidade volta para o limite, informa o fim do episódio que foi notificado
+        let ended_episode = ViolationEpisode::track(
+            &mut self.episode,
+            violating,
+            telemetry_timestamp,
+            telemetry_humidity,
+            self.limit_kind.peak_kind(),
+            self.historical_policy.should_send(is_historical),
+        );
+        if let Some(ended_episode) = ended_episode {
+            sink.push(
+                self.limit_kind.normalized_path(),
+                ended_episode.normalized_detection(
+                    dev_id,
+                    self.notif_id,
+                    telemetry_timestamp,
+                    is_historical,
+                ),
+            );
+        }
+
+        if !violating {
+            return;
+        }
+
+        // Se o episódio atual já foi notificado, só notifica de novo depois que a umidade normalizar
+        // (ou no reenvio do RENOTIFY_HOURS)
+        let persisting = self.episode.as_ref().is_some_and(|x| x.notified);
+        let decision = self
+            .timing
+            .send_decision(self.last_notif_sent.as_ref(), persisting);
+        if decision == SendDecision::Blocked || (persisting && decision == SendDecision::Send) {
+            return;
+        }
+
+        if decision == SendDecision::Renotify || self.acc_t > self.timing.duration_secs {
+            if !self.historical_policy.should_send(is_historical) {
+                // A detecção cairia no período histórico, então o estado que a dispararia é descartado
+                // para não virar um alerta antigo na primeira telemetria ao vivo
+                self.acc_t = 0;
+                return;
+            }
+            self.acc_t = 0;
+            self.last_notif_sent = Some(sim_clock::now_utc());
+            ViolationEpisode::mark_notified(&mut self.episode);
+            let mut detection = serde_json::json!({
+                "dev_id": dev_id.to_owned(),
+                "notif_id": self.notif_id,
+                "duration_in_seconds": self.timing.duration_secs,
+                "renotification": decision == SendDecision::Renotify,
+                "telemetry_timestamp": *telemetry_timestamp,
+                "detection_time": sim_clock::now_utc(),
+                "episode": ViolationEpisode::context(&self.episode),
+                "schedule": schedule_window_context(self.schedule.as_deref(), telemetry_timestamp),
+                "late": is_historical,
+            });
+            // Mantém o nome do limite usado nas detecções de antes ("HUM_MAX" ou "HUM_MIN")
+            detection[self.limit_kind.cond_par()] = self.humidity_limit.into();
+            sink.push(self.limit_kind.notif_path(), detection);
+        } else if !descontinuidade {
+            // O intervalo sem telemetria não conta no tempo acumulado
+            self.acc_t += delta_secs;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app_realtime::notifications::dut::notifs_dut::dut_rules_registry;
+    use crate::app_realtime::notifications::dut::on_dut_telemetry::DutTelemetry;
+    use crate::app_realtime::notifications::rules::test_helpers::*;
+    use crate::app_realtime::notifications::rules::{parse_rules_list, RuleSet};
+    use crate::app_realtime::sim_clock;
+    use chrono::TimeDelta;
+
+    fn humidity_rules(cond_id: &str, cond_pars: serde_json::Value) -> RuleSet<DutTelemetry> {
+        rule_set(
+            dut_rules_registry(),
+            cond_id,
+            cond_pars,
+            &automation_cfg(None, None, None),
+        )
+    }
+
+    /// Uma leitura de umidade a cada "step_secs" a partir das 10:00
+    fn humidity_samples(step_secs: i64, values: &[f64]) -> Vec<DutTelemetry> {
+        let start = ts("2026-01-01 10:00:00");
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| DutTelemetry {
+                timestamp: start + TimeDelta::seconds(step_secs * i as i64),
+                Temperature: None,
+                eCO2: None,
+                Humidity: Some(*value),
+                TVOC: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn humidity_above_max_for_the_duration() {
+        let mut set = humidity_rules(
+            "DUT_HUM >",
+            serde_json::json!({ "HUM_MAX": 70.0, "DURATION_IN_MINUTES": 1 }),
+        );
+        // Acumula 10 s a cada leitura, o alerta sai quando passa de 60 s
+        let detections = run(&mut set, humidity_samples(10, &[80.0; 9]));
+        assert_eq!(detections.len(), 1);
+        let (path, detection) = &detections[0];
+        assert_eq!(*path, "/DUT_HUM/Acima");
+        assert_eq!(detection["HUM_MAX"], 70.0);
+        assert_eq!(detection["telemetry_timestamp"], "2026-01-01T10:01:20");
+        assert_eq!(detection["episode"]["peak_value"], 80.0);
+
+        // O episódio notificado não notifica de novo, e termina quando a umidade normaliza
+        let mut samples = humidity_samples(10, &[0.0; 12]);
+        samples[10].Humidity = Some(85.0);
+        samples[11].Humidity = Some(60.0);
+        let detections = run(&mut set, samples.into_iter().skip(10));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DUT_HUM/Acima/Normalizado");
+        assert_eq!(detections[0].1["peak_value"], 85.0);
+    }
+
+    #[test]
+    fn humidity_below_min_uses_the_min_limit() {
+        let mut set = humidity_rules(
+            "DUT_HUM <",
+            serde_json::json!({ "HUM_MIN": 30.0, "DURATION_IN_MINUTES": 1 }),
+        );
+        // Acima do mínimo não conta
+        assert!(run(&mut set, humidity_samples(10, &[80.0; 9])).is_empty());
+
+        let mut set = humidity_rules(
+            "DUT_HUM <",
+            serde_json::json!({ "HUM_MIN": 30.0, "DURATION_IN_MINUTES": 1 }),
+        );
+        let mut values = [25.0; 9];
+        values[4] = 20.0;
+        let detections = run(&mut set, humidity_samples(10, &values));
+        assert_eq!(detections.len(), 1);
+        let (path, detection) = &detections[0];
+        assert_eq!(*path, "/DUT_HUM/Abaixo");
+        assert_eq!(detection["HUM_MIN"], 30.0);
+        assert!(detection.get("HUM_MAX").is_none());
+        // O pior valor do episódio é o menor
+        assert_eq!(detection["episode"]["peak_value"], 20.0);
+    }
+
+    #[test]
+    fn gaps_longer_than_continuity_do_not_accumulate() {
+        let mut set = humidity_rules(
+            "DUT_HUM >",
+            serde_json::json!({ "HUM_MAX": 70.0, "DURATION_IN_MINUTES": 1 }),
+        );
+        // Uma leitura por minuto com CONTINUITY_SECONDS padrão de 30 s: nenhum intervalo conta
+        assert!(run(&mut set, humidity_samples(60, &[80.0; 10])).is_empty());
+        assert_eq!(set.rules[&1].state_json()["acc_t"], 0);
+    }
+
+    #[test]
+    fn renotify_while_humidity_stays_above() {
+        let mut set = humidity_rules(
+            "DUT_HUM >",
+            serde_json::json!({ "HUM_MAX": 70.0, "DURATION_IN_MINUTES": 1, "RENOTIFY_HOURS": 1 }),
+        );
+        sim_clock::set_simulated_now(1_767_261_600_000);
+        let samples = humidity_samples(10, &[80.0; 20]);
+        let mut samples = samples.into_iter();
+        assert_eq!(run(&mut set, samples.by_ref().take(10)).len(), 1);
+
+        // O episódio continua, mas o RENOTIFY_HOURS ainda não passou
+        assert!(run(&mut set, samples.by_ref().take(5)).is_empty());
+
+        sim_clock::set_simulated_now(1_767_261_600_000 + 61 * 60 * 1000);
+        let detections = run(&mut set, samples);
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DUT_HUM/Acima");
+        assert_eq!(detections[0].1["renotification"], true);
+        sim_clock::set_simulated_now(0);
+    }
+
+    #[test]
+    fn missing_limit_is_rejected() {
+        let notifs = [notif_item(
+            1,
+            "DUT_HUM <",
+            serde_json::json!({ "HUM_MAX": 70.0 }),
+        )];
+        let mut rejected = Vec::new();
+        let rules = parse_rules_list(
+            dut_rules_registry(),
+            &notifs,
+            &automation_cfg(None, None, None),
+            &mut rejected,
+        );
+        assert!(rules.is_empty());
+        assert_eq!(rejected.len(), 1);
+        assert!(rejected[0].1.contains("HUM_MIN"), "{}", rejected[0].1);
+    }
+}
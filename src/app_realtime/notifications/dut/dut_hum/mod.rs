@@ -0,0 +1,3 @@
+pub use dut_hum_fora_limite::{HumLimitKind, NotifDutHumOutOfLimit};
+
+pub mod dut_hum_fora_limite;
@@ -1,17 +1,41 @@
-use crate::{
-    app_realtime::notifications::notifs_cfg::{DutAutomationConfig, NotifsCfgResponse_notif_item},
-    global_vars::GlobalVars,
+use crate::app_realtime::notifications::dut::on_dut_telemetry::DutTelemetry;
+use crate::app_realtime::notifications::episode::{
+    schedule_window_context, PeakKind, ViolationEpisode,
+};
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::{
+    AutomationSchedule, DutAutomationConfig, NotifsCfgResponse_notif_item,
+};
+use crate::app_realtime::notifications::rules::{
+    DetectionSink, NotifRule, RuleSample, StreamDelta,
+};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+// Padrão: 10 minutos além do limite, uma notificação por episódio
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 10 * 60,
+    cooldown_secs: 0,
+    continuity_secs: 30,
+    renotify_secs: None,
 };
-use chrono::{NaiveDateTime, Utc};
-use std::{collections::HashMap, sync::Arc};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct NotifDutTempHighCritic {
     pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    pub last_notif_sent: Option<DateTime<Utc>>,
     pub acc_t: u64,
     pub is_cond_notification: bool,
+    pub episode: Option<ViolationEpisode>,
     pub temperature_limit: f64,
-    pub duration_in_seconds: u64,
+    pub timing: NotifTiming,
+    #[serde(skip)]
+    pub schedule: Option<Arc<AutomationSchedule>>,
 }
 
 impl NotifDutTempHighCritic {
@@ -19,114 +43,174 @@ impl NotifDutTempHighCritic {
     pub fn from_notif_cfg(
         updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
         automation_cfg: &Option<Arc<DutAutomationConfig>>,
-    ) -> Result<Self, &'static str> {
+    ) -> Result<Self, String> {
         let tusemax = automation_cfg.as_ref().and_then(|x| x.tusemax);
         let tusemax = match tusemax {
             Some(x) => x,
             None => {
-                return Err("TUSEMAX é obrigatório para a notificação");
+                return Err("TUSEMAX é obrigatório para a notificação".to_owned());
             }
         };
         // OFFSET_OVER_T_MAX é o offset de temperatura além do limite máximo do ambiente
         let mut offset_over_t_max = updated_notif_data.cond_pars["OFFSET_OVER_T_MAX"]
             .as_f64()
             .unwrap_or(0.0);
-        // DURATION_IN_MINUTES é o tempo em minutos para disparar o alerta
-        let duration_in_minutes = updated_notif_data.cond_pars["DURATION_IN_MINUTES"]
-            .as_u64()
-            .unwrap_or(10);
-
         if offset_over_t_max < 0.0 {
             offset_over_t_max = 0.0;
         }
 
         let temperature_limit = tusemax + offset_over_t_max;
-        let duration_in_seconds = duration_in_minutes * 60;
 
         Ok(Self {
             notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
+            last_notif_sent: None,
             acc_t: 0,
             is_cond_notification: false,
+            episode: None,
             temperature_limit,
-            duration_in_seconds,
+            timing: NotifTiming::from_cond_pars(&updated_notif_data.cond_pars, DEFAULT_TIMING),
+            schedule: automation_cfg.as_ref().map(|x| x.schedule.clone()),
         })
     }
+}
+
+impl NotifRule<DutTelemetry> for NotifDutTempHighCritic {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
 
-    pub fn update_new_notifs_parameters(
-        list_old: &HashMap<u64, Self>,
-        list_new: &mut HashMap<u64, Self>,
-    ) {
-        for (notif_id, updated_notif) in list_new.iter_mut() {
-            updated_notif.update_notif_parameters(list_old.get(&notif_id));
-        }
+    fn as_any(&self) -> &dyn Any {
+        self
     }
 
-    pub fn update_notif_parameters(&mut self, existing: Option<&Self>) {
-        if let Some(existing) = existing {
-            self.acc_t = existing.acc_t;
-            self.is_cond_notification = existing.is_cond_notification;
-        }
+    fn carry_over(&mut self, existing: &dyn NotifRule<DutTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.last_notif_sent = existing.last_notif_sent;
+        self.acc_t = existing.acc_t;
+        self.is_cond_notification = existing.is_cond_notification;
+        self.episode = existing.episode.clone();
     }
 
-    pub async fn on_dut_telemetry(
-        &mut self,
-        telemetry_temperature: f64,
-        // telemetry_timestamp: &DateTime<FixedOffset>,
-        telemetry_timestamp: &NaiveDateTime,
-        delta_secs: u64,
-        prev_temperature: Option<f64>,
-        dev_id: &str,
-        globs: &Arc<GlobalVars>,
-    ) -> Result<(), String> {
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DutTelemetry>, sink: &mut DetectionSink) {
+        let Some(telemetry_temperature) = sample.value("Temperature") else {
+            return;
+        };
+        // Cada notificação pode ter o seu próprio CONTINUITY_SECONDS
+        let Some(StreamDelta {
+            delta_secs,
+            descontinuidade,
+            ..
+        }) = sample.delta("Temperature", self.timing.continuity_secs)
+        else {
+            return;
+        };
+        let telemetry_timestamp = sample.timestamp();
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
         // { label: 'DUT_T.temperaturaAmbiente.estiverAcimaLimiteCritica', value: 'T>T', unit: 'notificacao.offsetLimiteSuperiorTemperatura', unit2: 'notificacao.duraçãoMinimaAcumuladaMin', describe: (val) => !req? `Temperatura de Ambiente acima do limite estabelecido` : 'notificacao.temperaturaAmbienteAcimaDosLimites' },
 
-        // if new_day || descontinuidade {
-        //     self.acc_t = 0;
-        // }
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
 
-        // Se já tiver enviado uma notificação menos de 24 horas atrás, não precisa nem conferir
-        // if let Some(last_notif_sent) = self.last_notif_sent.as_ref() {
-        //     if last_notif_sent.elapsed().as_secs() < 24 * 60 * 60 {
-        //         continue;
-        //     }
-        // }
+        // Sem continuidade a temperatura precisa ficar fora do limite por todo o tempo de novo
+        let prev_temperature = if descontinuidade {
+            if self.acc_t > 0 {
+                // Registra no histórico que o acumulador da notificação vai ser zerado
+                let detail =
+                    serde_json::json!({ "stream": "Temperature", "accumulated_secs": self.acc_t });
+                sink.push_reset(self.notif_id, "descontinuidade", detail);
+            }
+            self.acc_t = 0;
+            None
+        } else {
+            sample.prev_value("Temperature")
+        };
 
         let curr_temp_above = telemetry_temperature > self.temperature_limit;
         let prev_temp_above = prev_temperature
             .map(|t| t > self.temperature_limit)
             .unwrap_or(false);
 
-        if (curr_temp_above && !prev_temp_above) || self.is_cond_notification {
+        // Quando a temperatura volta para o limite, informa o fim do episódio que foi notificado
+        let ended_episode = ViolationEpisode::track(
+            &mut self.episode,
+            curr_temp_above,
+            telemetry_timestamp,
+            telemetry_temperature,
+            PeakKind::Max,
+            self.historical_policy.should_send(is_historical),
+        );
+        if let Some(ended_episode) = ended_episode {
+            sink.push(
+                "/DUT_T/AcimaLimiteCritica/Normalizado",
+                ended_episode.normalized_detection(
+                    dev_id,
+                    self.notif_id,
+                    telemetry_timestamp,
+                    is_historical,
+                ),
+            );
+        }
+
+        // Se estiver dentro do intervalo mínimo entre notificações (COOLDOWN_MINUTES), não precisa nem conferir
+        let persisting = self.episode.as_ref().is_some_and(|x| x.notified);
+        let decision = self
+            .timing
+            .send_decision(self.last_notif_sent.as_ref(), persisting);
+        if decision == SendDecision::Blocked {
+            return;
+        }
+        // RENOTIFY_HOURS: reenvia enquanto o episódio notificado continuar
+        let renotify = decision == SendDecision::Renotify && curr_temp_above;
+
+        if (curr_temp_above && !prev_temp_above) || self.is_cond_notification || renotify {
             if telemetry_temperature <= self.temperature_limit {
                 self.is_cond_notification = false;
                 self.acc_t = 0;
             } else {
                 self.is_cond_notification = true;
-                if self.acc_t > self.duration_in_seconds {
+                if renotify || self.acc_t > self.timing.duration_secs {
+                    if !self.historical_policy.should_send(is_historical) {
+                        // A detecção cairia no período histórico, então o estado que a dispararia é descartado
+                        // para não virar um alerta antigo na primeira telemetria ao vivo
+                        self.is_cond_notification = false;
+                        self.acc_t = 0;
+                        return;
+                    }
                     self.is_cond_notification = false;
                     self.acc_t = 0;
-                    // self.last_notif_sent = Some(Instant::now());
-                    let result = globs
-                        .to_notifs_queue
-                        .send((
-                            "/DUT_T/AcimaLimiteCritica",
+                    self.last_notif_sent = Some(sim_clock::now_utc());
+                    ViolationEpisode::mark_notified(&mut self.episode);
+                    sink.push("/DUT_T/AcimaLimiteCritica",
                             serde_json::json!({
                                 "dev_id": dev_id.to_owned(),
                                 "notif_id": self.notif_id,
                                 "telemetry_timestamp": *telemetry_timestamp,
                                 "temperature_limit": self.temperature_limit,
-                                "duration_in_seconds": self.duration_in_seconds,
-                                "detection_time": Utc::now(),
+                                "duration_in_seconds": self.timing.duration_secs,
+                                "renotification": renotify,
+                                "detection_time": sim_clock::now_utc(),
+                                "episode": ViolationEpisode::context(&self.episode),
+                                "schedule": schedule_window_context(self.schedule.as_deref(), telemetry_timestamp),
+                                "late": is_historical,
                             }),
-                        ))
-                        .await;
-                    result.map_err(|err| crate::log_err("[360]", err)).ok();
+                        );
                 } else {
                     self.acc_t += delta_secs;
                 }
             }
         }
-
-        return Ok(());
     }
 }
@@ -1,130 +1,242 @@
-use crate::{
-    app_realtime::notifications::notifs_cfg::{DutAutomationConfig, NotifsCfgResponse_notif_item},
-    global_vars::GlobalVars,
+use crate::app_realtime::notifications::dut::on_dut_telemetry::DutTelemetry;
+use crate::app_realtime::notifications::episode::{
+    schedule_window_context, PeakKind, ViolationEpisode,
+};
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::{
+    AutomationSchedule, DutAutomationConfig, NotifsCfgResponse_notif_item,
+};
+use crate::app_realtime::notifications::rules::{DetectionSink, NotifRule, RuleSample};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+// Padrão: 10 minutos fora dos limites e no máximo uma notificação a cada 24 horas
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 10 * 60,
+    cooldown_secs: 24 * 60 * 60,
+    continuity_secs: 30,
+    renotify_secs: None,
 };
-use chrono::{NaiveDateTime, Utc};
-use std::{collections::HashMap, sync::Arc};
-use tokio::time::Instant;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct NotifDutTempOutOfBounds {
     pub notif_id: u64,
-    pub last_notif_sent: Option<Instant>,
+    pub historical_policy: HistoricalPolicy,
+    pub last_notif_sent: Option<DateTime<Utc>>,
     pub seconds_above: u64,
     pub seconds_below: u64,
+    pub episode_above: Option<ViolationEpisode>,
+    pub episode_below: Option<ViolationEpisode>,
     pub tusemax: Option<f64>,
     pub tusemin: Option<f64>,
+    pub timing: NotifTiming,
+    #[serde(skip)]
+    pub schedule: Option<Arc<AutomationSchedule>>,
 }
 impl NotifDutTempOutOfBounds {
     // "DUT_T T<>T"
     pub fn from_notif_cfg(
         updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
         automation_cfg: &Option<Arc<DutAutomationConfig>>,
-    ) -> Result<Self, &'static str> {
+    ) -> Result<Self, String> {
         let tusemax = automation_cfg.as_ref().and_then(|x| x.tusemax);
         let tusemin = automation_cfg.as_ref().and_then(|x| x.tusemin);
         if tusemax.is_none() && tusemin.is_none() {
-            return Err("Nenhum limite definido");
+            return Err("Nenhum limite definido".to_owned());
         }
         Ok(Self {
             notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                &updated_notif_data.cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
             seconds_above: 0,
             seconds_below: 0,
+            episode_above: None,
+            episode_below: None,
             last_notif_sent: None,
             tusemax: tusemax.to_owned(),
             tusemin: tusemin.to_owned(),
+            timing: NotifTiming::from_cond_pars(&updated_notif_data.cond_pars, DEFAULT_TIMING),
+            schedule: automation_cfg.as_ref().map(|x| x.schedule.clone()),
         })
     }
+}
 
-    pub fn update_new_notifs_parameters(
-        list_old: &HashMap<u64, Self>,
-        list_new: &mut HashMap<u64, Self>,
-    ) {
-        for (notif_id, updated_notif) in list_new.iter_mut() {
-            updated_notif.update_notif_parameters(list_old.get(&notif_id));
-        }
+impl NotifRule<DutTelemetry> for NotifDutTempOutOfBounds {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
     }
 
-    pub fn update_notif_parameters(&mut self, existing: Option<&Self>) {
-        if let Some(existing) = existing {
-            self.last_notif_sent = existing.last_notif_sent;
-            self.seconds_above = existing.seconds_above;
-            self.seconds_below = existing.seconds_below;
-        }
+    fn as_any(&self) -> &dyn Any {
+        self
     }
 
-    pub async fn on_dut_telemetry(
-        &mut self,
-        // dev_alerts: &mut NotifsDut,
-        telemetry_temperature: f64,
-        telemetry_timestamp: &NaiveDateTime,
-        // telemetry_timestamp: &DateTime<FixedOffset>,
-        delta_secs: u64,
-        new_day: bool,
-        descontinuidade: bool,
-        dev_id: &str,
-        globs: &Arc<GlobalVars>,
-    ) -> Result<(), String> {
-        if new_day || descontinuidade {
+    fn carry_over(&mut self, existing: &dyn NotifRule<DutTelemetry>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.last_notif_sent = existing.last_notif_sent;
+        self.seconds_above = existing.seconds_above;
+        self.seconds_below = existing.seconds_below;
+        self.episode_above = existing.episode_above.clone();
+        self.episode_below = existing.episode_below.clone();
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<DutTelemetry>, sink: &mut DetectionSink) {
+        let Some(telemetry_temperature) = sample.value("Temperature") else {
+            return;
+        };
+        // Cada notificação pode ter o seu próprio CONTINUITY_SECONDS
+        let Some(delta) = sample.delta("Temperature", self.timing.continuity_secs) else {
+            return;
+        };
+        let telemetry_timestamp = sample.timestamp();
+        let is_historical = sample.is_historical;
+        let dev_id = sample.dev_id;
+
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(is_historical) {
+            return;
+        }
+
+        if delta.new_day || delta.descontinuidade {
+            let accumulated_secs = self.seconds_above + self.seconds_below;
+            if accumulated_secs > 0 {
+                // Registra no histórico que os acumuladores da notificação vão ser zerados
+                let reason = if delta.descontinuidade {
+                    "descontinuidade"
+                } else {
+                    "new_day"
+                };
+                let detail = serde_json::json!({ "stream": "Temperature", "accumulated_secs": accumulated_secs });
+                sink.push_reset(self.notif_id, reason, detail);
+            }
             self.seconds_above = 0;
             self.seconds_below = 0;
         }
 
-        // Se já tiver enviado uma notificação menos de 24 horas atrás, não precisa nem conferir
-        if let Some(last_notif_sent) = self.last_notif_sent.as_ref() {
-            if last_notif_sent.elapsed().as_secs() < 24 * 60 * 60 {
-                return Ok(());
-            }
+        // O fim dos episódios notificados é informado mesmo durante o intervalo entre notificações
+        let can_close_notified = self.historical_policy.should_send(is_historical);
+        let ended_above = ViolationEpisode::track(
+            &mut self.episode_above,
+            self.tusemax
+                .is_some_and(|tusemax| telemetry_temperature > tusemax),
+            telemetry_timestamp,
+            telemetry_temperature,
+            PeakKind::Max,
+            can_close_notified,
+        );
+        if let Some(ended_above) = ended_above {
+            sink.push(
+                "/DUT_T/AcimaLimiteAntiga/Normalizado",
+                ended_above.normalized_detection(
+                    dev_id,
+                    self.notif_id,
+                    telemetry_timestamp,
+                    is_historical,
+                ),
+            );
+        }
+        let ended_below = ViolationEpisode::track(
+            &mut self.episode_below,
+            self.tusemin
+                .is_some_and(|tusemin| telemetry_temperature < tusemin),
+            telemetry_timestamp,
+            telemetry_temperature,
+            PeakKind::Min,
+            can_close_notified,
+        );
+        if let Some(ended_below) = ended_below {
+            sink.push(
+                "/DUT_T/AbaixoLimiteAntiga/Normalizado",
+                ended_below.normalized_detection(
+                    dev_id,
+                    self.notif_id,
+                    telemetry_timestamp,
+                    is_historical,
+                ),
+            );
+        }
+
+        // Se estiver dentro do intervalo mínimo entre notificações, não precisa nem conferir
+        let persisting = self.episode_above.as_ref().is_some_and(|x| x.notified)
+            || self.episode_below.as_ref().is_some_and(|x| x.notified);
+        let decision = self
+            .timing
+            .send_decision(self.last_notif_sent.as_ref(), persisting);
+        if decision == SendDecision::Blocked {
+            return;
         }
 
         if let Some(tusemax) = self.tusemax {
             if telemetry_temperature > tusemax {
-                if self.seconds_above > (10 * 60) {
-                    self.last_notif_sent = Some(Instant::now());
-                    let result = globs
-                        .to_notifs_queue
-                        .send((
-                            "/DUT_T/AcimaLimiteAntiga",
-                            serde_json::json!({
-                                "dev_id": dev_id.to_owned(),
-                                "notif_id": self.notif_id,
-                                "TUSEMAX": tusemax,
-                                "telemetry_timestamp": *telemetry_timestamp,
-                                "detection_time": Utc::now(),
-                            }),
-                        ))
-                        .await;
-                    result.map_err(|err| crate::log_err("[216]", err)).ok();
+                if self.seconds_above > self.timing.duration_secs {
+                    if !self.historical_policy.should_send(is_historical) {
+                        // A detecção cairia no período histórico, então o acumulador que a dispararia é descartado
+                        // para não virar um alerta antigo na primeira telemetria ao vivo
+                        self.seconds_above = 0;
+                        return;
+                    }
+                    self.last_notif_sent = Some(sim_clock::now_utc());
+                    ViolationEpisode::mark_notified(&mut self.episode_above);
+                    sink.push(
+                        "/DUT_T/AcimaLimiteAntiga",
+                        serde_json::json!({
+                            "dev_id": dev_id.to_owned(),
+                            "notif_id": self.notif_id,
+                            "TUSEMAX": tusemax,
+                            "episode": ViolationEpisode::context(&self.episode_above),
+                            "schedule": schedule_window_context(self.schedule.as_deref(), telemetry_timestamp),
+                            "renotification": decision == SendDecision::Renotify,
+                            "telemetry_timestamp": *telemetry_timestamp,
+                            "detection_time": sim_clock::now_utc(),
+                            "late": is_historical,
+                        }),
+                    );
                 } else {
-                    self.seconds_above += delta_secs;
+                    self.seconds_above += delta.delta_secs;
                 }
             }
         };
 
         if let Some(tusemin) = self.tusemin {
             if telemetry_temperature < tusemin {
-                if self.seconds_below > (10 * 60) {
-                    self.last_notif_sent = Some(Instant::now());
-                    let result = globs
-                        .to_notifs_queue
-                        .send((
-                            "/DUT_T/AbaixoLimiteAntiga",
-                            serde_json::json!({
-                                "dev_id": dev_id.to_owned(),
-                                "notif_id": self.notif_id,
-                                "TUSEMIN": tusemin,
-                                "telemetry_timestamp": *telemetry_timestamp,
-                                "detection_time": Utc::now(),
-                            }),
-                        ))
-                        .await;
-                    result.map_err(|err| crate::log_err("[244]", err)).ok();
+                if self.seconds_below > self.timing.duration_secs {
+                    if !self.historical_policy.should_send(is_historical) {
+                        // A detecção cairia no período histórico, então o acumulador que a dispararia é descartado
+                        // para não virar um alerta antigo na primeira telemetria ao vivo
+                        self.seconds_below = 0;
+                        return;
+                    }
+                    self.last_notif_sent = Some(sim_clock::now_utc());
+                    ViolationEpisode::mark_notified(&mut self.episode_below);
+                    sink.push(
+                        "/DUT_T/AbaixoLimiteAntiga",
+                        serde_json::json!({
+                            "dev_id": dev_id.to_owned(),
+                            "notif_id": self.notif_id,
+                            "TUSEMIN": tusemin,
+                            "episode": ViolationEpisode::context(&self.episode_below),
+                            "schedule": schedule_window_context(self.schedule.as_deref(), telemetry_timestamp),
+                            "renotification": decision == SendDecision::Renotify,
+                            "telemetry_timestamp": *telemetry_timestamp,
+                            "detection_time": sim_clock::now_utc(),
+                            "late": is_historical,
+                        }),
+                    );
                 } else {
-                    self.seconds_below += delta_secs;
+                    self.seconds_below += delta.delta_secs;
                 }
             }
         };
-
-        return Ok(());
     }
 }
@@ -1,4 +1,5 @@
-pub mod dut_co2;
+pub mod dut_ar;
+pub mod dut_hum;
 pub mod dut_t;
 pub mod notifs_dut;
 pub mod on_dut_telemetry;
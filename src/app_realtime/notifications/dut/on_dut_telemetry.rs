@@ -1,10 +1,9 @@
-use super::dut_co2;
-use super::dut_t;
 use super::notifs_dut::NotifsDut;
+use crate::app_realtime::notifications::rules::RuleTelemetry;
 use crate::global_vars::GlobalVars;
 use crate::helpers::telemetry_payloads::{
     parse_json_props::{get_float_array_optional, get_i16_array_optional},
-    telemetry_formats::{get_json_sampling_time, get_json_timestamp_with_gmt},
+    telemetry_formats::{get_json_sampling_time, get_json_saved_data, get_json_timestamp_with_gmt},
 };
 use chrono::{NaiveDateTime, TimeDelta};
 use std::sync::Arc;
@@ -13,11 +12,13 @@ pub async fn on_dut_telemetry(
     payload_json: &serde_json::Value,
     dev_alerts: &mut NotifsDut,
     dev_id: &str,
+    ts_correction: Option<TimeDelta>,
     globs: &Arc<GlobalVars>,
 ) {
-    // Se a telemetria for histórica acho que posso ignorar.
+    // Telemetria histórica (enviada depois pelo gateway): cada notificação decide como tratar
+    let is_historical = get_json_saved_data(payload_json);
 
-    let telemetry_list = match DutTelemetry::parse_from_json_as_vec(payload_json) {
+    let mut telemetry_list = match DutTelemetry::parse_from_json_as_vec(payload_json) {
         Err(err) => {
             crate::write_to_log_file_v2(
                 "ERROR",
@@ -29,6 +30,13 @@ pub async fn on_dut_telemetry(
         Ok(x) => x,
     };
 
+    // Dispositivo com desvio de relógio: usa o horário corrigido pelo servidor
+    if let Some(ts_correction) = ts_correction {
+        for telemetry in telemetry_list.iter_mut() {
+            telemetry.timestamp -= ts_correction;
+        }
+    }
+
     for telemetry in telemetry_list {
         // O DUT tem que ter um horário de funcionamento definido para o dia do timestamp da telemetria
         let Some(schedule) = dev_alerts.schedule.as_ref() else {
@@ -44,18 +52,21 @@ pub async fn on_dut_telemetry(
             continue;
         }
 
-        // Notificações sobre a temperatura
-        dut_t::on_dut_telemetry(&telemetry, dev_alerts, dev_id, globs).await;
-
-        // Notificações sobre o CO2
-        dut_co2::on_dut_telemetry(&telemetry, dev_alerts, dev_id, globs).await;
+        // Regras registradas no "dut_rules_registry"
+        dev_alerts
+            .rules
+            .on_telemetry(&telemetry, dev_id, is_historical, globs)
+            .await;
     }
 }
 
+#[derive(Debug)]
 pub struct DutTelemetry {
     pub timestamp: NaiveDateTime,
     pub Temperature: Option<f64>,
     pub eCO2: Option<i16>,
+    pub Humidity: Option<f64>,
+    pub TVOC: Option<i16>,
 }
 
 impl DutTelemetry {
@@ -65,11 +76,15 @@ impl DutTelemetry {
 
         let Temperature = get_float_array_optional(&payload_json["Temperature"]);
         let eCO2 = get_i16_array_optional(&payload_json["eCO2"]);
+        let Humidity = get_float_array_optional(&payload_json["Humidity"]);
+        let TVOC = get_i16_array_optional(&payload_json["TVOC"]);
 
         let vec_len = Temperature
             .as_ref()
             .and_then(|v| Some(v.len()))
             .or_else(|| eCO2.as_ref().and_then(|v| Some(v.len())))
+            .or_else(|| Humidity.as_ref().map(|v| v.len()))
+            .or_else(|| TVOC.as_ref().map(|v| v.len()))
             .unwrap_or(0);
 
         let mut vec = Vec::with_capacity(vec_len);
@@ -83,6 +98,8 @@ impl DutTelemetry {
             let telemetry = DutTelemetry {
                 Temperature: Temperature.as_ref().and_then(|x| x.get(i).and_then(|x| *x)),
                 eCO2: eCO2.as_ref().and_then(|x| x.get(i).and_then(|x| *x)),
+                Humidity: Humidity.as_ref().and_then(|x| x.get(i).and_then(|x| *x)),
+                TVOC: TVOC.as_ref().and_then(|x| x.get(i).and_then(|x| *x)),
                 timestamp: telemetry_timestamp,
             };
             vec.push(telemetry);
@@ -90,3 +107,31 @@ impl DutTelemetry {
         Ok(vec)
     }
 }
+
+impl RuleTelemetry for DutTelemetry {
+    fn timestamp(&self) -> &NaiveDateTime {
+        &self.timestamp
+    }
+
+    fn get_field(&self, name: &str) -> Option<f64> {
+        match name {
+            "Temperature" => self.Temperature,
+            "eCO2" => self.eCO2.map(|x| x as f64),
+            "Humidity" => self.Humidity,
+            "TVOC" => self.TVOC.map(|x| x as f64),
+            _ => None,
+        }
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &["Temperature", "eCO2", "Humidity", "TVOC"]
+    }
+
+    fn continuity_secs(field: &str) -> u64 {
+        match field {
+            // Mesmos intervalos usados nas notificações de CO2 e TVOC
+            "eCO2" | "TVOC" => 3 * 60,
+            _ => 30,
+        }
+    }
+}
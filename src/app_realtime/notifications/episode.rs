@@ -0,0 +1,255 @@
+/*
+Episódio de violação de uma notificação: começa quando o valor sai da faixa permitida e termina quando volta.
+Se a detecção do episódio tiver sido enviada, o fim do episódio também é informado ao API-Server
+(caminho ".../Normalizado") para que o chamado possa ser encerrado.
+*/
+
+use super::notifs_cfg::{AutomationSchedule, ProgPermission};
+use super::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+
+// Quantidade de leituras mais recentes do episódio enviadas junto com a detecção
+const EPISODE_LAST_SAMPLES: usize = 10;
+
+#[derive(Debug, Serialize, Clone, Copy)]
+pub enum PeakKind {
+    Max, // O pior valor do episódio é o maior (ex.: temperatura acima do limite)
+    Min, // O pior valor do episódio é o menor (ex.: temperatura abaixo do limite)
+}
+
+/// Estatísticas compactas dos valores de um episódio, enviadas junto com a detecção para dar contexto ao e-mail
+#[derive(Debug, Serialize, Clone)]
+pub struct EpisodeStats {
+    pub min: f64,
+    pub max: f64,
+    pub sum: f64,
+    pub count: u64,
+    pub accumulated_secs: u64, // Tempo entre a primeira e a última leitura do episódio
+    pub last_samples: VecDeque<(NaiveDateTime, f64)>,
+}
+
+impl EpisodeStats {
+    pub fn new(timestamp: &NaiveDateTime, value: f64) -> Self {
+        let mut last_samples = VecDeque::with_capacity(EPISODE_LAST_SAMPLES);
+        last_samples.push_back((*timestamp, value));
+        Self {
+            min: value,
+            max: value,
+            sum: value,
+            count: 1,
+            accumulated_secs: 0,
+            last_samples,
+        }
+    }
+
+    pub fn push(&mut self, timestamp: &NaiveDateTime, value: f64) {
+        if let Some((prev_ts, _)) = self.last_samples.back() {
+            let delta = timestamp.signed_duration_since(*prev_ts).num_seconds();
+            self.accumulated_secs += delta.max(0) as u64;
+        }
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+        self.count += 1;
+        if self.last_samples.len() >= EPISODE_LAST_SAMPLES {
+            self.last_samples.pop_front();
+        }
+        self.last_samples.push_back((*timestamp, value));
+    }
+
+    pub fn avg(&self) -> f64 {
+        self.sum / (self.count.max(1) as f64)
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ViolationEpisode {
+    pub start: NaiveDateTime,
+    pub peak: f64,
+    pub notified: bool,
+    pub stats: EpisodeStats,
+}
+
+impl ViolationEpisode {
+    pub fn new(start: &NaiveDateTime, value: f64) -> Self {
+        Self {
+            start: start.to_owned(),
+            peak: value,
+            notified: false,
+            stats: EpisodeStats::new(start, value),
+        }
+    }
+
+    pub fn update_peak(&mut self, value: f64, peak_kind: PeakKind) {
+        self.peak = match peak_kind {
+            PeakKind::Max => self.peak.max(value),
+            PeakKind::Min => self.peak.min(value),
+        };
+    }
+
+    /// Contexto do episódio enviado nas detecções: início, pior valor, mínimo/máximo/média,
+    /// tempo acumulado e as últimas leituras.
+    pub fn context_json(&self) -> serde_json::Value {
+        let last_samples: Vec<serde_json::Value> = self
+            .stats
+            .last_samples
+            .iter()
+            .map(|(ts, value)| serde_json::json!({ "timestamp": ts, "value": value }))
+            .collect();
+        serde_json::json!({
+            "start_time": self.start,
+            "peak_value": self.peak,
+            "min_value": self.stats.min,
+            "max_value": self.stats.max,
+            "avg_value": self.stats.avg(),
+            "samples_count": self.stats.count,
+            "accumulated_seconds": self.stats.accumulated_secs,
+            "last_samples": last_samples,
+        })
+    }
+
+    /// Contexto do episódio atual (ou null se não tiver episódio aberto)
+    pub fn context(episode: &Option<Self>) -> serde_json::Value {
+        episode
+            .as_ref()
+            .map(|x| x.context_json())
+            .unwrap_or(serde_json::Value::Null)
+    }
+
+    /// Atualiza o episódio da notificação com o valor atual da telemetria.
+    /// Retorna o episódio encerrado quando o valor volta ao normal depois de ter sido notificado.
+    /// O parâmetro "can_close_notified" permite manter o episódio aberto (por exemplo para telemetrias
+    /// históricas que não podem enviar detecções), assim ele é encerrado na próxima telemetria ao vivo.
+    pub fn track(
+        episode: &mut Option<Self>,
+        violating: bool,
+        timestamp: &NaiveDateTime,
+        value: f64,
+        peak_kind: PeakKind,
+        can_close_notified: bool,
+    ) -> Option<Self> {
+        // Telemetria fora de ordem (anterior à última leitura do episódio) não altera o episódio
+        let last_sample_ts = episode
+            .as_ref()
+            .and_then(|x| x.stats.last_samples.back())
+            .map(|(ts, _)| *ts);
+        if last_sample_ts.is_some_and(|last| *timestamp < last) {
+            return None;
+        }
+
+        if violating {
+            match episode.as_mut() {
+                Some(episode) => {
+                    episode.update_peak(value, peak_kind);
+                    episode.stats.push(timestamp, value);
+                }
+                None => {
+                    *episode = Some(Self::new(timestamp, value));
+                }
+            }
+            return None;
+        }
+
+        let notified = episode.as_ref()?.notified;
+        if notified && !can_close_notified {
+            return None;
+        }
+        episode.take().filter(|ended| ended.notified)
+    }
+
+    pub fn mark_notified(episode: &mut Option<Self>) {
+        if let Some(episode) = episode.as_mut() {
+            episode.notified = true;
+        }
+    }
+
+    pub fn normalized_detection(
+        &self,
+        dev_id: &str,
+        notif_id: u64,
+        end: &NaiveDateTime,
+        late: bool,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "dev_id": dev_id.to_owned(),
+            "notif_id": notif_id,
+            "start_time": self.start,
+            "end_time": *end,
+            "peak_value": self.peak,
+            "duration_seconds": end.signed_duration_since(self.start).num_seconds(),
+            "episode": self.context_json(),
+            "detection_time": sim_clock::now_utc(),
+            "late": late,
+        })
+    }
+}
+
+/// Janela da programação do dispositivo que valia no momento da telemetria (ou null se não tiver programação)
+pub fn schedule_window_context(
+    schedule: Option<&AutomationSchedule>,
+    timestamp: &NaiveDateTime,
+) -> serde_json::Value {
+    let Some(prog) = schedule.and_then(|x| x.get_for(&timestamp.date())) else {
+        return serde_json::Value::Null;
+    };
+    let permission = match prog.permission {
+        ProgPermission::Allow => "allow",
+        ProgPermission::Forbid => "forbid",
+        ProgPermission::Ventilation => "ventilation",
+    };
+    serde_json::json!({
+        "permission": permission,
+        "start": prog.start.format("%H:%M").to_string(),
+        "end": prog.end.format("%H:%M").to_string(),
+        "inside_schedule": prog.is_inside_sched(timestamp.time()),
+    })
+}
+
+/// Condição que precisa se manter por um tempo mínimo antes de notificar (ex.: modo manual, relé sem retorno).
+/// Usa o timestamp das telemetrias, então funciona mesmo com dispositivos que só enviam quando algo muda.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct SustainedCondition {
+    pub since: Option<NaiveDateTime>,
+    pub is_notified: bool,
+    // Não é zerado quando a condição termina, o COOLDOWN_MINUTES vale entre uma condição e outra
+    pub last_notif_sent: Option<DateTime<Utc>>,
+}
+
+impl SustainedCondition {
+    /// Retorna há quantos segundos a condição está ativa quando já passou do DURATION_IN_MINUTES e a detecção
+    /// pode ser enviada: ainda não foi notificada e está fora do COOLDOWN_MINUTES, ou é o reenvio do RENOTIFY_HOURS.
+    pub fn update(
+        &mut self,
+        active: bool,
+        timestamp: &NaiveDateTime,
+        timing: &NotifTiming,
+    ) -> Option<(u64, SendDecision)> {
+        // Telemetria fora de ordem (anterior ao início da condição) não altera a condição
+        if self.since.is_some_and(|since| *timestamp < since) {
+            return None;
+        }
+        if !active {
+            self.since = None;
+            self.is_notified = false;
+            return None;
+        }
+        let since = *self.since.get_or_insert(*timestamp);
+        let elapsed_secs = timestamp.signed_duration_since(since).num_seconds().max(0) as u64;
+        if elapsed_secs < timing.duration_secs {
+            return None;
+        }
+        match timing.send_decision(self.last_notif_sent.as_ref(), self.is_notified) {
+            SendDecision::Blocked => None,
+            SendDecision::Send if self.is_notified => None,
+            decision => Some((elapsed_secs, decision)),
+        }
+    }
+
+    pub fn mark_notified(&mut self) {
+        self.is_notified = true;
+        self.last_notif_sent = Some(sim_clock::now_utc());
+    }
+}
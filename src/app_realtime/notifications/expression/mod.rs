@@ -0,0 +1,403 @@
+/*
+Expressões simples sobre os campos da telemetria, usadas nas notificações genéricas ("DUT_EXPR", "DAC_EXPR").
+Exemplos:
+  "Tamb > 35 and Lcmp = 1"
+  "(Temperature < 18 or Temperature > 26) and not Humidity >= 80"
+Operadores de comparação: <, <=, >, >=, = (ou ==), !=
+Operadores lógicos: and (&&), or (||), not (!), com parênteses para agrupar.
+*/
+
+use serde::Serialize;
+
+pub mod notif_expression;
+
+pub use notif_expression::NotifExpression;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub enum Operand {
+    Field(String),
+    Number(f64),
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub enum Expr {
+    Compare(Operand, CompareOp, Operand),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+impl Expr {
+    /// Avalia a expressão. Retorna None se o resultado depender de algum campo que não está presente na amostra.
+    pub fn evaluate(&self, get_field: &dyn Fn(&str) -> Option<f64>) -> Option<bool> {
+        match self {
+            Expr::Compare(left, op, right) => {
+                let left = left.value(get_field)?;
+                let right = right.value(get_field)?;
+                Some(match op {
+                    CompareOp::Lt => left < right,
+                    CompareOp::Le => left <= right,
+                    CompareOp::Gt => left > right,
+                    CompareOp::Ge => left >= right,
+                    CompareOp::Eq => left == right,
+                    CompareOp::Ne => left != right,
+                })
+            }
+            // Um lado já define o resultado mesmo se faltar campo do outro lado
+            Expr::And(left, right) => match (left.evaluate(get_field), right.evaluate(get_field)) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            },
+            Expr::Or(left, right) => match (left.evaluate(get_field), right.evaluate(get_field)) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            },
+            Expr::Not(inner) => Some(!inner.evaluate(get_field)?),
+        }
+    }
+
+    /// Lista (sem repetição) dos campos da telemetria usados na expressão
+    pub fn fields(&self) -> Vec<&str> {
+        let mut list = Vec::new();
+        self.collect_fields(&mut list);
+        list
+    }
+
+    fn collect_fields<'a>(&'a self, list: &mut Vec<&'a str>) {
+        match self {
+            Expr::Compare(left, _, right) => {
+                for operand in [left, right] {
+                    if let Operand::Field(name) = operand {
+                        if !list.contains(&name.as_str()) {
+                            list.push(name);
+                        }
+                    }
+                }
+            }
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                left.collect_fields(list);
+                right.collect_fields(list);
+            }
+            Expr::Not(inner) => inner.collect_fields(list),
+        }
+    }
+}
+
+impl Operand {
+    fn value(&self, get_field: &dyn Fn(&str) -> Option<f64>) -> Option<f64> {
+        match self {
+            Operand::Field(name) => get_field(name),
+            Operand::Number(value) => Some(*value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Op(CompareOp),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            ' ' | '\t' | '\n' | '\r' => {
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            '<' | '>' | '=' | '!' => {
+                let (token, len) = match (c, next) {
+                    ('<', Some('=')) => (Token::Op(CompareOp::Le), 2),
+                    ('>', Some('=')) => (Token::Op(CompareOp::Ge), 2),
+                    ('=', Some('=')) => (Token::Op(CompareOp::Eq), 2),
+                    ('!', Some('=')) => (Token::Op(CompareOp::Ne), 2),
+                    ('<', _) => (Token::Op(CompareOp::Lt), 1),
+                    ('>', _) => (Token::Op(CompareOp::Gt), 1),
+                    ('=', _) => (Token::Op(CompareOp::Eq), 1),
+                    _ => (Token::Not, 1),
+                };
+                tokens.push(token);
+                i += len;
+            }
+            '&' | '|' => {
+                if next != Some(c) {
+                    return Err(format!("Operador inválido na posição {i}"));
+                }
+                tokens.push(if c == '&' { Token::And } else { Token::Or });
+                i += 2;
+            }
+            c if c.is_ascii_digit()
+                || c == '.'
+                || (c == '-' && next.is_some_and(|x| x.is_ascii_digit() || x == '.')) =>
+            {
+                let start = i;
+                i += 1;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let value = text
+                    .parse::<f64>()
+                    .map_err(|_| format!("Número inválido: '{text}'"))?;
+                tokens.push(Token::Number(value));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                tokens.push(match word.to_ascii_lowercase().as_str() {
+                    "and" => Token::And,
+                    "or" => Token::Or,
+                    "not" => Token::Not,
+                    _ => Token::Ident(word),
+                });
+            }
+            c => {
+                return Err(format!("Caractere inválido na expressão: '{c}'"));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_not()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            let right = self.parse_not()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let inner = self.parse_or()?;
+            if self.next() != Some(Token::RParen) {
+                return Err("Parêntese não fechado".to_owned());
+            }
+            return Ok(inner);
+        }
+        let left = self.parse_operand()?;
+        let op = match self.next() {
+            Some(Token::Op(op)) => op,
+            _ => {
+                return Err("Esperado operador de comparação".to_owned());
+            }
+        };
+        let right = self.parse_operand()?;
+        Ok(Expr::Compare(left, op, right))
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand, String> {
+        match self.next() {
+            Some(Token::Ident(name)) => Ok(Operand::Field(name)),
+            Some(Token::Number(value)) => Ok(Operand::Number(value)),
+            _ => Err("Esperado campo da telemetria ou número".to_owned()),
+        }
+    }
+}
+
+/// Faz o parse da expressão e confere se todos os campos usados existem na telemetria
+pub fn parse_expression(text: &str, valid_fields: &[&str]) -> Result<Expr, String> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+    };
+    if parser.tokens.is_empty() {
+        return Err("Expressão vazia".to_owned());
+    }
+    let expr = parser.parse_or()?;
+    if parser.pos < parser.tokens.len() {
+        return Err("Conteúdo inesperado no final da expressão".to_owned());
+    }
+    for field in expr.fields() {
+        if !valid_fields.contains(&field) {
+            return Err(format!("Campo desconhecido na expressão: '{field}'"));
+        }
+    }
+    Ok(expr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELDS: &[&str] = &["Tamb", "Lcmp", "Tsuc", "Humidity"];
+
+    fn eval(text: &str, values: &[(&str, f64)]) -> Option<bool> {
+        let expr = parse_expression(text, FIELDS).unwrap();
+        expr.evaluate(&|name| {
+            values
+                .iter()
+                .find(|(field, _)| *field == name)
+                .map(|(_, value)| *value)
+        })
+    }
+
+    #[test]
+    fn and_has_precedence_over_or() {
+        let expr = parse_expression("Tamb > 35 or Lcmp = 1 and Tsuc < 0", FIELDS).unwrap();
+        assert!(matches!(expr, Expr::Or(_, _)));
+        assert_eq!(
+            eval(
+                "Tamb > 35 or Lcmp = 1 and Tsuc < 0",
+                &[("Tamb", 40.0), ("Lcmp", 0.0), ("Tsuc", 5.0)]
+            ),
+            Some(true)
+        );
+        assert_eq!(
+            eval(
+                "(Tamb > 35 or Lcmp = 1) and Tsuc < 0",
+                &[("Tamb", 40.0), ("Lcmp", 0.0), ("Tsuc", 5.0)]
+            ),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn not_applies_to_the_next_comparison() {
+        let values = [("Tamb", 20.0), ("Humidity", 90.0)];
+        assert_eq!(
+            eval("Tamb < 25 and not Humidity >= 80", &values),
+            Some(false)
+        );
+        assert_eq!(
+            eval("not (Tamb < 25 and Humidity >= 80)", &values),
+            Some(false)
+        );
+        assert_eq!(eval("!Tamb > 25", &values), Some(true));
+        assert_eq!(eval("not not Tamb < 25", &values), Some(true));
+    }
+
+    #[test]
+    fn negative_and_decimal_literals() {
+        assert_eq!(
+            parse_expression("Tsuc < -2.5", FIELDS).unwrap(),
+            Expr::Compare(
+                Operand::Field("Tsuc".to_owned()),
+                CompareOp::Lt,
+                Operand::Number(-2.5)
+            )
+        );
+        assert_eq!(eval("Tsuc <= -.5", &[("Tsuc", -0.5)]), Some(true));
+        assert_eq!(eval("-10 < Tsuc", &[("Tsuc", -12.0)]), Some(false));
+    }
+
+    #[test]
+    fn missing_field_value_is_not_evaluated() {
+        assert_eq!(eval("Tamb > 35 and Lcmp = 1", &[("Tamb", 40.0)]), None);
+        assert_eq!(eval("Tamb > 35 and Lcmp = 1", &[("Lcmp", 1.0)]), None);
+    }
+
+    #[test]
+    fn missing_field_does_not_matter_when_the_other_side_decides() {
+        assert_eq!(eval("Tamb > 35 or Lcmp = 1", &[("Tamb", 40.0)]), Some(true));
+        assert_eq!(eval("Lcmp = 1 or Tamb > 35", &[("Tamb", 40.0)]), Some(true));
+        assert_eq!(eval("Tamb > 35 or Lcmp = 1", &[("Tamb", 20.0)]), None);
+        assert_eq!(
+            eval("Tamb > 35 and Lcmp = 1", &[("Tamb", 20.0)]),
+            Some(false)
+        );
+        assert_eq!(
+            eval("Lcmp = 1 and Tamb > 35", &[("Tamb", 20.0)]),
+            Some(false)
+        );
+        assert_eq!(
+            eval("not (Tamb > 35 and Lcmp = 1)", &[("Tamb", 20.0)]),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn unknown_field_is_rejected() {
+        let err = parse_expression("Tamb > 35 and Psuc > 10", FIELDS).unwrap_err();
+        assert!(err.contains("'Psuc'"), "{err}");
+    }
+
+    #[test]
+    fn malformed_expressions_are_rejected() {
+        for text in [
+            "",
+            "Tamb",
+            "Tamb >",
+            "> 35",
+            "(Tamb > 35",
+            "Tamb > 35)",
+            "Tamb > 35 and",
+            "Tamb > 35 & Lcmp = 1",
+            "Tamb > 35 Lcmp = 1",
+            "Tamb > 1.2.3",
+            "Tamb # 35",
+        ] {
+            assert!(parse_expression(text, FIELDS).is_err(), "{text}");
+        }
+    }
+}
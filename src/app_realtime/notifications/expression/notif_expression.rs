@@ -0,0 +1,358 @@
+use super::{parse_expression, Expr};
+use crate::app_realtime::notifications::historical::HistoricalPolicy;
+use crate::app_realtime::notifications::notifs_cfg::{
+    AutomationSchedule, DutAutomationConfig, NotifsCfgResponse_notif_item,
+};
+use crate::app_realtime::notifications::rules::{
+    DetectionSink, NotifRule, RuleSample, RuleTelemetry,
+};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+// Padrão: notifica assim que a condição for verdadeira, uma notificação por episódio.
+// CONTINUITY_SECONDS 0 usa a continuidade padrão de cada campo da expressão.
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 0,
+    cooldown_secs: 0,
+    continuity_secs: 0,
+    renotify_secs: None,
+};
+
+/// Em que parte da programação do dispositivo a expressão é avaliada
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum ScheduleGate {
+    Any,
+    Inside,
+    Outside,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotifExpression {
+    pub notif_id: u64,
+    pub historical_policy: HistoricalPolicy,
+    pub acc_t: u64,
+    pub is_notified: bool,
+    pub since: Option<NaiveDateTime>,
+    // Não é zerado no fim da condição, o COOLDOWN_MINUTES vale entre uma condição e outra
+    pub last_notif_sent: Option<DateTime<Utc>>,
+
+    pub notif_path: &'static str,
+    pub expression_text: String,
+    pub expression: Expr,
+    pub timing: NotifTiming,
+    pub schedule_gate: ScheduleGate,
+    #[serde(skip)]
+    pub schedule: Option<Arc<AutomationSchedule>>,
+}
+
+impl NotifExpression {
+    // "DUT_EXPR" / "DAC_EXPR" condicaoPersonalizada
+    pub fn from_notif_cfg<T: RuleTelemetry>(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+        automation_cfg: &Option<Arc<DutAutomationConfig>>,
+        notif_path: &'static str,
+    ) -> Result<Self, String> {
+        let cond_pars = &updated_notif_data.cond_pars;
+        // EXPRESSION é a condição sobre os campos da telemetria (ex.: "Tamb > 35 and Lcmp = 1")
+        let Some(expression_text) = cond_pars["EXPRESSION"].as_str() else {
+            return Err("EXPRESSION é obrigatório para a notificação".to_owned());
+        };
+        let expression = parse_expression(expression_text, T::field_names())?;
+        // SCHEDULE limita a avaliação a dentro ("inside") ou fora ("outside") da programação
+        let schedule_gate = match cond_pars["SCHEDULE"].as_str() {
+            None | Some("any") => ScheduleGate::Any,
+            Some("inside") => ScheduleGate::Inside,
+            Some("outside") => ScheduleGate::Outside,
+            Some(gate) => {
+                return Err(format!("SCHEDULE inválido: '{gate}'"));
+            }
+        };
+        let schedule = automation_cfg.as_ref().map(|x| x.schedule.clone());
+        if schedule_gate != ScheduleGate::Any && schedule.is_none() {
+            return Err("A notificação exige que o dispositivo tenha programação".to_owned());
+        }
+
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            historical_policy: HistoricalPolicy::from_cond_pars(
+                cond_pars,
+                HistoricalPolicy::Rebuild,
+            ),
+            acc_t: 0,
+            is_notified: false,
+            since: None,
+            last_notif_sent: None,
+            notif_path,
+            expression_text: expression_text.to_owned(),
+            expression,
+            timing: NotifTiming::from_cond_pars(cond_pars, DEFAULT_TIMING),
+            schedule_gate,
+            schedule,
+        })
+    }
+
+    fn is_schedule_allowed(&self, timestamp: &NaiveDateTime) -> bool {
+        let inside_schedule = || {
+            self.schedule
+                .as_ref()
+                .and_then(|x| x.get_for(&timestamp.date()))
+                .is_some_and(|prog| prog.is_inside_sched(timestamp.time()))
+        };
+        match self.schedule_gate {
+            ScheduleGate::Any => true,
+            ScheduleGate::Inside => inside_schedule(),
+            ScheduleGate::Outside => !inside_schedule(),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.acc_t = 0;
+        self.is_notified = false;
+        self.since = None;
+    }
+}
+
+impl<T: RuleTelemetry + 'static> NotifRule<T> for NotifExpression {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<T>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.last_notif_sent = existing.last_notif_sent;
+        // Se tiver alterado a expressão, os acumuladores anteriores não servem mais
+        if existing.expression != self.expression {
+            return;
+        }
+        self.acc_t = existing.acc_t;
+        self.is_notified = existing.is_notified;
+        self.since = existing.since;
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<T>, sink: &mut DetectionSink) {
+        // Telemetria histórica: a política da notificação define se deve ser avaliada
+        if !self.historical_policy.should_evaluate(sample.is_historical) {
+            return;
+        }
+
+        let telemetry_timestamp = sample.telemetry.timestamp();
+        // Telemetria fora de ordem (anterior ao início da condição) não altera a condição
+        if self.since.is_some_and(|since| *telemetry_timestamp < since) {
+            return;
+        }
+        if !self.is_schedule_allowed(telemetry_timestamp) {
+            self.reset();
+            return;
+        }
+
+        let Some(active) = self
+            .expression
+            .evaluate(&|field| sample.telemetry.get_field(field))
+        else {
+            // Algum campo da expressão não veio nesta amostra
+            return;
+        };
+        if !active {
+            self.reset();
+            return;
+        }
+
+        // O intervalo considerado é o do campo da expressão com a leitura mais antiga
+        let fields = self.expression.fields();
+        let deltas = fields.iter().filter_map(|field| {
+            let continuity_secs = match self.timing.continuity_secs {
+                0 => T::continuity_secs(field),
+                x => x,
+            };
+            sample.delta(field, continuity_secs)
+        });
+        let descontinuidade = deltas.clone().any(|x| x.descontinuidade);
+        let delta_secs = deltas.map(|x| x.delta_secs).max().unwrap_or(0);
+
+        // A condição precisa durar sem interrupção
+        if descontinuidade && self.acc_t > 0 {
+            let detail = serde_json::json!({ "fields": fields, "accumulated_secs": self.acc_t });
+            sink.push_reset(self.notif_id, "descontinuidade", detail);
+        }
+        if descontinuidade || self.since.is_none() {
+            self.acc_t = 0;
+            self.since = Some(*telemetry_timestamp);
+        } else {
+            self.acc_t += delta_secs;
+        }
+
+        // A condição notificada só é enviada de novo no reenvio do RENOTIFY_HOURS
+        let decision = self
+            .timing
+            .send_decision(self.last_notif_sent.as_ref(), self.is_notified);
+        let renotify = decision == SendDecision::Renotify;
+        if self.is_notified && !renotify {
+            return;
+        }
+
+        if !renotify && self.acc_t < self.timing.duration_secs {
+            return;
+        }
+
+        // Dentro do intervalo mínimo entre notificações (COOLDOWN_MINUTES)
+        if decision == SendDecision::Blocked {
+            return;
+        }
+
+        if !self.historical_policy.should_send(sample.is_historical) {
+            // A detecção cairia no período histórico, então fica como notificada
+            // para não virar um alerta antigo na primeira telemetria ao vivo
+            self.is_notified = true;
+            return;
+        }
+        self.is_notified = true;
+        self.last_notif_sent = Some(sim_clock::now_utc());
+
+        let mut values = serde_json::Map::new();
+        for field in fields {
+            values.insert(field.to_owned(), sample.telemetry.get_field(field).into());
+        }
+        sink.push(
+            self.notif_path,
+            serde_json::json!({
+                "dev_id": sample.dev_id.to_owned(),
+                "notif_id": self.notif_id,
+                "expression": self.expression_text,
+                "values": values,
+                "since": self.since,
+                "duration_in_seconds": self.timing.duration_secs,
+                "renotification": renotify,
+                "telemetry_timestamp": *telemetry_timestamp,
+                "detection_time": sim_clock::now_utc(),
+                "late": sample.is_historical,
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app_realtime::notifications::dac::notifs_dac::dac_rules_registry;
+    use crate::app_realtime::notifications::dac::on_dac_telemetry::DacTelemetry;
+    use crate::app_realtime::notifications::dut::notifs_dut::dut_rules_registry;
+    use crate::app_realtime::notifications::rules::parse_rules_list;
+    use crate::app_realtime::notifications::rules::test_helpers::*;
+    use crate::app_realtime::sim_clock;
+    use chrono::TimeDelta;
+
+    fn tamb_sample(timestamp: &str, tamb: f64) -> DacTelemetry {
+        DacTelemetry {
+            timestamp: ts(timestamp),
+            Lcmp: None,
+            Tamb: Some(tamb),
+            Tsuc: None,
+            Tliq: None,
+            Psuc: None,
+            Pliq: None,
+            Tsc: None,
+            Tsh: None,
+            Curr: None,
+        }
+    }
+
+    #[test]
+    fn cooldown_and_renotify_use_the_server_clock() {
+        let mut set = rule_set(
+            dac_rules_registry(),
+            "DAC_EXPR",
+            serde_json::json!({
+                "EXPRESSION": "Tamb > 35",
+                "COOLDOWN_MINUTES": 60,
+                "RENOTIFY_HOURS": 2,
+            }),
+            &None,
+        );
+        sim_clock::set_simulated_now(1_767_261_600_000);
+
+        let detections = run(
+            &mut set,
+            [
+                tamb_sample("2026-01-01 10:00:00", 40.0),
+                tamb_sample("2026-01-01 10:00:10", 40.0),
+            ],
+        );
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DAC_EXPR/Detectado");
+        assert_eq!(detections[0].1["renotification"], false);
+
+        // Nova condição com as telemetrias horas depois, mas dentro do COOLDOWN_MINUTES do servidor
+        let detections = run(
+            &mut set,
+            [
+                tamb_sample("2026-01-01 10:00:20", 20.0),
+                tamb_sample("2026-01-01 13:00:00", 40.0),
+            ],
+        );
+        assert!(detections.is_empty());
+
+        sim_clock::set_simulated_now(1_767_261_600_000 + 61 * 60 * 1000);
+        let detections = run(&mut set, [tamb_sample("2026-01-01 13:00:10", 40.0)]);
+        assert_eq!(detections.len(), 1);
+
+        // A condição continua: reenvia depois do RENOTIFY_HOURS
+        sim_clock::set_simulated_now(1_767_261_600_000 + 182 * 60 * 1000);
+        let detections = run(&mut set, [tamb_sample("2026-01-01 13:00:20", 40.0)]);
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].1["renotification"], true);
+        sim_clock::set_simulated_now(0);
+    }
+
+    #[test]
+    fn duration_counts_from_the_first_active_sample() {
+        let mut set = rule_set(
+            dac_rules_registry(),
+            "DAC_EXPR",
+            serde_json::json!({ "EXPRESSION": "Tamb > 35", "DURATION_IN_MINUTES": 1 }),
+            &None,
+        );
+        let samples = (0..7).map(|i| {
+            let mut sample = tamb_sample("2026-01-01 10:00:00", 40.0);
+            sample.timestamp += TimeDelta::seconds(10 * i);
+            sample
+        });
+        let detections = run(&mut set, samples);
+        assert_eq!(detections.len(), 1);
+        assert_eq!(
+            detections[0].1["telemetry_timestamp"],
+            serde_json::json!(ts("2026-01-01 10:01:00"))
+        );
+    }
+
+    #[test]
+    fn dut_rejects_outside_schedule_gate() {
+        let notifs = [notif_item(
+            1,
+            "DUT_EXPR",
+            serde_json::json!({ "EXPRESSION": "Temperature > 30", "SCHEDULE": "outside" }),
+        )];
+        let mut rejected = Vec::new();
+        let rules = parse_rules_list(
+            dut_rules_registry(),
+            &notifs,
+            &automation_cfg(None, None, Some(("08:00", "18:00"))),
+            &mut rejected,
+        );
+        assert!(rules.is_empty());
+        assert_eq!(rejected.len(), 1);
+        assert!(rejected[0].1.contains("outside"), "{}", rejected[0].1);
+    }
+}
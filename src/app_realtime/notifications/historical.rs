@@ -0,0 +1,63 @@
+/*
+Telemetrias históricas são as que chegam com "saved_data: true" (o gateway guardou os pacotes enquanto
+estava sem conexão e enviou depois). Cada tipo de notificação escolhe o que fazer com elas:
+ - Skip: ignora a telemetria
+ - Rebuild: avalia normalmente para reconstruir os acumuladores, mas não envia detecções
+ - Late: avalia e envia as detecções marcadas como atrasadas ("late": true)
+O tipo de notificação define a política padrão e o COND_PARS['HISTORICAL_POLICY'] pode alterar.
+Telemetrias com timestamp anterior ao último valor processado (ex.: pacotes guardados que chegam depois dos atuais)
+também são tratadas como históricas. Como a continuidade já avançou, elas são avaliadas com delta zero.
+No modo Rebuild a detecção que cairia no período histórico é descartada junto com o estado que a dispararia,
+para não virar um alerta antigo na primeira telemetria ao vivo.
+*/
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum HistoricalPolicy {
+    Skip,
+    Rebuild,
+    Late,
+}
+
+impl HistoricalPolicy {
+    pub fn from_cond_pars(cond_pars: &serde_json::Value, default: HistoricalPolicy) -> Self {
+        match cond_pars["HISTORICAL_POLICY"].as_str() {
+            Some("skip") => HistoricalPolicy::Skip,
+            Some("rebuild") => HistoricalPolicy::Rebuild,
+            Some("late") => HistoricalPolicy::Late,
+            _ => default,
+        }
+    }
+
+    /// Indica se a telemetria deve ser avaliada pela notificação
+    pub fn should_evaluate(&self, is_historical: bool) -> bool {
+        !is_historical || *self != HistoricalPolicy::Skip
+    }
+
+    /// Indica se a detecção pode ser enviada para o API-Server
+    pub fn should_send(&self, is_historical: bool) -> bool {
+        !is_historical || *self == HistoricalPolicy::Late
+    }
+}
+
+/// Posição da telemetria em relação ao último valor processado do mesmo tipo
+pub struct TelemetryOrder {
+    pub out_of_order: bool, // Anterior ao último valor processado, deve ser tratada como histórica
+}
+
+/// Retorna None para pacote repetido (mesmo timestamp do último valor processado), que deve ser descartado.
+/// A telemetria fora de ordem não deve atualizar o último valor processado (ver "RuleSample::delta").
+pub fn check_telemetry_order(
+    curr_ts: &NaiveDateTime,
+    prev_ts: Option<&NaiveDateTime>,
+) -> Option<TelemetryOrder> {
+    match prev_ts {
+        Some(prev) if curr_ts == prev => None,
+        Some(prev) if curr_ts < prev => Some(TelemetryOrder { out_of_order: true }),
+        _ => Some(TelemetryOrder {
+            out_of_order: false,
+        }),
+    }
+}
@@ -0,0 +1,294 @@
+/*
+Histórico das notificações de cada dispositivo, para responder perguntas como "por que não recebi o alerta ontem?"
+sem precisar procurar as linhas NOTIF-DETECTED nos arquivos de log.
+ - Guarda as últimas detecções com o resultado do envio pela fila (enviada, falhou, silenciada, agrupada em incidente)
+ - Guarda também quando os acumuladores das notificações foram zerados (descontinuidade, troca de dia, alteração de config)
+ - Fica limitado a HISTORY_LEN_PER_NOTIF detecções por notificação de cada dispositivo, assim uma notificação que dispara
+   muito não apaga o histórico das outras. Os resets ficam em uma fila separada de RESETS_LEN_PER_DEV eventos por dispositivo,
+   porque são bem mais frequentes que as detecções.
+ - Fica em memória e em "./cache/notifHistory.json"
+*/
+
+use crate::app_realtime::sim_clock;
+use crate::global_vars::GlobalVars;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::{io::ErrorKind, sync::Arc, time::Duration};
+
+const HISTORY_LEN_PER_NOTIF: usize = 50;
+const RESETS_LEN_PER_DEV: usize = 100;
+const CACHE_FILE: &str = "./cache/notifHistory.json";
+const CACHE_FILE_TMP: &str = "./cache/notifHistory-tmp.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryEventKind {
+    Sent,     // Detecção entregue ao API-Server
+    Failed,   // Detecção descartada depois de todas as tentativas de envio
+    Snoozed,  // Detecção só registrada no log por causa de um snooze / janela de manutenção
+    Incident, // Detecção agrupada em um incidente
+    Reset,    // Acumuladores da notificação zerados
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HistoryEvent {
+    pub ts: DateTime<Utc>,
+    pub notif_id: Option<u64>,
+    pub kind: HistoryEventKind,
+    pub notif_path: Option<String>,
+    pub detail: serde_json::Value,
+}
+
+/// Histórico de um dispositivo
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct DevHistory {
+    pub by_notif: HashMap<u64, VecDeque<HistoryEvent>>, // Detecções de cada notif_id (0 se a detecção não tiver notif_id)
+    pub resets: VecDeque<HistoryEvent>,
+}
+
+impl DevHistory {
+    fn events(&self) -> impl Iterator<Item = &HistoryEvent> {
+        self.by_notif.values().flatten().chain(self.resets.iter())
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct NotifHistory {
+    pub by_dev: HashMap<String, DevHistory>,
+}
+
+impl NotifHistory {
+    pub fn push(&mut self, dev_id: &str, event: HistoryEvent) {
+        let dev_history = match self.by_dev.get_mut(dev_id) {
+            Some(x) => x,
+            None => self.by_dev.entry(dev_id.to_owned()).or_default(),
+        };
+        let (list, max_len) = if event.kind == HistoryEventKind::Reset {
+            (&mut dev_history.resets, RESETS_LEN_PER_DEV)
+        } else {
+            let notif_id = event.notif_id.unwrap_or(0);
+            (
+                dev_history.by_notif.entry(notif_id).or_default(),
+                HISTORY_LEN_PER_NOTIF,
+            )
+        };
+        if list.len() >= max_len {
+            list.pop_front();
+        }
+        list.push_back(event);
+    }
+
+    pub fn query(
+        &self,
+        dev_id: Option<&str>,
+        notif_id: Option<u64>,
+        since: Option<&DateTime<Utc>>,
+        until: Option<&DateTime<Utc>>,
+    ) -> Vec<(&str, &HistoryEvent)> {
+        let mut result = Vec::new();
+        for (event_dev_id, dev_history) in self.by_dev.iter() {
+            if dev_id.is_some_and(|x| x != event_dev_id) {
+                continue;
+            }
+            for event in dev_history.events() {
+                if notif_id.is_some() && event.notif_id != notif_id {
+                    continue;
+                }
+                if since.is_some_and(|x| event.ts < *x) || until.is_some_and(|x| event.ts > *x) {
+                    continue;
+                }
+                result.push((event_dev_id.as_str(), event));
+            }
+        }
+        result.sort_by_key(|a| a.1.ts);
+        result
+    }
+}
+
+/// Registra o resultado do envio de uma detecção (ou de todas as detecções agrupadas em um incidente)
+pub async fn record_delivery(
+    globs: &Arc<GlobalVars>,
+    notif_path: &str,
+    detection: &serde_json::Value,
+    kind: HistoryEventKind,
+    detail: serde_json::Value,
+) {
+    let mut history = globs.notif_history.write().await;
+    let now = sim_clock::now_utc();
+    if notif_path.starts_with("/INCIDENT/") {
+        let Some(grouped) = detection["detections"].as_array() else {
+            return;
+        };
+        for grouped_detection in grouped {
+            let Some(dev_id) = grouped_detection["dev_id"].as_str() else {
+                continue;
+            };
+            history.push(
+                dev_id,
+                HistoryEvent {
+                    ts: now,
+                    notif_id: grouped_detection["notif_id"].as_u64(),
+                    kind: HistoryEventKind::Incident,
+                    notif_path: Some(notif_path.to_owned()),
+                    detail: serde_json::json!({
+                        "incident_delivery": kind,
+                        "telemetry_timestamp": grouped_detection["telemetry_timestamp"],
+                    }),
+                },
+            );
+        }
+        return;
+    }
+
+    let Some(dev_id) = detection["dev_id"].as_str() else {
+        return;
+    };
+    let mut detail = detail;
+    detail["telemetry_timestamp"] = detection["telemetry_timestamp"].clone();
+    history.push(
+        dev_id,
+        HistoryEvent {
+            ts: now,
+            notif_id: detection["notif_id"].as_u64(),
+            kind,
+            notif_path: Some(notif_path.to_owned()),
+            detail,
+        },
+    );
+}
+
+/// Registra que os acumuladores de uma notificação foram zerados.
+/// "reason" é "descontinuidade", "new_day" ou "config_change".
+pub async fn record_reset(
+    globs: &Arc<GlobalVars>,
+    dev_id: &str,
+    notif_id: Option<u64>,
+    reason: &'static str,
+    detail: serde_json::Value,
+) {
+    let mut detail = detail;
+    detail["reason"] = reason.into();
+    globs.notif_history.write().await.push(
+        dev_id,
+        HistoryEvent {
+            ts: sim_clock::now_utc(),
+            notif_id,
+            kind: HistoryEventKind::Reset,
+            notif_path: None,
+            detail,
+        },
+    );
+}
+
+async fn dump_to_file(globs: &Arc<GlobalVars>) -> Result<(), String> {
+    let bytes = {
+        let history = globs.notif_history.read().await;
+        serde_json::to_vec(&*history).map_err(|err| format!("[154] {err}"))?
+    };
+    tokio::fs::write(CACHE_FILE_TMP, bytes)
+        .await
+        .map_err(|err| format!("[154] {err}"))?;
+    tokio::fs::rename(CACHE_FILE_TMP, CACHE_FILE)
+        .await
+        .map_err(|err| format!("[154] {err}"))?;
+    Ok(())
+}
+
+async fn load_from_cache(globs: &Arc<GlobalVars>) -> Result<(), String> {
+    let file_contents = match tokio::fs::read_to_string(CACHE_FILE).await {
+        Ok(x) => x,
+        Err(err) => {
+            if err.kind() != ErrorKind::NotFound {
+                crate::write_to_log_file("WARN", &format!("Could not load history cache: {err}"));
+            }
+            return Ok(());
+        }
+    };
+    let loaded: NotifHistory =
+        serde_json::from_str(&file_contents).map_err(|err| format!("[155] {err}"))?;
+
+    // Eventos registrados antes de carregar o cache são mais novos, então ficam no final
+    let mut history = globs.notif_history.write().await;
+    let current = std::mem::replace(&mut *history, loaded);
+    for (dev_id, dev_history) in current.by_dev.into_iter() {
+        let events = dev_history.by_notif.into_values().flatten();
+        for event in events.chain(dev_history.resets) {
+            history.push(&dev_id, event);
+        }
+    }
+    Ok(())
+}
+
+/// Carrega o histórico salvo e de tempo em tempo salva novamente no disco
+pub async fn run_service(globs: Arc<GlobalVars>) -> Result<(), String> {
+    tokio::fs::create_dir_all("./cache")
+        .await
+        .map_err(|err| format!("[5] {err}"))?;
+    if let Err(err) = load_from_cache(&globs).await {
+        crate::write_to_log_file("ERROR", &format!("Error loading history cache: {err}"));
+    }
+
+    loop {
+        tokio::time::sleep(Duration::from_secs(3 * 60)).await;
+        dump_to_file(&globs)
+            .await
+            .map_err(|err| crate::log_err("[243]", err))
+            .ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    fn event(seq: i64, notif_id: u64, kind: HistoryEventKind) -> HistoryEvent {
+        HistoryEvent {
+            ts: DateTime::from_timestamp(1_767_261_600 + seq, 0).unwrap(),
+            notif_id: Some(notif_id),
+            kind,
+            notif_path: None,
+            detail: serde_json::json!({ "seq": seq }),
+        }
+    }
+
+    #[test]
+    fn noisy_notification_does_not_evict_the_others() {
+        let mut history = NotifHistory::default();
+        history.push("DEV001", event(0, 2, HistoryEventKind::Sent));
+        for seq in 1..=(HISTORY_LEN_PER_NOTIF as i64 + 10) {
+            history.push("DEV001", event(seq, 1, HistoryEventKind::Sent));
+        }
+
+        // A notificação 1 ficou só com as detecções mais recentes
+        let events = history.query(Some("DEV001"), Some(1), None, None);
+        assert_eq!(events.len(), HISTORY_LEN_PER_NOTIF);
+        assert_eq!(events[0].1.detail["seq"], 11);
+
+        // A detecção da notificação 2 continua no histórico
+        let events = history.query(Some("DEV001"), Some(2), None, None);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].1.detail["seq"], 0);
+    }
+
+    #[test]
+    fn resets_are_evicted_separately_from_the_detections() {
+        let mut history = NotifHistory::default();
+        history.push("DEV001", event(0, 1, HistoryEventKind::Failed));
+        for seq in 1..=(RESETS_LEN_PER_DEV as i64 + 5) {
+            history.push("DEV001", event(seq, 1, HistoryEventKind::Reset));
+        }
+
+        let events = history.query(Some("DEV001"), Some(1), None, None);
+        assert_eq!(events.len(), RESETS_LEN_PER_DEV + 1);
+        assert_eq!(events[0].1.kind, HistoryEventKind::Failed);
+        assert_eq!(events[1].1.detail["seq"], 6);
+
+        // Filtro por horário considera as duas filas
+        let since = DateTime::from_timestamp(1_767_261_600, 0).unwrap() + TimeDelta::seconds(100);
+        let events = history.query(None, None, Some(&since), None);
+        assert_eq!(events.len(), 6);
+        assert!(history.query(Some("DEV002"), None, None, None).is_empty());
+    }
+}
@@ -0,0 +1,314 @@
+/*
+Agrupamento de detecções em incidentes.
+Quando um gateway, o broker ou a alimentação de uma unidade falha, centenas de dispositivos disparam notificações
+(ou ficam offline) ao mesmo tempo. Para não gerar centenas de e-mails, a fila de envio segura as detecções por
+"INCIDENT_WINDOW_SECONDS" e, se forem correlacionadas, envia um único incidente listando os dispositivos afetados.
+O agrupamento é opcional: com "INCIDENT_WINDOW_SECONDS" igual a 0 (padrão) as detecções são enviadas na hora.
+ - /INCIDENT/MassOffline: mais de "INCIDENT_OFFLINE_PERCENT" dos dispositivos da unidade ficaram offline na janela.
+   Se o API-Server não informar as unidades, a população considerada são os dispositivos da notificação.
+ - /INCIDENT/MassAlarm: a mesma notificação (notif_id e notif_path) disparou em "INCIDENT_MIN_DEVICES" dispositivos na janela.
+   Notificações diferentes (que podem ser de clientes diferentes) nunca são agrupadas no mesmo incidente.
+As detecções que não formarem incidente são enviadas individualmente no final da janela.
+*/
+
+use super::notifs_cfg::NotifsCfgResponse_unit_item;
+use super::send_queue::MsgToQueue;
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+const OFFLINE_NOTIF_PATH: &str = "/DEV/Offline";
+
+/// Populações usadas para calcular o percentual de dispositivos afetados. Atualizado junto com as notificações.
+#[derive(Debug, Default)]
+pub struct IncidentGroups {
+    pub unit_by_dev: HashMap<String, u64>,
+    pub unit_size: HashMap<u64, usize>,
+    pub notif_size: HashMap<u64, usize>,
+}
+
+impl IncidentGroups {
+    pub fn new(units: &[NotifsCfgResponse_unit_item], notifs_size: HashMap<u64, usize>) -> Self {
+        let mut unit_by_dev = HashMap::new();
+        let mut unit_size = HashMap::new();
+        for unit in units.iter() {
+            unit_size.insert(unit.unit_id, unit.dev_ids.len());
+            for dev_id in unit.dev_ids.iter() {
+                unit_by_dev.insert(dev_id.to_owned(), unit.unit_id);
+            }
+        }
+        Self {
+            unit_by_dev,
+            unit_size,
+            notif_size: notifs_size,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum GroupKey {
+    OfflineUnit(u64),
+    OfflineNotif(u64),
+    Alarm(&'static str, u64),
+}
+
+struct Bucket {
+    deadline: DateTime<Utc>, // Horário do servidor (sim_clock), assim o "--replay" fecha as janelas como na captura
+    detections: Vec<MsgToQueue>,
+}
+
+pub struct IncidentGrouper {
+    window: Duration,
+    min_devices: usize,
+    offline_percent: u64,
+    buckets: HashMap<GroupKey, Bucket>,
+}
+
+impl IncidentGrouper {
+    pub fn new(window_secs: u64, min_devices: usize, offline_percent: u64) -> Self {
+        Self {
+            window: Duration::from_secs(window_secs),
+            min_devices,
+            offline_percent,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Horário em que a próxima janela fecha
+    pub fn next_deadline(&self) -> Option<DateTime<Utc>> {
+        self.buckets.values().map(|x| x.deadline).min()
+    }
+
+    /// Segura a detecção na janela de agrupamento. Retorna a detecção de volta se ela deve ser enviada imediatamente.
+    pub fn push(&mut self, msg: MsgToQueue, groups: &IncidentGroups) -> Option<MsgToQueue> {
+        if self.window.is_zero() {
+            return Some(msg);
+        }
+        let (notif_path, detection) = &msg;
+        let Some(dev_id) = detection["dev_id"].as_str() else {
+            return Some(msg);
+        };
+        let key = if *notif_path == OFFLINE_NOTIF_PATH {
+            match (
+                groups.unit_by_dev.get(dev_id),
+                detection["notif_id"].as_u64(),
+            ) {
+                (Some(unit_id), _) => GroupKey::OfflineUnit(*unit_id),
+                (None, Some(notif_id)) => GroupKey::OfflineNotif(notif_id),
+                (None, None) => {
+                    return Some(msg);
+                }
+            }
+        } else {
+            let Some(notif_id) = detection["notif_id"].as_u64() else {
+                return Some(msg);
+            };
+            GroupKey::Alarm(notif_path, notif_id)
+        };
+        let window = TimeDelta::seconds(self.window.as_secs() as i64);
+        let bucket = self.buckets.entry(key).or_insert_with(|| Bucket {
+            deadline: sim_clock::now_utc() + window,
+            detections: Vec::new(),
+        });
+        bucket.detections.push(msg);
+        None
+    }
+
+    /// Fecha as janelas que já expiraram e retorna o que deve ser enviado: um incidente ou as detecções individuais
+    pub fn take_expired(&mut self, groups: &IncidentGroups) -> Vec<MsgToQueue> {
+        let now = sim_clock::now_utc();
+        let expired: Vec<GroupKey> = self
+            .buckets
+            .iter()
+            .filter(|(_, bucket)| bucket.deadline <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        let mut to_send = Vec::new();
+        for key in expired {
+            let Some(bucket) = self.buckets.remove(&key) else {
+                continue;
+            };
+            match self.build_incident(&key, &bucket.detections, groups) {
+                Some(incident) => to_send.push(incident),
+                None => to_send.extend(bucket.detections),
+            }
+        }
+        to_send
+    }
+
+    fn build_incident(
+        &self,
+        key: &GroupKey,
+        detections: &[MsgToQueue],
+        groups: &IncidentGroups,
+    ) -> Option<MsgToQueue> {
+        let dev_ids: HashSet<&str> = detections
+            .iter()
+            .filter_map(|(_, detection)| detection["dev_id"].as_str())
+            .collect();
+        // Um único dispositivo nunca é um incidente
+        if dev_ids.len() < 2 {
+            return None;
+        }
+
+        let (notif_path, incident_type, scope) = match key {
+            GroupKey::OfflineUnit(unit_id) => {
+                let population = *groups.unit_size.get(unit_id)?;
+                if !self.exceeds_offline_percent(dev_ids.len(), population) {
+                    return None;
+                }
+                (
+                    "/INCIDENT/MassOffline",
+                    "MASS_OFFLINE",
+                    serde_json::json!({ "unit_id": unit_id, "devices_total": population }),
+                )
+            }
+            GroupKey::OfflineNotif(notif_id) => {
+                let population = *groups.notif_size.get(notif_id)?;
+                if !self.exceeds_offline_percent(dev_ids.len(), population) {
+                    return None;
+                }
+                (
+                    "/INCIDENT/MassOffline",
+                    "MASS_OFFLINE",
+                    serde_json::json!({ "notif_id": notif_id, "devices_total": population }),
+                )
+            }
+            GroupKey::Alarm(alarm_path, notif_id) => {
+                if dev_ids.len() < self.min_devices {
+                    return None;
+                }
+                (
+                    "/INCIDENT/MassAlarm",
+                    "MASS_ALARM",
+                    serde_json::json!({ "notif_path": alarm_path, "notif_id": notif_id }),
+                )
+            }
+        };
+
+        let notif_ids: HashSet<u64> = detections
+            .iter()
+            .filter_map(|(_, detection)| detection["notif_id"].as_u64())
+            .collect();
+        let mut dev_ids: Vec<&str> = dev_ids.into_iter().collect();
+        dev_ids.sort();
+        let affected: Vec<&serde_json::Value> = detections.iter().map(|(_, x)| x).collect();
+
+        crate::write_to_log_file(
+            "NOTIF-INCIDENT",
+            &format!(
+                "{incident_type} {scope} {} dispositivos, {} detecções agrupadas",
+                dev_ids.len(),
+                detections.len()
+            ),
+        );
+
+        Some((
+            notif_path,
+            serde_json::json!({
+                "incident_type": incident_type,
+                "scope": scope,
+                "notif_ids": notif_ids,
+                "dev_ids": dev_ids,
+                "devices_count": dev_ids.len(),
+                "window_seconds": self.window.as_secs(),
+                "detections": affected,
+                "detection_time": sim_clock::now_utc(),
+            }),
+        ))
+    }
+
+    fn exceeds_offline_percent(&self, offline_count: usize, population: usize) -> bool {
+        population > 0 && (offline_count as u64) * 100 > self.offline_percent * (population as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW_MS: u64 = 1_767_261_600_000;
+
+    fn detection(notif_path: &'static str, dev_id: &str, notif_id: u64) -> MsgToQueue {
+        (
+            notif_path,
+            serde_json::json!({ "dev_id": dev_id, "notif_id": notif_id }),
+        )
+    }
+
+    fn unit_groups() -> IncidentGroups {
+        let mut groups = IncidentGroups::default();
+        for dev_id in ["DEV001", "DEV002", "DEV003", "DEV004"] {
+            groups.unit_by_dev.insert(dev_id.to_owned(), 5);
+        }
+        groups.unit_size.insert(5, 4);
+        groups
+    }
+
+    #[test]
+    fn mass_offline_closes_the_window_on_the_server_clock() {
+        sim_clock::set_simulated_now(NOW_MS);
+        let groups = unit_groups();
+        let mut grouper = IncidentGrouper::new(60, 10, 50);
+
+        for dev_id in ["DEV001", "DEV002", "DEV003"] {
+            let msg = detection(OFFLINE_NOTIF_PATH, dev_id, 1);
+            assert!(grouper.push(msg, &groups).is_none());
+        }
+        assert_eq!(
+            grouper.next_deadline(),
+            DateTime::from_timestamp_millis((NOW_MS + 60_000) as i64)
+        );
+        assert!(grouper.take_expired(&groups).is_empty());
+
+        sim_clock::set_simulated_now(NOW_MS + 60_000);
+        let to_send = grouper.take_expired(&groups);
+        assert_eq!(to_send.len(), 1);
+        assert_eq!(to_send[0].0, "/INCIDENT/MassOffline");
+        assert_eq!(to_send[0].1["devices_count"], 3);
+        assert_eq!(to_send[0].1["scope"]["unit_id"], 5);
+        assert!(grouper.next_deadline().is_none());
+        sim_clock::set_simulated_now(0);
+    }
+
+    #[test]
+    fn detections_below_the_thresholds_are_sent_individually() {
+        sim_clock::set_simulated_now(NOW_MS);
+        let groups = unit_groups();
+        let mut grouper = IncidentGrouper::new(60, 3, 50);
+
+        // 2 de 4 dispositivos offline não passa de 50%
+        for dev_id in ["DEV001", "DEV002"] {
+            assert!(grouper
+                .push(detection(OFFLINE_NOTIF_PATH, dev_id, 1), &groups)
+                .is_none());
+        }
+        // Notificações diferentes não são agrupadas, e cada uma ficou abaixo do INCIDENT_MIN_DEVICES
+        for (dev_id, notif_id) in [("DEV001", 7), ("DEV002", 7), ("DEV003", 8)] {
+            assert!(grouper
+                .push(
+                    detection("/DUT_T/AcimaLimiteCritica", dev_id, notif_id),
+                    &groups
+                )
+                .is_none());
+        }
+
+        sim_clock::set_simulated_now(NOW_MS + 60_000);
+        let to_send = grouper.take_expired(&groups);
+        assert_eq!(to_send.len(), 5);
+        assert!(to_send
+            .iter()
+            .all(|(path, _)| !path.starts_with("/INCIDENT")));
+        sim_clock::set_simulated_now(0);
+    }
+
+    #[test]
+    fn zero_window_sends_immediately() {
+        let mut grouper = IncidentGrouper::new(0, 10, 50);
+        let msg = detection(OFFLINE_NOTIF_PATH, "DEV001", 1);
+        assert!(grouper.push(msg, &unit_groups()).is_some());
+        assert!(grouper.next_deadline().is_none());
+    }
+}
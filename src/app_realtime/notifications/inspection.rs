@@ -1,22 +1,31 @@
+use super::episode::schedule_window_context;
+use super::notifs_cfg::AutomationSchedule;
 use crate::app_realtime::global_vars::GlobalVars;
+use crate::app_realtime::sim_clock;
+use crate::helpers::telemetry_payloads::telemetry_formats::get_json_timestamp_with_gmt;
+use chrono::{Duration, NaiveDateTime};
+use serde::Serialize;
 use serde_json::json;
 use std::sync::{atomic::Ordering, Arc};
 
 /**
  * Funções de debug das notificações
  * curl http://localhost:46136/diel-internal/realtime-rs/inspect_dev_notifications --data '{"device_code":"DEV123"}'
+ * curl http://localhost:46136/diel-internal/realtime-rs/inspect_dev_notifications --data '{"devIds":["DUT1","DUT2"]}'
+ * curl http://localhost:46136/diel-internal/realtime-rs/inspect_dev_notifications --data '{"notifId":1234}'
  */
 
 pub async fn get_dev_notifs_info(
     device_code: &str,
     globs: &Arc<GlobalVars>,
-) -> Result<String, String> {
+) -> Result<serde_json::Value, String> {
     let devs_info = globs.devs_info.read().await;
     let dev_info = match devs_info.get(device_code) {
         None => {
-            return Ok(format!(
-                "Não existe registro do '{device_code}' no 'globs.devs_info'"
-            ));
+            return Ok(json!({
+                "device_code": device_code,
+                "error": format!("Não existe registro do '{device_code}' no 'globs.devs_info'"),
+            }));
         }
         Some(x) => x,
     };
@@ -28,22 +37,88 @@ pub async fn get_dev_notifs_info(
         .as_ref()
         .map(|x| x.telemetry.clone());
 
+    // A programação é resolvida no horário local do dispositivo (GMT da última telemetria)
+    let gmt = last_telemetry
+        .as_ref()
+        .and_then(|x| get_json_timestamp_with_gmt(x).ok())
+        .map(|(_, gmt)| gmt)
+        .unwrap_or(-3);
+    let dev_now = sim_clock::now_utc().naive_utc() + Duration::hours(gmt);
+
     // DUT
     let notifs_dut = dev_info.notifs_dut.read().await;
     let has_notifs_dut = dev_info.has_notifs_dut.load(Ordering::Relaxed);
+    let schedule_dut = notifs_dut.as_ref().and_then(|x| x.schedule.clone());
 
     // DAC
     let notifs_dac = dev_info.notifs_dac.read().await;
     let has_notifs_dac = dev_info.has_notifs_dac.load(Ordering::Relaxed);
 
+    // DMA
+    let notifs_dma = dev_info.notifs_dma.read().await;
+    let has_notifs_dma = dev_info.has_notifs_dma.load(Ordering::Relaxed);
+
+    // DAM
+    let notifs_dam = dev_info.notifs_dam.read().await;
+    let has_notifs_dam = dev_info.has_notifs_dam.load(Ordering::Relaxed);
+    let schedule_dam = notifs_dam.as_ref().and_then(|x| x.schedule.clone());
+
+    // DAL
+    let notifs_dal = dev_info.notifs_dal.read().await;
+    let has_notifs_dal = dev_info.has_notifs_dal.load(Ordering::Relaxed);
+    let schedule_dal = notifs_dal.as_ref().and_then(|x| x.schedule.clone());
+
+    // DMT
+    let notifs_dmt = dev_info.notifs_dmt.read().await;
+    let has_notifs_dmt = dev_info.has_notifs_dmt.load(Ordering::Relaxed);
+    let schedule_dmt = notifs_dmt.as_ref().and_then(|x| x.schedule.clone());
+
+    // Offline
+    let notifs_offline = dev_info.notifs_offline.read().await;
+    let has_notifs_offline = dev_info.has_notifs_offline.load(Ordering::Relaxed);
+    let schedule_offline = notifs_offline.as_ref().and_then(|x| x.schedule.clone());
+
     let resposta = json!({
         "device_code": device_code,
+        "device_time": dev_now,
         "last_telemetry": last_telemetry,
 
         "has_notifs_dut": has_notifs_dut,
-        "notifs_dut": format!("{notifs_dut:?}"),
+        "notifs_dut": to_json(&*notifs_dut)?,
+        "schedule_dut": schedule_today(&schedule_dut, &dev_now),
         "has_notifs_dac": has_notifs_dac,
-        "notifs_dac": format!("{notifs_dac:?}"),
+        "notifs_dac": to_json(&*notifs_dac)?,
+        "has_notifs_dma": has_notifs_dma,
+        "notifs_dma": to_json(&*notifs_dma)?,
+        "has_notifs_dam": has_notifs_dam,
+        "notifs_dam": to_json(&*notifs_dam)?,
+        "schedule_dam": schedule_today(&schedule_dam, &dev_now),
+        "has_notifs_dal": has_notifs_dal,
+        "notifs_dal": to_json(&*notifs_dal)?,
+        "schedule_dal": schedule_today(&schedule_dal, &dev_now),
+        "has_notifs_dmt": has_notifs_dmt,
+        "notifs_dmt": to_json(&*notifs_dmt)?,
+        "schedule_dmt": schedule_today(&schedule_dmt, &dev_now),
+        "has_notifs_offline": has_notifs_offline,
+        "notifs_offline": to_json(&*notifs_offline)?,
+        "schedule_offline": schedule_today(&schedule_offline, &dev_now),
     });
-    return Ok(resposta.to_string());
+    Ok(resposta)
+}
+
+/// Lista os dispositivos que estão sendo monitorados pela notificação
+pub async fn get_devs_with_notif(notif_id: u64, globs: &Arc<GlobalVars>) -> Vec<String> {
+    // O índice de cobertura já guarda em quais dispositivos a notificação foi aceita
+    globs.notif_coverage.read().await.active_dev_ids(notif_id)
+}
+
+fn to_json<T: Serialize>(notifs: &T) -> Result<serde_json::Value, String> {
+    serde_json::to_value(notifs).map_err(|err| format!("[58] {err}"))
+}
+
+fn schedule_today(
+    schedule: &Option<Arc<AutomationSchedule>>,
+    dev_now: &NaiveDateTime,
+) -> serde_json::Value {
+    schedule_window_context(schedule.as_deref(), dev_now)
 }
@@ -1,12 +1,27 @@
+use super::clock_drift;
 use super::global_vars::{DevInfo, GlobalVars};
 use chrono::{NaiveDateTime, TimeDelta};
 use std::sync::{atomic::Ordering, Arc};
 
+pub mod coverage;
 pub mod dac;
+pub mod dal;
+pub mod dam;
+pub mod dma;
+pub mod dmt;
 pub mod dut;
+pub mod episode;
+pub mod expression;
+pub mod historical;
+pub mod history;
+pub mod incidents;
 pub mod inspection;
 pub mod notifs_cfg;
+pub mod offline;
+pub mod rules;
 pub mod send_queue;
+pub mod snooze;
+pub mod timing;
 pub mod update_queue;
 
 pub async fn on_device_telemetry(
@@ -15,19 +30,54 @@ pub async fn on_device_telemetry(
     dev_info: &DevInfo,
     globs: &Arc<GlobalVars>,
 ) {
+    // Se o relógio do dispositivo estiver com desvio, as notificações podem ser avaliadas no horário corrigido
+    let ts_correction = clock_drift::get_notifs_correction(dev_info, globs).await;
+
     // Confere as notificações de DUT
-    if dev_info.has_notifs_dut.load(Ordering::Relaxed) == true {
+    if dev_info.has_notifs_dut.load(Ordering::Relaxed) {
         let mut notifs_dut = dev_info.notifs_dut.write().await;
         if let Some(notifs_dut) = notifs_dut.as_mut() {
-            dut::on_dut_telemetry(&payload_json, notifs_dut, &dev_id, &globs).await;
+            dut::on_dut_telemetry(payload_json, notifs_dut, dev_id, ts_correction, globs).await;
         };
     }
 
     // Confere as notificações de DAC
-    if dev_info.has_notifs_dac.load(Ordering::Relaxed) == true {
+    if dev_info.has_notifs_dac.load(Ordering::Relaxed) {
         let mut notifs_dac = dev_info.notifs_dac.write().await;
         if let Some(notifs_dac) = notifs_dac.as_mut() {
-            dac::on_dac_telemetry(&payload_json, notifs_dac, &dev_id, &globs).await;
+            dac::on_dac_telemetry(payload_json, notifs_dac, dev_id, ts_correction, globs).await;
+        };
+    }
+
+    // Confere as notificações de DMA
+    if dev_info.has_notifs_dma.load(Ordering::Relaxed) {
+        let mut notifs_dma = dev_info.notifs_dma.write().await;
+        if let Some(notifs_dma) = notifs_dma.as_mut() {
+            dma::on_dma_telemetry(payload_json, notifs_dma, dev_id, ts_correction, globs).await;
+        };
+    }
+
+    // Confere as notificações de DAM
+    if dev_info.has_notifs_dam.load(Ordering::Relaxed) {
+        let mut notifs_dam = dev_info.notifs_dam.write().await;
+        if let Some(notifs_dam) = notifs_dam.as_mut() {
+            dam::on_dam_telemetry(payload_json, notifs_dam, dev_id, ts_correction, globs).await;
+        };
+    }
+
+    // Confere as notificações de DAL
+    if dev_info.has_notifs_dal.load(Ordering::Relaxed) {
+        let mut notifs_dal = dev_info.notifs_dal.write().await;
+        if let Some(notifs_dal) = notifs_dal.as_mut() {
+            dal::on_dal_telemetry(payload_json, notifs_dal, dev_id, ts_correction, globs).await;
+        };
+    }
+
+    // Confere as notificações de DMT
+    if dev_info.has_notifs_dmt.load(Ordering::Relaxed) {
+        let mut notifs_dmt = dev_info.notifs_dmt.write().await;
+        if let Some(notifs_dmt) = notifs_dmt.as_mut() {
+            dmt::on_dmt_telemetry(payload_json, notifs_dmt, dev_id, ts_correction, globs).await;
         };
     }
 }
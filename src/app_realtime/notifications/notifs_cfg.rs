@@ -3,14 +3,22 @@ Neste arquivo ficam as funções de solicitar do API-Server as notificações ca
 Aqui também fica o serviço que de tempo em tempo solicita novamente para verificar se teve alterações.
 */
 
+use super::coverage::NotifRejections;
 use super::dac::notifs_dac;
+use super::dal::notifs_dal;
+use super::dam::notifs_dam;
+use super::dma::notifs_dma;
+use super::dmt::notifs_dmt;
 use super::dut::notifs_dut;
+use super::history;
+use super::incidents::IncidentGroups;
+use super::offline::notifs_offline;
 use crate::app_realtime::global_vars::DevInfo;
 use crate::app_realtime::{configs::ConfigFile, global_vars::GlobalVars};
 use chrono::Datelike;
 use chrono::Timelike;
 use chrono::{NaiveDate, NaiveTime, TimeDelta, Weekday};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::HashMap;
 use std::sync::atomic::Ordering;
@@ -125,6 +133,8 @@ async fn make_notifs_http_req(
 struct NotifsCfgResponse {
     notifs_list: Vec<NotifsCfgResponse_notif_item>,
     devs_schedule: Vec<NotifsCfgResponse_sched_item>,
+    #[serde(default)]
+    devs_unit: Vec<NotifsCfgResponse_unit_item>, // Usado para identificar incidentes que afetam a unidade toda
 }
 #[derive(Deserialize, Debug)]
 pub struct NotifsCfgResponse_notif_item {
@@ -138,6 +148,11 @@ pub struct NotifsCfgResponse_notif_item {
     pub dev_ids: Vec<String>,
 }
 #[derive(Deserialize)]
+pub struct NotifsCfgResponse_unit_item {
+    pub unit_id: u64,
+    pub dev_ids: Vec<String>,
+}
+#[derive(Deserialize)]
 pub struct NotifsCfgResponse_sched_item {
     pub dev_ids: Vec<String>,
     #[serde(rename = "TUSEMAX")]
@@ -146,6 +161,8 @@ pub struct NotifsCfgResponse_sched_item {
     pub tusemin: Option<f64>,
     #[serde(rename = "CO2MAX")]
     pub co2max: Option<f64>,
+    #[serde(rename = "TVOCMAX")]
+    pub tvocmax: Option<f64>,
     pub schedule: NotifsCfgResponse_schedule,
 }
 
@@ -174,6 +191,7 @@ pub struct DutAutomationConfig {
     pub tusemax: Option<f64>,
     pub tusemin: Option<f64>,
     pub co2max: Option<f64>,
+    pub tvocmax: Option<f64>,
     pub schedule: Arc<AutomationSchedule>,
 }
 
@@ -190,10 +208,36 @@ pub async fn parse_partial_notifs_update(
     // "aut_cfg_by_dev" faz associação de "dev_id" com os parâmetros de automação de DUT (DutAutomationConfig)
     let aut_cfg_by_dev = get_devices_automation_params(parsed.devs_schedule);
 
+    // Notificações com COND_ID que nenhum tipo de dispositivo trata ficam registradas no log
+    report_unknown_cond_ids(&parsed.notifs_list);
+
+    // Atualiza no índice as notificações alteradas (as que não vieram na resposta foram excluídas)
+    let mut coverage = globs.notif_coverage.write().await;
+    for (notif_id, _) in changed_notifs_list.iter() {
+        coverage.remove_notif(*notif_id);
+    }
+    coverage.set_notifs(&parsed.notifs_list, false);
+    drop(coverage);
+
     // Interpreta a lista de notificações
     // "notifs_by_dev" faz associação de "dev_id" com a lista de todas as notificações monitorando ele
     let notifs_by_dev = get_notifs_by_each_device(parsed.notifs_list);
 
+    // Registra no histórico dos dispositivos a alteração de config, os acumuladores podem ter sido zerados
+    for (dev_id, updated_dev_notifs) in notifs_by_dev.iter() {
+        for notif in updated_dev_notifs.iter() {
+            if !changed_notifs_list
+                .iter()
+                .any(|(x, _)| *x == notif.notif_id)
+            {
+                continue;
+            }
+            let detail = json!({ "cond_id": notif.cond_id });
+            history::record_reset(globs, dev_id, Some(notif.notif_id), "config_change", detail)
+                .await;
+        }
+    }
+
     let mut dev_info = globs.devs_info.write().await;
 
     for (notif_id, removed_dev_ids) in changed_notifs_list.iter() {
@@ -204,27 +248,114 @@ pub async fn parse_partial_notifs_update(
             let Some(dev_info) = dev_info.get_mut(dev_id) else {
                 continue;
             };
+            let detail = json!({ "removed": true });
+            history::record_reset(globs, dev_id, Some(*notif_id), "config_change", detail).await;
             if let Some(notifs_dac) = dev_info.notifs_dac.write().await.as_mut() {
                 notifs_dac.remove_notif_id(*notif_id);
             }
             if let Some(notifs_dut) = dev_info.notifs_dut.write().await.as_mut() {
                 notifs_dut.remove_notif_id(*notif_id);
             }
+            if let Some(notifs_dma) = dev_info.notifs_dma.write().await.as_mut() {
+                notifs_dma.remove_notif_id(*notif_id);
+            }
+            if let Some(notifs_dam) = dev_info.notifs_dam.write().await.as_mut() {
+                notifs_dam.remove_notif_id(*notif_id);
+            }
+            if let Some(notifs_dal) = dev_info.notifs_dal.write().await.as_mut() {
+                notifs_dal.remove_notif_id(*notif_id);
+            }
+            if let Some(notifs_dmt) = dev_info.notifs_dmt.write().await.as_mut() {
+                notifs_dmt.remove_notif_id(*notif_id);
+            }
+            if let Some(notifs_offline) = dev_info.notifs_offline.write().await.as_mut() {
+                notifs_offline.remove_notif_id(*notif_id);
+            }
         }
     }
 
+    let mut dev_checks = Vec::new();
     for (dev_id, updated_dev_notifs) in notifs_by_dev.iter() {
         let Some(dev_info) = dev_info.get_mut(dev_id) else {
             continue;
         };
+        let mut rejected = NotifRejections::new();
 
         // Programação associada ao dispositivo
         let updated_dev_sched = aut_cfg_by_dev.get(dev_id).map(|x| x.clone());
 
-        notifs_dut::update_notifs_dut(dev_info, &Some(updated_dev_notifs), updated_dev_sched, true)
-            .await;
+        notifs_dut::update_notifs_dut(
+            dev_info,
+            &Some(updated_dev_notifs),
+            updated_dev_sched.clone(),
+            true,
+            &mut rejected,
+        )
+        .await;
+
+        notifs_offline::update_notifs_offline(
+            dev_info,
+            &Some(updated_dev_notifs),
+            updated_dev_sched.clone(),
+            true,
+            &mut rejected,
+        )
+        .await;
+
+        notifs_dam::update_notifs_dam(
+            dev_info,
+            &Some(updated_dev_notifs),
+            updated_dev_sched.clone(),
+            true,
+            &mut rejected,
+        )
+        .await;
+
+        notifs_dal::update_notifs_dal(
+            dev_info,
+            &Some(updated_dev_notifs),
+            updated_dev_sched.clone(),
+            true,
+            &mut rejected,
+        )
+        .await;
+
+        notifs_dac::update_notifs_dac(
+            dev_info,
+            &Some(updated_dev_notifs),
+            updated_dev_sched.clone(),
+            true,
+            &mut rejected,
+        )
+        .await;
+
+        notifs_dmt::update_notifs_dmt(
+            dev_info,
+            &Some(updated_dev_notifs),
+            updated_dev_sched.clone(),
+            true,
+            &mut rejected,
+        )
+        .await;
+
+        notifs_dma::update_notifs_dma(
+            dev_info,
+            &Some(updated_dev_notifs),
+            updated_dev_sched,
+            true,
+            &mut rejected,
+        )
+        .await;
+
+        dev_checks.push((dev_id, rejected));
+    }
+    drop(dev_info);
 
-        notifs_dac::update_notifs_dac(dev_info, &Some(updated_dev_notifs), true).await;
+    let mut coverage = globs.notif_coverage.write().await;
+    for (dev_id, rejected) in dev_checks.iter() {
+        if let Some(updated_dev_notifs) = notifs_by_dev.get(*dev_id) {
+            coverage.record_dev_check(dev_id, updated_dev_notifs, rejected);
+        }
     }
 
     return Ok(());
@@ -242,11 +373,30 @@ pub async fn parse_complete_notifs_update(
     // "aut_cfg_by_dev" faz associação de "dev_id" com os parâmetros de automação de DUT (DutAutomationConfig)
     let aut_cfg_by_dev = get_devices_automation_params(parsed.devs_schedule);
 
+    // Notificações com COND_ID que nenhum tipo de dispositivo trata ficam registradas no log
+    report_unknown_cond_ids(&parsed.notifs_list);
+
+    // Atualiza as populações (unidades e notificações) usadas para identificar incidentes
+    let notifs_size = parsed
+        .notifs_list
+        .iter()
+        .map(|x| (x.notif_id, x.dev_ids.len()))
+        .collect();
+    *globs.incident_groups.write().await = IncidentGroups::new(&parsed.devs_unit, notifs_size);
+
+    // Índice "notif_id -> dispositivos", com a situação da notificação em cada dispositivo
+    globs
+        .notif_coverage
+        .write()
+        .await
+        .set_notifs(&parsed.notifs_list, true);
+
     // Interpreta a lista de notificações
     // "notifs_by_dev" faz associação de "dev_id" com a lista de todas as notificações monitorando ele
     let notifs_by_dev = get_notifs_by_each_device(parsed.notifs_list);
 
     // Atualiza o globs.devs_info com as novas configurações
+    let mut dev_checks = Vec::new();
     let mut devs_info = globs.devs_info.write().await;
     for (dev_id, dev_info) in devs_info.iter_mut() {
         // Lista de todas as notificações monitorando este dispositivo
@@ -256,7 +406,24 @@ pub async fn parse_complete_notifs_update(
         let updated_dev_sched = aut_cfg_by_dev.get(dev_id).map(|x| x.clone());
 
         // Ajusta no "dev_info" (do "globs") a lista de notificações associadas ao dispositivo
-        update_all_device_notifs(dev_info, &device_full_notif_list, updated_dev_sched).await;
+        let mut rejected = NotifRejections::new();
+        update_all_device_notifs(
+            dev_info,
+            &device_full_notif_list,
+            updated_dev_sched,
+            &mut rejected,
+        )
+        .await;
+
+        if let Some(device_full_notif_list) = device_full_notif_list {
+            dev_checks.push((dev_id.to_owned(), device_full_notif_list, rejected));
+        }
+    }
+    drop(devs_info);
+
+    let mut coverage = globs.notif_coverage.write().await;
+    for (dev_id, device_full_notif_list, rejected) in dev_checks.iter() {
+        coverage.record_dev_check(dev_id, device_full_notif_list, rejected);
     }
 
     return Ok(());
@@ -279,6 +446,7 @@ fn get_devices_automation_params(
             tusemax: new_sched.tusemax,
             tusemin: new_sched.tusemin,
             co2max: new_sched.co2max,
+            tvocmax: new_sched.tvocmax,
             schedule: Arc::new(parsed_sched),
         });
 
@@ -290,6 +458,31 @@ fn get_devices_automation_params(
     aut_cfg_by_dev
 }
 
+/// Indica se algum tipo de dispositivo sabe tratar o COND_ID
+pub fn is_known_cond_id(cond_id: &str) -> bool {
+    notifs_dut::is_dut_cond_id(cond_id)
+        || notifs_dac::is_dac_cond_id(cond_id)
+        || notifs_dma::is_dma_cond_id(cond_id)
+        || notifs_dam::is_dam_cond_id(cond_id)
+        || notifs_dal::is_dal_cond_id(cond_id)
+        || notifs_dmt::is_dmt_cond_id(cond_id)
+        || notifs_offline::is_offline_cond_id(cond_id)
+}
+
+fn report_unknown_cond_ids(notifs_list: &[NotifsCfgResponse_notif_item]) {
+    for notif in notifs_list.iter() {
+        if !is_known_cond_id(&notif.cond_id) {
+            crate::write_to_log_file(
+                "WARN",
+                &format!(
+                    "[151] COND_ID desconhecido: '{}' (notif_id {})",
+                    notif.cond_id, notif.notif_id
+                ),
+            );
+        }
+    }
+}
+
 fn get_notifs_by_each_device(
     notifs_list: Vec<NotifsCfgResponse_notif_item>,
 ) -> HashMap<String, Vec<Arc<NotifsCfgResponse_notif_item>>> {
@@ -314,12 +507,77 @@ async fn update_all_device_notifs(
     dev_info: &mut DevInfo,
     updated_dev_notifs: &Option<&Vec<Arc<NotifsCfgResponse_notif_item>>>,
     updated_dev_sched: Option<Arc<DutAutomationConfig>>,
+    rejected: &mut NotifRejections,
 ) {
     // A função "update_notifs_dut" vai atualizar o "dev_info.notifs_dut" com os dados de "dut_notifs"
-    notifs_dut::update_notifs_dut(dev_info, updated_dev_notifs, updated_dev_sched, false).await;
+    notifs_dut::update_notifs_dut(
+        dev_info,
+        updated_dev_notifs,
+        updated_dev_sched.clone(),
+        false,
+        rejected,
+    )
+    .await;
+
+    // A função "update_notifs_offline" vai atualizar o "dev_info.notifs_offline" com os dados de "offline_notifs"
+    notifs_offline::update_notifs_offline(
+        dev_info,
+        updated_dev_notifs,
+        updated_dev_sched.clone(),
+        false,
+        rejected,
+    )
+    .await;
+
+    // A função "update_notifs_dam" vai atualizar o "dev_info.notifs_dam" com os dados de "dam_notifs"
+    notifs_dam::update_notifs_dam(
+        dev_info,
+        updated_dev_notifs,
+        updated_dev_sched.clone(),
+        false,
+        rejected,
+    )
+    .await;
+
+    // A função "update_notifs_dal" vai atualizar o "dev_info.notifs_dal" com os dados de "dal_notifs"
+    notifs_dal::update_notifs_dal(
+        dev_info,
+        updated_dev_notifs,
+        updated_dev_sched.clone(),
+        false,
+        rejected,
+    )
+    .await;
 
     // A função "update_notifs_dut" vai atualizar o "dev_info.notifs_dut" com os dados de "dut_notifs"
-    notifs_dac::update_notifs_dac(dev_info, updated_dev_notifs, false).await;
+    notifs_dac::update_notifs_dac(
+        dev_info,
+        updated_dev_notifs,
+        updated_dev_sched.clone(),
+        false,
+        rejected,
+    )
+    .await;
+
+    // A função "update_notifs_dma" vai atualizar o "dev_info.notifs_dma" com os dados de "dma_notifs"
+    notifs_dma::update_notifs_dma(
+        dev_info,
+        updated_dev_notifs,
+        updated_dev_sched.clone(),
+        false,
+        rejected,
+    )
+    .await;
+
+    // A função "update_notifs_dmt" vai atualizar o "dev_info.notifs_dmt" com os dados de "dmt_notifs"
+    notifs_dmt::update_notifs_dmt(
+        dev_info,
+        updated_dev_notifs,
+        updated_dev_sched,
+        false,
+        rejected,
+    )
+    .await;
 }
 
 fn parse_cfg_schedule(schedule: NotifsCfgResponse_schedule) -> Result<AutomationSchedule, String> {
@@ -353,14 +611,14 @@ fn parse_cfg_schedule(schedule: NotifsCfgResponse_schedule) -> Result<Automation
     Ok(parsed_prog)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub enum ProgPermission {
     Allow,
     Forbid,
     Ventilation,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct DayProg {
     pub permission: ProgPermission,
     pub start: NaiveTime, // '00:00'
@@ -380,7 +638,7 @@ impl DayProg {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct AutomationSchedule {
     pub by_day: HashMap<String, DayProg>, // 'mon', 'tue', '2024-12-25'
 }
@@ -0,0 +1,74 @@
+/*
+A notificação de dispositivo offline é disparada pela falta de telemetria, então não dá para verificar
+no "on_device_telemetry". Esta tarefa confere periodicamente o "last_timestamp" de cada dispositivo.
+*/
+
+use super::dev_offline::OfflineCheck;
+use crate::app_realtime::global_vars::{DevInfo, GlobalVars};
+use crate::app_realtime::sim_clock;
+use crate::helpers::telemetry_payloads::telemetry_formats::get_json_timestamp_with_gmt;
+use chrono::{DateTime, NaiveDateTime, TimeDelta};
+use std::sync::{atomic::Ordering, Arc};
+use std::time::Duration;
+
+pub async fn run_service(globs: Arc<GlobalVars>) {
+    // Enquanto o realtime não estiver rodando há tempo suficiente, o "last_timestamp" pode ser do cache
+    // e não quer dizer que o dispositivo está offline. Por isso o tempo sem mensagens é contado a partir daqui.
+    let service_start = sim_clock::now_millis();
+    loop {
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        check_all_devices(&globs, sim_clock::now_millis(), service_start).await;
+    }
+}
+
+pub async fn check_all_devices(globs: &Arc<GlobalVars>, now_millis: u64, service_start: u64) {
+    let devs_info = globs.devs_info.read().await;
+    for (dev_id, dev_info) in devs_info.iter() {
+        if dev_info.has_notifs_offline.load(Ordering::Relaxed) {
+            check_device(dev_id, dev_info, now_millis, service_start, globs).await;
+        }
+    }
+}
+
+async fn check_device(
+    dev_id: &str,
+    dev_info: &DevInfo,
+    now_millis: u64,
+    service_start: u64,
+    globs: &Arc<GlobalVars>,
+) {
+    let last_timestamp = dev_info.last_timestamp.load(Ordering::Relaxed);
+    let silent_secs = now_millis.saturating_sub(last_timestamp.max(service_start)) / 1000;
+
+    // A programação é definida no horário local do dispositivo
+    let gmt = dev_info
+        .last_telemetry
+        .read()
+        .await
+        .as_ref()
+        .and_then(|x| get_json_timestamp_with_gmt(&x.telemetry).ok())
+        .map(|(_, gmt)| gmt)
+        .unwrap_or(-3);
+    let Some(local_now) = get_local_time(now_millis, gmt) else {
+        return;
+    };
+
+    let mut notifs_offline = dev_info.notifs_offline.write().await;
+    let Some(notifs_offline) = notifs_offline.as_mut() else {
+        return;
+    };
+    let check = OfflineCheck {
+        local_now,
+        silent_secs,
+        last_timestamp,
+    };
+    notifs_offline
+        .rules
+        .on_telemetry(&check, dev_id, false, globs)
+        .await;
+}
+
+fn get_local_time(now_millis: u64, gmt: i64) -> Option<NaiveDateTime> {
+    let now_utc = DateTime::from_timestamp_millis(now_millis as i64)?.naive_utc();
+    now_utc.checked_add_signed(TimeDelta::hours(gmt))
+}
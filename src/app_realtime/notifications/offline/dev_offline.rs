@@ -0,0 +1,287 @@
+use crate::app_realtime::notifications::notifs_cfg::{
+    AutomationSchedule, DutAutomationConfig, NotifsCfgResponse_notif_item,
+};
+use crate::app_realtime::notifications::rules::{
+    DetectionSink, NotifRule, RuleSample, RuleTelemetry,
+};
+use crate::app_realtime::notifications::timing::{NotifTiming, SendDecision};
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Serialize;
+use std::any::Any;
+use std::sync::Arc;
+
+/// Verificação periódica do tempo sem mensagens do dispositivo (ver "check_offline")
+#[derive(Debug)]
+pub struct OfflineCheck {
+    pub local_now: NaiveDateTime, // Horário atual no fuso do dispositivo
+    pub silent_secs: u64,         // Segundos desde a última mensagem do dispositivo
+    pub last_timestamp: u64,      // Timestamp do servidor da última mensagem do dispositivo
+}
+
+impl RuleTelemetry for OfflineCheck {
+    fn timestamp(&self) -> &NaiveDateTime {
+        &self.local_now
+    }
+
+    // A regra usa o tempo sem mensagens, não tem campos de telemetria
+    fn get_field(&self, _name: &str) -> Option<f64> {
+        None
+    }
+
+    fn field_names() -> &'static [&'static str] {
+        &[]
+    }
+
+    fn continuity_secs(_field: &str) -> u64 {
+        60
+    }
+}
+
+// Padrão: 30 minutos sem mensagens, uma notificação por vez que o dispositivo fica offline
+// (o tempo é configurado pelo OFFLINE_MINUTES no lugar do DURATION_IN_MINUTES, e o CONTINUITY_SECONDS não é usado)
+const DEFAULT_TIMING: NotifTiming = NotifTiming {
+    duration_secs: 30 * 60,
+    cooldown_secs: 0,
+    continuity_secs: 0,
+    renotify_secs: None,
+};
+
+#[derive(Debug, Serialize)]
+pub struct NotifDevOffline {
+    pub notif_id: u64,
+    pub timing: NotifTiming,
+    pub only_in_schedule: bool,
+    pub is_offline_notified: bool,
+    pub last_notif_sent: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    pub schedule: Option<Arc<AutomationSchedule>>,
+}
+
+impl NotifDevOffline {
+    // "OFFLINE >"
+    pub fn from_notif_cfg(
+        updated_notif_data: &Arc<NotifsCfgResponse_notif_item>,
+        automation_cfg: &Option<Arc<DutAutomationConfig>>,
+    ) -> Result<Self, String> {
+        let mut timing = NotifTiming::from_cond_pars(&updated_notif_data.cond_pars, DEFAULT_TIMING);
+        // OFFLINE_MINUTES é o tempo sem telemetria para considerar o dispositivo offline
+        if let Some(offline_minutes) = updated_notif_data.cond_pars["OFFLINE_MINUTES"].as_u64() {
+            timing.duration_secs = offline_minutes * 60;
+        }
+        // ONLY_IN_SCHEDULE indica que só deve notificar dentro do horário de funcionamento
+        let only_in_schedule = updated_notif_data.cond_pars["ONLY_IN_SCHEDULE"]
+            .as_bool()
+            .unwrap_or(false);
+
+        if timing.duration_secs == 0 {
+            return Err("OFFLINE_MINUTES inválido".to_owned());
+        }
+        if only_in_schedule && automation_cfg.is_none() {
+            return Err("ONLY_IN_SCHEDULE exige que o dispositivo tenha programação".to_owned());
+        }
+
+        Ok(Self {
+            notif_id: updated_notif_data.notif_id,
+            timing,
+            only_in_schedule,
+            is_offline_notified: false,
+            last_notif_sent: None,
+            schedule: automation_cfg.as_ref().map(|x| x.schedule.clone()),
+        })
+    }
+}
+
+impl NotifRule<OfflineCheck> for NotifDevOffline {
+    fn notif_id(&self) -> u64 {
+        self.notif_id
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn carry_over(&mut self, existing: &dyn NotifRule<OfflineCheck>) {
+        let Some(existing) = existing.as_any().downcast_ref::<Self>() else {
+            return;
+        };
+        self.is_offline_notified = existing.is_offline_notified;
+        self.last_notif_sent = existing.last_notif_sent;
+    }
+
+    fn state_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or(serde_json::Value::Null)
+    }
+
+    // O horário local pode voltar com a troca de fuso do dispositivo, mas o tempo sem mensagens continua valendo
+    fn evaluates_out_of_order(&self) -> bool {
+        true
+    }
+
+    fn evaluate(&mut self, sample: &RuleSample<OfflineCheck>, sink: &mut DetectionSink) {
+        let OfflineCheck {
+            local_now,
+            silent_secs,
+            last_timestamp,
+        } = sample.telemetry;
+        let dev_id = sample.dev_id;
+        let last_message = DateTime::from_timestamp_millis(*last_timestamp as i64);
+
+        if *silent_secs < self.timing.duration_secs {
+            if self.is_offline_notified {
+                // O dispositivo voltou a enviar mensagens depois de ter sido notificado como offline
+                self.is_offline_notified = false;
+                sink.push(
+                    "/DEV/Online",
+                    serde_json::json!({
+                        "dev_id": dev_id.to_owned(),
+                        "notif_id": self.notif_id,
+                        "last_message": last_message,
+                        "detection_time": sim_clock::now_utc(),
+                    }),
+                );
+            }
+            return;
+        }
+
+        // Continuando offline, só notifica de novo no reenvio do RENOTIFY_HOURS
+        let decision = self
+            .timing
+            .send_decision(self.last_notif_sent.as_ref(), self.is_offline_notified);
+        if decision == SendDecision::Blocked
+            || (self.is_offline_notified && decision == SendDecision::Send)
+        {
+            return;
+        }
+
+        if self.only_in_schedule {
+            let inside_schedule = self
+                .schedule
+                .as_ref()
+                .and_then(|schedule| schedule.get_for(&local_now.date()))
+                .map(|current| current.is_inside_sched(local_now.time()))
+                .unwrap_or(false);
+            if !inside_schedule {
+                return;
+            }
+        }
+
+        self.is_offline_notified = true;
+        self.last_notif_sent = Some(sim_clock::now_utc());
+        sink.push(
+            "/DEV/Offline",
+            serde_json::json!({
+                "dev_id": dev_id.to_owned(),
+                "notif_id": self.notif_id,
+                "last_message": last_message,
+                "offline_limit_secs": self.timing.duration_secs,
+                "renotification": decision == SendDecision::Renotify,
+                "detection_time": sim_clock::now_utc(),
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OfflineCheck;
+    use crate::app_realtime::notifications::offline::notifs_offline::offline_rules_registry;
+    use crate::app_realtime::notifications::rules::test_helpers::*;
+    use crate::app_realtime::sim_clock;
+
+    // 2026-01-01 10:00:00 UTC
+    const NOW_MS: u64 = 1_767_261_600_000;
+
+    fn check(local_now: &str, silent_secs: u64) -> OfflineCheck {
+        OfflineCheck {
+            local_now: ts(local_now),
+            silent_secs,
+            last_timestamp: NOW_MS - silent_secs * 1000,
+        }
+    }
+
+    #[test]
+    fn offline_once_and_back_online() {
+        let mut set = rule_set(
+            offline_rules_registry(),
+            "OFFLINE >",
+            serde_json::json!({ "OFFLINE_MINUTES": 10 }),
+            &None,
+        );
+        sim_clock::set_simulated_now(NOW_MS);
+
+        let detections = run(&mut set, [check("2026-01-01 07:00:00", 9 * 60)]);
+        assert!(detections.is_empty());
+
+        // Só uma notificação enquanto continuar offline
+        let detections = run(
+            &mut set,
+            [
+                check("2026-01-01 07:01:00", 10 * 60),
+                check("2026-01-01 07:30:00", 40 * 60),
+            ],
+        );
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DEV/Offline");
+        assert_eq!(detections[0].1["offline_limit_secs"], 600);
+        assert_eq!(detections[0].1["renotification"], false);
+
+        let detections = run(&mut set, [check("2026-01-01 07:31:00", 10)]);
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DEV/Online");
+        assert_eq!(set.rules[&1].state_json()["is_offline_notified"], false);
+        sim_clock::set_simulated_now(0);
+    }
+
+    #[test]
+    fn renotify_while_still_offline_uses_the_server_clock() {
+        let mut set = rule_set(
+            offline_rules_registry(),
+            "OFFLINE >",
+            serde_json::json!({ "OFFLINE_MINUTES": 10, "RENOTIFY_HOURS": 1 }),
+            &None,
+        );
+        sim_clock::set_simulated_now(NOW_MS);
+        let detections = run(&mut set, [check("2026-01-01 07:00:00", 10 * 60)]);
+        assert_eq!(detections.len(), 1);
+
+        sim_clock::set_simulated_now(NOW_MS + 59 * 60 * 1000);
+        let detections = run(&mut set, [check("2026-01-01 07:59:00", 69 * 60)]);
+        assert!(detections.is_empty());
+
+        sim_clock::set_simulated_now(NOW_MS + 60 * 60 * 1000);
+        let detections = run(&mut set, [check("2026-01-01 08:00:00", 70 * 60)]);
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DEV/Offline");
+        assert_eq!(detections[0].1["renotification"], true);
+        sim_clock::set_simulated_now(0);
+    }
+
+    #[test]
+    fn only_in_schedule_waits_for_the_schedule_start() {
+        let mut set = rule_set(
+            offline_rules_registry(),
+            "OFFLINE >",
+            serde_json::json!({ "OFFLINE_MINUTES": 10, "ONLY_IN_SCHEDULE": true }),
+            &automation_cfg(None, None, Some(("08:00", "18:00"))),
+        );
+        let detections = run(&mut set, [check("2026-01-01 07:50:00", 60 * 60)]);
+        assert!(detections.is_empty());
+        assert_eq!(set.rules[&1].state_json()["is_offline_notified"], false);
+
+        let detections = run(&mut set, [check("2026-01-01 08:00:00", 70 * 60)]);
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].0, "/DEV/Offline");
+    }
+
+    #[test]
+    fn only_in_schedule_requires_a_schedule() {
+        let notifs = [notif_item(
+            1,
+            "OFFLINE >",
+            serde_json::json!({ "OFFLINE_MINUTES": 10, "ONLY_IN_SCHEDULE": true }),
+        )];
+        let err = super::NotifDevOffline::from_notif_cfg(&notifs[0], &None).unwrap_err();
+        assert!(err.contains("ONLY_IN_SCHEDULE"));
+    }
+}
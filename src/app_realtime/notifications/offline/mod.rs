@@ -0,0 +1,6 @@
+pub mod check_offline;
+pub mod dev_offline;
+pub mod notifs_offline;
+
+pub use check_offline::run_service;
+pub use notifs_offline::NotifsOffline;
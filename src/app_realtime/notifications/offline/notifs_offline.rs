@@ -0,0 +1,92 @@
+use super::dev_offline::{NotifDevOffline, OfflineCheck};
+use crate::app_realtime::global_vars::DevInfo;
+use crate::app_realtime::notifications::coverage::NotifRejections;
+use crate::app_realtime::notifications::notifs_cfg::{
+    AutomationSchedule, DutAutomationConfig, NotifsCfgResponse_notif_item,
+};
+use crate::app_realtime::notifications::rules::{parse_rules_list, RuleRegistry, RuleSet};
+use serde::Serialize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::OnceLock;
+
+/// Regras de notificação de dispositivo offline (ver notifications/rules.rs)
+pub fn offline_rules_registry() -> &'static RuleRegistry<OfflineCheck> {
+    static REGISTRY: OnceLock<RuleRegistry<OfflineCheck>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut registry = RuleRegistry::new();
+        registry.register("OFFLINE >", |notif, automation_cfg| {
+            Ok(Box::new(NotifDevOffline::from_notif_cfg(
+                notif,
+                automation_cfg,
+            )?))
+        });
+        registry
+    })
+}
+
+pub fn is_offline_cond_id(cond_id: &str) -> bool {
+    offline_rules_registry().contains(cond_id)
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotifsOffline {
+    pub rules: RuleSet<OfflineCheck>,
+    #[serde(skip)]
+    pub schedule: Option<Arc<AutomationSchedule>>,
+}
+impl NotifsOffline {
+    pub fn remove_notif_id(&mut self, notif_id: u64) {
+        self.rules.remove_notif_id(notif_id);
+    }
+}
+
+pub async fn update_notifs_offline(
+    dev_info: &mut DevInfo,
+    updated_dev_notifs: &Option<&Vec<Arc<NotifsCfgResponse_notif_item>>>,
+    updated_dev_sched: Option<Arc<DutAutomationConfig>>,
+    // O parâmetro "partial_update" indica se a lista "updated_dev_notifs" é completa ou parcial.
+    // Se for parcial, vamos atualizar as notificações que estiverem na lista sem mexer nas outras.
+    // Se for completa, vamos remover as notificações que não estiverem na lista.
+    partial_update: bool,
+    // Notificações recusadas pelo "from_notif_cfg" (ex.: falta TUSEMAX na programação)
+    rejected: &mut NotifRejections,
+) {
+    // Interpreta a resposta do API-Server (faz parse do JSON)
+    let rules = updated_dev_notifs
+        .map(|notifs| {
+            parse_rules_list(
+                offline_rules_registry(),
+                notifs,
+                &updated_dev_sched,
+                rejected,
+            )
+        })
+        .unwrap_or_default();
+
+    if rules.is_empty() {
+        if !partial_update {
+            // Já que não tem nenhuma notificação monitorando o dispositivo, limpa a lista
+            dev_info.has_notifs_offline.store(false, Ordering::Relaxed);
+            *dev_info.notifs_offline.write().await = None;
+        }
+        return;
+    }
+
+    let schedule = updated_dev_sched.map(|x| x.schedule.clone());
+
+    // Pega o "dev_info.notifs_offline" em modo "write" para atualizar
+    let mut notifs_offline = dev_info.notifs_offline.write().await;
+
+    if let Some(existente) = notifs_offline.as_mut() {
+        existente.schedule = schedule;
+        existente.rules.update_rules(rules, partial_update);
+    } else {
+        *notifs_offline = Some(NotifsOffline {
+            rules: RuleSet::new(rules),
+            schedule,
+        });
+    };
+
+    dev_info.has_notifs_offline.store(true, Ordering::Relaxed);
+}
@@ -0,0 +1,469 @@
+/*
+Regras de notificação plugáveis.
+Cada tipo de notificação implementa o trait "NotifRule" e é registrado no registro da família do
+dispositivo (ex.: "dut_rules_registry"), sem mexer no código de dispatch:
+ - a construção é feita pela fábrica registrada para o COND_ID
+ - o estado da regra anterior com o mesmo notif_id é copiado com "carry_over"
+ - a avaliação recebe a telemetria já decodificada e coloca as detecções no "DetectionSink"
+*/
+
+use super::coverage::NotifRejections;
+use super::get_telemetry_delta;
+use super::historical::check_telemetry_order;
+use super::history;
+use super::notifs_cfg::{DutAutomationConfig, NotifsCfgResponse_notif_item};
+use crate::global_vars::GlobalVars;
+use chrono::NaiveDateTime;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::any::Any;
+use std::fmt::Debug;
+use std::{collections::HashMap, sync::Arc};
+
+/// Telemetria decodificada de uma família de dispositivos que pode ser avaliada pelas regras
+pub trait RuleTelemetry {
+    fn timestamp(&self) -> &NaiveDateTime;
+    /// Valor numérico de um campo da telemetria pelo nome usado no payload (ex.: "Temperature")
+    fn get_field(&self, name: &str) -> Option<f64>;
+    /// Nomes dos campos que podem ser usados nas regras
+    fn field_names() -> &'static [&'static str];
+    /// Intervalo máximo em segundos entre duas leituras do campo para considerar que não houve descontinuidade
+    fn continuity_secs(field: &str) -> u64;
+}
+
+/// Intervalo desde a leitura anterior do mesmo campo (ver "get_telemetry_delta")
+#[derive(Debug, Clone, Copy)]
+pub struct StreamDelta {
+    pub delta_secs: u64,
+    pub descontinuidade: bool,
+    pub new_day: bool,
+}
+
+impl StreamDelta {
+    pub fn new(
+        curr_ts: &NaiveDateTime,
+        prev_ts: Option<&NaiveDateTime>,
+        continuity_secs: u64,
+    ) -> Self {
+        let (delta_secs, descontinuidade, new_day) =
+            get_telemetry_delta(curr_ts, prev_ts, continuity_secs);
+        Self {
+            delta_secs,
+            descontinuidade,
+            new_day,
+        }
+    }
+}
+
+/// Última leitura processada de um campo da telemetria
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct FieldReading {
+    pub timestamp: NaiveDateTime,
+    pub value: f64,
+}
+
+/// Contexto de uma amostra de telemetria entregue às regras
+pub struct RuleSample<'a, T> {
+    pub telemetry: &'a T,
+    pub dev_id: &'a str,
+    pub is_historical: bool,
+    // Mais antiga que a última amostra processada (ver "check_telemetry_order")
+    pub out_of_order: bool,
+    // Leitura anterior dos campos presentes nesta amostra. Fica vazio para amostras fora de ordem.
+    prev_by_field: HashMap<&'static str, FieldReading>,
+}
+
+impl<'a, T: RuleTelemetry> RuleSample<'a, T> {
+    pub fn timestamp(&self) -> &NaiveDateTime {
+        self.telemetry.timestamp()
+    }
+
+    pub fn value(&self, field: &str) -> Option<f64> {
+        self.telemetry.get_field(field)
+    }
+
+    /// Valor do campo na leitura anterior. Não é conhecido para as amostras fora de ordem.
+    pub fn prev_value(&self, field: &str) -> Option<f64> {
+        self.prev_by_field.get(field).map(|reading| reading.value)
+    }
+
+    /// Intervalo desde a leitura anterior do campo, None se o campo não veio nesta amostra.
+    /// Uma amostra fora de ordem usa o próprio timestamp como referência, então não acumula tempo.
+    pub fn delta(&self, field: &str, continuity_secs: u64) -> Option<StreamDelta> {
+        self.value(field)?;
+        let curr_ts = self.timestamp();
+        let prev_ts = if self.out_of_order {
+            Some(curr_ts)
+        } else {
+            self.prev_by_field
+                .get(field)
+                .map(|reading| &reading.timestamp)
+        };
+        Some(StreamDelta::new(curr_ts, prev_ts, continuity_secs))
+    }
+}
+
+/// Detecções geradas durante a avaliação de uma amostra, enviadas para a fila depois que todas as regras rodaram.
+/// Também guarda os acumuladores zerados, registrados no histórico junto com o envio.
+#[derive(Default)]
+pub struct DetectionSink {
+    pub detections: Vec<(&'static str, serde_json::Value)>,
+    pub resets: Vec<(u64, &'static str, serde_json::Value)>, // (notif_id, reason, detail)
+}
+
+impl DetectionSink {
+    pub fn push(&mut self, notif_path: &'static str, detection: serde_json::Value) {
+        self.detections.push((notif_path, detection));
+    }
+
+    /// Ver "history::record_reset"
+    pub fn push_reset(&mut self, notif_id: u64, reason: &'static str, detail: serde_json::Value) {
+        self.resets.push((notif_id, reason, detail));
+    }
+
+    pub async fn flush(&mut self, dev_id: &str, globs: &Arc<GlobalVars>) {
+        for (notif_id, reason, detail) in self.resets.drain(..) {
+            history::record_reset(globs, dev_id, Some(notif_id), reason, detail).await;
+        }
+        for detection in self.detections.drain(..) {
+            let result = globs.to_notifs_queue.send(detection).await;
+            result.map_err(|err| crate::log_err("[240]", err)).ok();
+        }
+    }
+}
+
+pub trait NotifRule<T>: Debug + Send + Sync {
+    fn notif_id(&self) -> u64;
+    fn as_any(&self) -> &dyn Any;
+    /// Copia o estado (acumuladores, episódio atual, ...) da regra que estava configurada com o mesmo notif_id
+    fn carry_over(&mut self, existing: &dyn NotifRule<T>);
+    fn evaluate(&mut self, sample: &RuleSample<T>, sink: &mut DetectionSink);
+    /// Se a regra também avalia as amostras fora de ordem (como históricas).
+    /// Por padrão não avalia: uma leitura antiga não pode alterar os acumuladores e episódios ao vivo.
+    fn evaluates_out_of_order(&self) -> bool {
+        false
+    }
+    /// Estado da regra em JSON, usado na inspeção das notificações do dispositivo
+    fn state_json(&self) -> serde_json::Value;
+}
+
+pub type RuleFactory<T> = fn(
+    &Arc<NotifsCfgResponse_notif_item>,
+    &Option<Arc<DutAutomationConfig>>,
+) -> Result<Box<dyn NotifRule<T>>, String>;
+
+/// Associa os COND_IDs às fábricas das regras de uma família de dispositivos
+pub struct RuleRegistry<T> {
+    factories: HashMap<&'static str, RuleFactory<T>>,
+}
+
+impl<T> RuleRegistry<T> {
+    pub fn new() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, cond_id: &'static str, factory: RuleFactory<T>) {
+        self.factories.insert(cond_id, factory);
+    }
+
+    pub fn contains(&self, cond_id: &str) -> bool {
+        self.factories.contains_key(cond_id)
+    }
+
+    /// Retorna None se o COND_ID não for de uma regra registrada
+    pub fn build(
+        &self,
+        notif: &Arc<NotifsCfgResponse_notif_item>,
+        automation_cfg: &Option<Arc<DutAutomationConfig>>,
+    ) -> Option<Result<Box<dyn NotifRule<T>>, String>> {
+        let factory = self.factories.get(notif.cond_id.as_str())?;
+        Some(factory(notif, automation_cfg))
+    }
+}
+
+/// Regras configuradas para um dispositivo
+#[derive(Debug)]
+pub struct RuleSet<T> {
+    pub rules: HashMap<u64, Box<dyn NotifRule<T>>>,
+    pub last_timestamp: Option<NaiveDateTime>,
+    pub last_by_field: HashMap<&'static str, FieldReading>,
+}
+
+impl<T> Serialize for RuleSet<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let rules: HashMap<u64, serde_json::Value> = self
+            .rules
+            .iter()
+            .map(|(notif_id, rule)| (*notif_id, rule.state_json()))
+            .collect();
+        let mut state = serializer.serialize_struct("RuleSet", 3)?;
+        state.serialize_field("rules", &rules)?;
+        state.serialize_field("last_timestamp", &self.last_timestamp)?;
+        state.serialize_field("last_by_field", &self.last_by_field)?;
+        state.end()
+    }
+}
+
+impl<T: RuleTelemetry> RuleSet<T> {
+    pub fn new(rules: HashMap<u64, Box<dyn NotifRule<T>>>) -> Self {
+        Self {
+            rules,
+            last_timestamp: None,
+            last_by_field: HashMap::new(),
+        }
+    }
+
+    pub fn remove_notif_id(&mut self, notif_id: u64) {
+        self.rules.remove(&notif_id);
+    }
+
+    pub fn update_rules(
+        &mut self,
+        mut new_rules: HashMap<u64, Box<dyn NotifRule<T>>>,
+        partial_update: bool,
+    ) {
+        for (notif_id, new_rule) in new_rules.iter_mut() {
+            if let Some(existing) = self.rules.get(notif_id) {
+                new_rule.carry_over(existing.as_ref());
+            }
+        }
+        if partial_update {
+            self.rules.extend(new_rules);
+        } else {
+            self.rules = new_rules;
+        }
+    }
+
+    pub async fn on_telemetry(
+        &mut self,
+        telemetry: &T,
+        dev_id: &str,
+        is_historical: bool,
+        globs: &Arc<GlobalVars>,
+    ) {
+        let mut sink = self.evaluate(telemetry, dev_id, is_historical);
+        sink.flush(dev_id, globs).await;
+    }
+
+    /// Avalia todas as regras com a amostra e retorna as detecções sem enviar
+    pub fn evaluate(&mut self, telemetry: &T, dev_id: &str, is_historical: bool) -> DetectionSink {
+        let mut sink = DetectionSink::default();
+        if self.rules.is_empty() {
+            return sink;
+        }
+
+        let telemetry_timestamp = telemetry.timestamp();
+        let Some(order) = check_telemetry_order(telemetry_timestamp, self.last_timestamp.as_ref())
+        else {
+            // Pacote repetido, já foi processado
+            return sink;
+        };
+        // Valor mais antigo que o último processado: é histórico e não altera as leituras anteriores
+        let is_historical = is_historical || order.out_of_order;
+
+        let mut prev_by_field = HashMap::new();
+        if !order.out_of_order {
+            self.last_timestamp = Some(*telemetry_timestamp);
+            for field in T::field_names() {
+                let Some(value) = telemetry.get_field(field) else {
+                    continue;
+                };
+                let reading = FieldReading {
+                    timestamp: *telemetry_timestamp,
+                    value,
+                };
+                if let Some(prev) = self.last_by_field.insert(*field, reading) {
+                    prev_by_field.insert(*field, prev);
+                }
+            }
+        }
+
+        let sample = RuleSample {
+            telemetry,
+            dev_id,
+            is_historical,
+            out_of_order: order.out_of_order,
+            prev_by_field,
+        };
+        for (_, rule) in self.rules.iter_mut() {
+            if order.out_of_order && !rule.evaluates_out_of_order() {
+                continue;
+            }
+            rule.evaluate(&sample, &mut sink);
+        }
+        sink
+    }
+}
+
+/// Cria as regras registradas para os COND_IDs da lista. As que não forem do registro são ignoradas.
+pub fn parse_rules_list<T>(
+    registry: &RuleRegistry<T>,
+    updated_dev_notifs: &[Arc<NotifsCfgResponse_notif_item>],
+    updated_dev_sched: &Option<Arc<DutAutomationConfig>>,
+    rejected: &mut NotifRejections,
+) -> HashMap<u64, Box<dyn NotifRule<T>>> {
+    let mut rules = HashMap::new();
+    for notif in updated_dev_notifs.iter() {
+        match registry.build(notif, updated_dev_sched) {
+            Some(Ok(rule)) => {
+                rules.insert(rule.notif_id(), rule);
+            }
+            Some(Err(err)) => {
+                crate::write_to_log_file(
+                    "WARN",
+                    &format!(
+                        "[150] notif_id {} ({}) inválida: {err}",
+                        notif.notif_id, notif.cond_id
+                    ),
+                );
+                rejected.push((notif.notif_id, err));
+            }
+            None => {}
+        }
+    }
+    rules
+}
+
+/// Funções usadas nos testes das regras
+#[cfg(test)]
+pub mod test_helpers {
+    use super::*;
+    use crate::app_realtime::notifications::notifs_cfg::{
+        AutomationSchedule, DayProg, ProgPermission,
+    };
+    use chrono::NaiveTime;
+
+    pub const DEV_ID: &str = "DEV001";
+
+    /// Horário no formato "2026-01-01 10:00:00"
+    pub fn ts(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    /// Programação com os limites de temperatura e o horário de funcionamento ("HH:MM", "HH:MM") todos os dias
+    pub fn automation_cfg(
+        tusemax: Option<f64>,
+        tusemin: Option<f64>,
+        allow: Option<(&str, &str)>,
+    ) -> Option<Arc<DutAutomationConfig>> {
+        let mut by_day = HashMap::new();
+        if let Some((start, end)) = allow {
+            for day in ["mon", "tue", "wed", "thu", "fri", "sat", "sun"] {
+                let prog = DayProg {
+                    permission: ProgPermission::Allow,
+                    start: NaiveTime::parse_from_str(start, "%H:%M").unwrap(),
+                    end: NaiveTime::parse_from_str(end, "%H:%M").unwrap(),
+                };
+                by_day.insert(day.to_owned(), prog);
+            }
+        }
+        Some(Arc::new(DutAutomationConfig {
+            tusemax,
+            tusemin,
+            co2max: None,
+            tvocmax: None,
+            schedule: Arc::new(AutomationSchedule { by_day }),
+        }))
+    }
+
+    pub fn notif_item(
+        notif_id: u64,
+        cond_id: &str,
+        cond_pars: serde_json::Value,
+    ) -> Arc<NotifsCfgResponse_notif_item> {
+        Arc::new(NotifsCfgResponse_notif_item {
+            notif_id,
+            cond_id: cond_id.to_owned(),
+            cond_pars,
+            dev_ids: vec![DEV_ID.to_owned()],
+        })
+    }
+
+    /// Regras configuradas com uma notificação (notif_id 1), que precisa ser aceita pelo "from_notif_cfg"
+    pub fn rule_set<T: RuleTelemetry>(
+        registry: &RuleRegistry<T>,
+        cond_id: &str,
+        cond_pars: serde_json::Value,
+        automation_cfg: &Option<Arc<DutAutomationConfig>>,
+    ) -> RuleSet<T> {
+        let notifs = [notif_item(1, cond_id, cond_pars)];
+        let mut rejected = NotifRejections::new();
+        let rules = parse_rules_list(registry, &notifs, automation_cfg, &mut rejected);
+        assert_eq!(rejected, vec![]);
+        assert_eq!(rules.len(), 1, "{cond_id}");
+        RuleSet::new(rules)
+    }
+
+    /// Avalia as amostras em sequência e junta as detecções
+    pub fn run<T: RuleTelemetry>(
+        set: &mut RuleSet<T>,
+        samples: impl IntoIterator<Item = T>,
+    ) -> Vec<(&'static str, serde_json::Value)> {
+        let mut detections = Vec::new();
+        for sample in samples {
+            detections.extend(set.evaluate(&sample, DEV_ID, false).detections);
+        }
+        detections
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_helpers::*;
+    use super::*;
+    use crate::app_realtime::notifications::dut::notifs_dut::dut_rules_registry;
+    use crate::app_realtime::notifications::dut::on_dut_telemetry::DutTelemetry;
+
+    fn dut_critic_rules() -> RuleSet<DutTelemetry> {
+        rule_set(
+            dut_rules_registry(),
+            "DUT_T T>T",
+            serde_json::json!({ "DURATION_IN_MINUTES": 5 }),
+            &automation_cfg(Some(25.0), Some(18.0), None),
+        )
+    }
+
+    fn temperature(time: &str, value: f64) -> DutTelemetry {
+        DutTelemetry {
+            timestamp: ts(&format!("2026-01-01 {time}")),
+            Temperature: Some(value),
+            eCO2: None,
+            Humidity: None,
+            TVOC: None,
+        }
+    }
+
+    #[test]
+    fn out_of_order_sample_does_not_change_live_accumulators() {
+        let mut set = dut_critic_rules();
+        for time in ["10:00:00", "10:00:10", "10:00:20", "10:00:30"] {
+            let sink = set.evaluate(&temperature(time, 30.0), DEV_ID, false);
+            assert!(sink.detections.is_empty());
+        }
+        let live_state = set.rules[&1].state_json();
+        assert_eq!(live_state["acc_t"], 30);
+        assert_eq!(live_state["is_cond_notification"], true);
+
+        // Leitura antiga dentro do limite: zeraria o acumulador se fosse avaliada
+        let sink = set.evaluate(&temperature("09:59:00", 20.0), DEV_ID, false);
+        assert!(sink.detections.is_empty());
+        assert!(sink.resets.is_empty());
+        assert_eq!(set.rules[&1].state_json(), live_state);
+        assert_eq!(set.last_timestamp, Some(ts("2026-01-01 10:00:30")));
+
+        // A sequência ao vivo continua acumulando a partir da última leitura em ordem
+        set.evaluate(&temperature("10:00:40", 30.0), DEV_ID, false);
+        assert_eq!(set.rules[&1].state_json()["acc_t"], 40);
+    }
+
+    #[test]
+    fn duplicated_sample_is_ignored() {
+        let mut set = dut_critic_rules();
+        set.evaluate(&temperature("10:00:00", 30.0), DEV_ID, false);
+        set.evaluate(&temperature("10:00:10", 30.0), DEV_ID, false);
+        let live_state = set.rules[&1].state_json();
+        set.evaluate(&temperature("10:00:10", 30.0), DEV_ID, false);
+        assert_eq!(set.rules[&1].state_json(), live_state);
+    }
+}
@@ -1,30 +1,94 @@
+use super::history::{self, HistoryEventKind};
+use super::incidents::IncidentGrouper;
 use super::registrar_deteccao;
+use super::snooze::check_detection_snoozed;
 use crate::app_realtime::global_vars::GlobalVars;
+use crate::app_realtime::sim_clock;
 use std::{sync::Arc, time::Duration};
 use tokio::sync::mpsc;
 
 pub type MsgToQueue = (&'static str, serde_json::Value);
 
 pub async fn start_queue_manager(mut receiver: mpsc::Receiver<MsgToQueue>, globs: Arc<GlobalVars>) {
+    let mut grouper = IncidentGrouper::new(
+        globs.configfile.incident_window_secs,
+        globs.configfile.incident_min_devices,
+        globs.configfile.incident_offline_percent,
+    );
+
     loop {
-        let (notif_path, notif_data) = receiver.recv().await.expect("Erro ao receber do mpsc");
-
-        let mut tries = 0;
-        loop {
-            tries += 1;
-            let result = registrar_deteccao(notif_path, &notif_data, &globs).await;
-            if let Err(err) = result {
-                crate::log_err("[216]", err);
-                // TODO: criar uma estratégia para não bloquear a fila toda quando tiver erro em um endpoint específico
-                // Outra melhoria é tratar diferentes tipos de erro que o API-Server pode retornar.
-                // Por exemplo: se a notificação tiver sido excluída, nem precisa tentar de novo.
-                tokio::time::sleep(Duration::from_secs(10)).await;
-            } else {
-                break;
+        // Aguarda a próxima detecção, mas sem passar do horário de fechar a próxima janela de agrupamento
+        let received = match grouper.next_deadline() {
+            Some(deadline) => {
+                let remaining = (deadline - sim_clock::now_utc())
+                    .to_std()
+                    .unwrap_or(Duration::ZERO);
+                tokio::time::timeout(remaining, receiver.recv()).await.ok()
             }
-            if tries >= 3 {
-                break;
+            None => Some(receiver.recv().await),
+        };
+
+        if let Some(msg) = received {
+            let msg = msg.expect("Erro ao receber do mpsc");
+
+            // Detecções de dispositivos em manutenção (snooze) só ficam registradas no log
+            if let Some(snooze_id) = check_detection_snoozed(&msg.1, &globs).await {
+                let detail = serde_json::json!({ "snooze_id": snooze_id });
+                history::record_delivery(&globs, msg.0, &msg.1, HistoryEventKind::Snoozed, detail)
+                    .await;
+                continue;
             }
+
+            let groups = globs.incident_groups.read().await;
+            let not_grouped = grouper.push(msg, &groups);
+            drop(groups);
+            if let Some((notif_path, notif_data)) = not_grouped {
+                send_detection(notif_path, &notif_data, &globs).await;
+            }
+        }
+
+        // Envia os incidentes (ou as detecções individuais) das janelas que fecharam
+        let groups = globs.incident_groups.read().await;
+        let to_send = grouper.take_expired(&groups);
+        drop(groups);
+        for (notif_path, notif_data) in to_send {
+            send_detection(notif_path, &notif_data, &globs).await;
+        }
+    }
+}
+
+async fn send_detection(
+    notif_path: &'static str,
+    notif_data: &serde_json::Value,
+    globs: &Arc<GlobalVars>,
+) {
+    let mut tries = 0;
+    loop {
+        tries += 1;
+        let result = registrar_deteccao(notif_path, notif_data, globs).await;
+        let outcome = match &result {
+            Ok(()) => Some(HistoryEventKind::Sent),
+            Err(_) if tries >= 3 => Some(HistoryEventKind::Failed),
+            Err(_) => None,
+        };
+        if let Some(outcome) = outcome {
+            let detail = serde_json::json!({
+                "tries": tries,
+                "error": result.as_ref().err(),
+            });
+            history::record_delivery(globs, notif_path, notif_data, outcome, detail).await;
+        }
+        if let Err(err) = result {
+            crate::log_err("[216]", err);
+            // TODO: criar uma estratégia para não bloquear a fila toda quando tiver erro em um endpoint específico
+            // Outra melhoria é tratar diferentes tipos de erro que o API-Server pode retornar.
+            // Por exemplo: se a notificação tiver sido excluída, nem precisa tentar de novo.
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        } else {
+            break;
+        }
+        if tries >= 3 {
+            break;
         }
     }
 }
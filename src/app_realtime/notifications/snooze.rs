@@ -0,0 +1,355 @@
+/*
+Silenciamento de notificações (snooze) e janelas de manutenção.
+Enquanto um técnico trabalha em uma unidade, as notificações dos dispositivos continuam sendo avaliadas normalmente
+(os acumuladores e episódios são mantidos), mas as detecções não são enviadas para o API-Server, só registradas no log.
+ - O snooze é por dev_id e opcionalmente por notif_id, até um horário, com um motivo.
+ - Pode ser criado pela API HTTP ou pelo API-Server no tópico MQTT "apiserver/maintenance-window".
+ - A lista é salva em "./cache/snoozes.json" para sobreviver a reinícios do serviço.
+*/
+
+use crate::app_realtime::sim_clock;
+use crate::global_vars::GlobalVars;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{io::ErrorKind, sync::Arc, time::Duration};
+
+const CACHE_FILE: &str = "./cache/snoozes.json";
+const CACHE_FILE_TMP: &str = "./cache/snoozes-tmp.json";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Snooze {
+    pub snooze_id: u64,
+    pub dev_id: String,
+    pub notif_id: Option<u64>, // Se não for informado vale para todas as notificações do dispositivo
+    pub since: Option<DateTime<Utc>>, // Janelas de manutenção podem começar no futuro
+    pub until: DateTime<Utc>,
+    pub reason: String,
+    pub maintenance_window_id: Option<String>, // Preenchido quando foi criado por uma janela de manutenção do API-Server
+    pub created_at: DateTime<Utc>,
+}
+
+impl Snooze {
+    pub fn is_active(&self, now: &DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| since <= *now) && *now < self.until
+    }
+
+    pub fn matches(&self, dev_id: &str, notif_id: Option<u64>) -> bool {
+        if self.dev_id != dev_id {
+            return false;
+        }
+        match self.notif_id {
+            None => true,
+            Some(snoozed_notif_id) => notif_id == Some(snoozed_notif_id),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SnoozeList {
+    pub next_id: u64,
+    pub items: Vec<Snooze>,
+}
+
+impl SnoozeList {
+    pub fn find_active(
+        &self,
+        dev_id: &str,
+        notif_id: Option<u64>,
+        now: &DateTime<Utc>,
+    ) -> Option<&Snooze> {
+        self.items
+            .iter()
+            .find(|x| x.is_active(now) && x.matches(dev_id, notif_id))
+    }
+
+    pub fn add(
+        &mut self,
+        dev_id: String,
+        notif_id: Option<u64>,
+        since: Option<DateTime<Utc>>,
+        until: DateTime<Utc>,
+        reason: String,
+        maintenance_window_id: Option<String>,
+    ) -> Result<&Snooze, String> {
+        if dev_id.is_empty() {
+            return Err("dev_id é obrigatório".to_owned());
+        }
+        if until <= since.unwrap_or_else(sim_clock::now_utc) {
+            return Err("O horário final tem que ser depois do início".to_owned());
+        }
+        self.next_id += 1;
+        self.items.push(Snooze {
+            snooze_id: self.next_id,
+            dev_id,
+            notif_id,
+            since,
+            until,
+            reason,
+            maintenance_window_id,
+            created_at: sim_clock::now_utc(),
+        });
+        Ok(&self.items[self.items.len() - 1])
+    }
+
+    /// Remove os snoozes pelo id e retorna quantos foram removidos
+    pub fn cancel(&mut self, snooze_id: u64) -> usize {
+        let len_before = self.items.len();
+        self.items.retain(|x| x.snooze_id != snooze_id);
+        len_before - self.items.len()
+    }
+
+    /// Remove todos os snoozes de um dispositivo (ou só os de uma notificação) e retorna quantos foram removidos
+    pub fn cancel_dev(&mut self, dev_id: &str, notif_id: Option<u64>) -> usize {
+        let len_before = self.items.len();
+        self.items
+            .retain(|x| !(x.dev_id == dev_id && (notif_id.is_none() || x.notif_id == notif_id)));
+        len_before - self.items.len()
+    }
+
+    pub fn cancel_maintenance_window(&mut self, window_id: &str) -> usize {
+        let len_before = self.items.len();
+        self.items
+            .retain(|x| x.maintenance_window_id.as_deref() != Some(window_id));
+        len_before - self.items.len()
+    }
+
+    pub fn remove_expired(&mut self, now: &DateTime<Utc>) -> usize {
+        let len_before = self.items.len();
+        self.items.retain(|x| *now < x.until);
+        len_before - self.items.len()
+    }
+}
+
+/// Confere se a detecção deve ser enviada para o API-Server. Detecções silenciadas são só registradas no log.
+/// Retorna o snooze_id se a detecção estiver silenciada.
+pub async fn check_detection_snoozed(
+    detection: &serde_json::Value,
+    globs: &Arc<GlobalVars>,
+) -> Option<u64> {
+    let dev_id = detection["dev_id"].as_str()?;
+    let notif_id = detection["notif_id"].as_u64();
+    let snoozes = globs.snoozes.read().await;
+    let snooze = snoozes.find_active(dev_id, notif_id, &sim_clock::now_utc())?;
+    crate::write_to_log_file(
+        "NOTIF-SNOOZED",
+        &format!(
+            "snooze_id {} ({}) {}",
+            snooze.snooze_id, snooze.reason, detection
+        ),
+    );
+    Some(snooze.snooze_id)
+}
+
+/// Salva a lista de snoozes no disco. Deve ser chamada depois de qualquer alteração na lista.
+pub async fn save_to_file(globs: &Arc<GlobalVars>) -> Result<(), String> {
+    let bytes = {
+        let snoozes = globs.snoozes.read().await;
+        serde_json::to_vec(&*snoozes).map_err(|err| format!("[152] {err}"))?
+    };
+    tokio::fs::write(CACHE_FILE_TMP, bytes)
+        .await
+        .map_err(|err| format!("[152] {err}"))?;
+    tokio::fs::rename(CACHE_FILE_TMP, CACHE_FILE)
+        .await
+        .map_err(|err| format!("[152] {err}"))?;
+    Ok(())
+}
+
+async fn load_from_cache(globs: &Arc<GlobalVars>) -> Result<(), String> {
+    let file_contents = match tokio::fs::read_to_string(CACHE_FILE).await {
+        Ok(x) => x,
+        Err(err) => {
+            if err.kind() != ErrorKind::NotFound {
+                crate::write_to_log_file("WARN", &format!("Could not load snoozes cache: {err}"));
+            }
+            return Ok(());
+        }
+    };
+    let loaded: SnoozeList =
+        serde_json::from_str(&file_contents).map_err(|err| format!("[153] {err}"))?;
+    let mut snoozes = globs.snoozes.write().await;
+    *snoozes = loaded;
+    snoozes.remove_expired(&sim_clock::now_utc());
+    Ok(())
+}
+
+/// Carrega os snoozes salvos e remove periodicamente os que já expiraram
+pub async fn run_service(globs: Arc<GlobalVars>) -> Result<(), String> {
+    tokio::fs::create_dir_all("./cache")
+        .await
+        .map_err(|err| format!("[5] {err}"))?;
+    if let Err(err) = load_from_cache(&globs).await {
+        crate::write_to_log_file("ERROR", &format!("Error loading snoozes cache: {err}"));
+    }
+
+    loop {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        let removed = globs
+            .snoozes
+            .write()
+            .await
+            .remove_expired(&sim_clock::now_utc());
+        if removed > 0 {
+            save_to_file(&globs)
+                .await
+                .map_err(|err| crate::log_err("[241]", err))
+                .ok();
+        }
+    }
+}
+
+/*
+  Tópico "apiserver/maintenance-window":
+  {
+    window_id: string // Reenviar o mesmo window_id substitui a janela anterior
+    dev_ids: string[]
+    notif_id?: number
+    start?: string // ISO 8601, se não for informado começa imediatamente
+    end: string // ISO 8601
+    reason?: string
+    cancel?: boolean // Remove a janela de manutenção
+  }
+*/
+#[derive(Deserialize)]
+pub struct MaintenanceWindowMsg {
+    pub window_id: String,
+    #[serde(default)]
+    pub dev_ids: Vec<String>,
+    pub notif_id: Option<u64>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub cancel: bool,
+}
+
+pub async fn on_maintenance_window(payload_json: serde_json::Value, globs: Arc<GlobalVars>) {
+    let msg: MaintenanceWindowMsg = match serde_json::from_value(payload_json) {
+        Ok(x) => x,
+        Err(err) => {
+            crate::write_to_log_file("ERROR", &format!("[242] Invalid maintenance window: {err}"));
+            return;
+        }
+    };
+
+    if !msg.cancel && msg.end.is_none() {
+        crate::write_to_log_file("ERROR", "[242] Invalid maintenance window: missing end");
+        return;
+    }
+
+    let mut snoozes = globs.snoozes.write().await;
+    snoozes.cancel_maintenance_window(&msg.window_id);
+    if let (false, Some(end)) = (msg.cancel, msg.end) {
+        let reason = msg
+            .reason
+            .unwrap_or_else(|| format!("Janela de manutenção {}", msg.window_id));
+        for dev_id in msg.dev_ids {
+            let result = snoozes.add(
+                dev_id,
+                msg.notif_id,
+                msg.start,
+                end,
+                reason.clone(),
+                Some(msg.window_id.clone()),
+            );
+            if let Err(err) = result {
+                crate::write_to_log_file(
+                    "ERROR",
+                    &format!("[242] Invalid maintenance window: {err}"),
+                );
+            }
+        }
+    }
+    drop(snoozes);
+
+    save_to_file(&globs)
+        .await
+        .map_err(|err| crate::log_err("[241]", err))
+        .ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    const NOW_MS: u64 = 1_767_261_600_000;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(NOW_MS as i64).unwrap()
+    }
+
+    #[test]
+    fn snooze_window_uses_the_server_clock() {
+        sim_clock::set_simulated_now(NOW_MS);
+        let mut snoozes = SnoozeList::default();
+
+        // O horário final é comparado com o relógio do servidor
+        let err = snoozes.add(
+            "DEV001".to_owned(),
+            None,
+            None,
+            now() - TimeDelta::minutes(1),
+            "Manutenção".to_owned(),
+            None,
+        );
+        assert!(err.is_err());
+
+        let snooze = snoozes
+            .add(
+                "DEV001".to_owned(),
+                Some(7),
+                Some(now() + TimeDelta::hours(1)),
+                now() + TimeDelta::hours(2),
+                "Manutenção".to_owned(),
+                None,
+            )
+            .unwrap();
+        assert_eq!(snooze.created_at, now());
+        let snooze_id = snooze.snooze_id;
+
+        // A janela começa no futuro e vale só para a notificação informada
+        assert!(snoozes.find_active("DEV001", Some(7), &now()).is_none());
+        let inside = now() + TimeDelta::minutes(90);
+        assert_eq!(
+            snoozes
+                .find_active("DEV001", Some(7), &inside)
+                .map(|x| x.snooze_id),
+            Some(snooze_id)
+        );
+        assert!(snoozes.find_active("DEV001", Some(8), &inside).is_none());
+        assert!(snoozes.find_active("DEV002", Some(7), &inside).is_none());
+
+        let after = now() + TimeDelta::hours(2);
+        assert!(snoozes.find_active("DEV001", Some(7), &after).is_none());
+        assert_eq!(snoozes.remove_expired(&after), 1);
+        assert!(snoozes.items.is_empty());
+        sim_clock::set_simulated_now(0);
+    }
+
+    #[test]
+    fn snooze_without_notif_id_covers_the_device() {
+        sim_clock::set_simulated_now(NOW_MS);
+        let mut snoozes = SnoozeList::default();
+        for dev_id in ["DEV001", "DEV002"] {
+            snoozes
+                .add(
+                    dev_id.to_owned(),
+                    None,
+                    None,
+                    now() + TimeDelta::hours(1),
+                    "Janela".to_owned(),
+                    Some("W1".to_owned()),
+                )
+                .unwrap();
+        }
+        assert!(snoozes.find_active("DEV001", Some(3), &now()).is_some());
+        assert!(snoozes.find_active("DEV001", None, &now()).is_some());
+
+        assert_eq!(snoozes.cancel_dev("DEV001", Some(3)), 0);
+        assert_eq!(snoozes.cancel_dev("DEV001", None), 1);
+        assert_eq!(snoozes.cancel_maintenance_window("W1"), 1);
+        assert!(snoozes.items.is_empty());
+        sim_clock::set_simulated_now(0);
+    }
+}
@@ -0,0 +1,162 @@
+/*
+Tempos de uma notificação, configuráveis pelo COND_PARS (cada tipo de notificação define os valores padrão):
+ - DURATION_IN_MINUTES: tempo que a condição precisa durar para disparar o alerta
+ - COOLDOWN_MINUTES: intervalo mínimo entre duas notificações
+ - CONTINUITY_SECONDS: intervalo máximo entre telemetrias para considerar que não houve descontinuidade
+ - RENOTIFY_HOURS: modo de escalonamento, reenvia a detecção a cada N horas enquanto a condição persistir
+   (mesmo dentro do COOLDOWN_MINUTES). Se não for informado a detecção não é reenviada.
+*/
+
+use crate::app_realtime::sim_clock;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub struct NotifTiming {
+    pub duration_secs: u64,
+    pub cooldown_secs: u64,
+    pub continuity_secs: u64,
+    pub renotify_secs: Option<u64>,
+}
+
+/// Resultado da verificação de envio de uma detecção
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SendDecision {
+    Blocked,  // Dentro do intervalo mínimo entre notificações
+    Send,     // Nova detecção
+    Renotify, // Reenvio (escalonamento) de uma condição que continua ativa
+}
+
+impl NotifTiming {
+    pub fn from_cond_pars(cond_pars: &serde_json::Value, default: NotifTiming) -> Self {
+        let duration_secs = cond_pars["DURATION_IN_MINUTES"]
+            .as_u64()
+            .map(|x| x * 60)
+            .unwrap_or(default.duration_secs);
+        let cooldown_secs = cond_pars["COOLDOWN_MINUTES"]
+            .as_u64()
+            .map(|x| x * 60)
+            .unwrap_or(default.cooldown_secs);
+        let continuity_secs = continuity_from_cond_pars(cond_pars, default.continuity_secs);
+        let renotify_secs = cond_pars["RENOTIFY_HOURS"]
+            .as_f64()
+            .filter(|x| *x > 0.0)
+            .map(|x| (x * 3600.0) as u64)
+            .or(default.renotify_secs);
+        Self {
+            duration_secs,
+            cooldown_secs,
+            continuity_secs,
+            renotify_secs,
+        }
+    }
+
+    /// Decide se a detecção pode ser enviada considerando a última notificação enviada.
+    /// "persisting" indica que a condição continua ativa desde a última notificação.
+    pub fn send_decision(
+        &self,
+        last_notif_sent: Option<&DateTime<Utc>>,
+        persisting: bool,
+    ) -> SendDecision {
+        let Some(last_notif_sent) = last_notif_sent else {
+            return SendDecision::Send;
+        };
+        let elapsed_secs = sim_clock::now_utc()
+            .signed_duration_since(*last_notif_sent)
+            .num_seconds()
+            .max(0) as u64;
+        if persisting && self.renotify_secs.is_some_and(|x| elapsed_secs >= x) {
+            return SendDecision::Renotify;
+        }
+        if elapsed_secs < self.cooldown_secs {
+            return SendDecision::Blocked;
+        }
+        SendDecision::Send
+    }
+}
+
+/// CONTINUITY_SECONDS das notificações que não usam os outros tempos do NotifTiming
+pub fn continuity_from_cond_pars(cond_pars: &serde_json::Value, default: u64) -> u64 {
+    cond_pars["CONTINUITY_SECONDS"]
+        .as_u64()
+        .filter(|x| *x > 0)
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    const DEFAULT: NotifTiming = NotifTiming {
+        duration_secs: 10 * 60,
+        cooldown_secs: 24 * 60 * 60,
+        continuity_secs: 30,
+        renotify_secs: None,
+    };
+
+    #[test]
+    fn cond_pars_override_the_defaults() {
+        assert_eq!(
+            NotifTiming::from_cond_pars(&serde_json::json!({}), DEFAULT),
+            DEFAULT
+        );
+        let timing = NotifTiming::from_cond_pars(
+            &serde_json::json!({
+                "DURATION_IN_MINUTES": 5,
+                "COOLDOWN_MINUTES": 60,
+                "CONTINUITY_SECONDS": 120,
+                "RENOTIFY_HOURS": 0.5,
+            }),
+            DEFAULT,
+        );
+        assert_eq!(timing.duration_secs, 5 * 60);
+        assert_eq!(timing.cooldown_secs, 60 * 60);
+        assert_eq!(timing.continuity_secs, 120);
+        assert_eq!(timing.renotify_secs, Some(30 * 60));
+
+        // CONTINUITY_SECONDS e RENOTIFY_HOURS zerados ficam com o padrão
+        let timing = NotifTiming::from_cond_pars(
+            &serde_json::json!({ "CONTINUITY_SECONDS": 0, "RENOTIFY_HOURS": 0 }),
+            DEFAULT,
+        );
+        assert_eq!(timing, DEFAULT);
+    }
+
+    #[test]
+    fn cooldown_and_renotify_use_the_server_clock() {
+        sim_clock::set_simulated_now(1_767_261_600_000);
+        let last_notif_sent = sim_clock::now_utc();
+        let timing = NotifTiming {
+            renotify_secs: Some(2 * 60 * 60),
+            ..DEFAULT
+        };
+
+        assert_eq!(timing.send_decision(None, false), SendDecision::Send);
+        assert_eq!(
+            timing.send_decision(Some(&last_notif_sent), true),
+            SendDecision::Blocked
+        );
+
+        // Depois do RENOTIFY_HOURS só reenvia se a condição continuar ativa
+        let later = last_notif_sent + TimeDelta::hours(3);
+        sim_clock::set_simulated_now(later.timestamp_millis() as u64);
+        assert_eq!(
+            timing.send_decision(Some(&last_notif_sent), true),
+            SendDecision::Renotify
+        );
+        assert_eq!(
+            timing.send_decision(Some(&last_notif_sent), false),
+            SendDecision::Blocked
+        );
+
+        // Passado o COOLDOWN_MINUTES é uma nova detecção
+        let later = last_notif_sent + TimeDelta::hours(25);
+        sim_clock::set_simulated_now(later.timestamp_millis() as u64);
+        assert_eq!(
+            timing.send_decision(Some(&last_notif_sent), false),
+            SendDecision::Send
+        );
+        sim_clock::set_simulated_now(0);
+    }
+}
@@ -1,9 +1,12 @@
+use super::clock_drift;
 use super::global_vars::{DevInfo, DevLastMessage};
 use super::notifications;
+use super::sim_clock;
+use crate::helpers::telemetry_payloads::telemetry_formats::get_json_timestamp_with_gmt;
 use crate::GlobalVars;
+use chrono::TimeDelta;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
 
 /*
 
@@ -34,12 +37,16 @@ function onDeviceMessage(devId: string) {
 
 */
 
-pub fn process_payload(packet: rumqttc::Publish, globs: &Arc<GlobalVars>) {
+/// Retorna a tarefa que processa a mensagem, o modo "--replay" aguarda ela terminar antes da próxima mensagem
+pub fn process_payload(
+    packet: rumqttc::Publish,
+    globs: &Arc<GlobalVars>,
+) -> Option<tokio::task::JoinHandle<()>> {
     let mut topic = packet.topic.as_str();
 
     if topic.starts_with("apiserver/") {
         process_payload_from_apiserver(packet, globs);
-        return;
+        return None;
     }
 
     if topic.starts_with("iotrelay/") {
@@ -52,14 +59,14 @@ pub fn process_payload(packet: rumqttc::Publish, globs: &Arc<GlobalVars>) {
     let is_valid_topic = is_data || topic.starts_with("control/");
     if !is_valid_topic {
         // Ignore
-        return;
+        return None;
     }
 
-    tokio::spawn(process_payload_on_valid_topic(
+    Some(tokio::spawn(process_payload_on_valid_topic(
         globs.clone(),
         packet,
         is_data,
-    ));
+    )))
 }
 
 async fn process_payload_on_valid_topic(
@@ -82,14 +89,7 @@ async fn process_payload_on_valid_topic(
     };
 
     // Timestamp atual no servidor da última mensagem que chegou do dispositivo
-    let now_millis: u64 = {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards")
-            .as_millis()
-            .try_into()
-            .expect("timestamp too large")
-    };
+    let now_millis: u64 = sim_clock::now_millis();
 
     // Pega no globs.devs_info as informações do dispositivo
     let mut devs_info = globs.devs_info.read().await;
@@ -131,16 +131,36 @@ async fn atualizar_dev_info(
         let mut last_telemetry = dev_info.last_telemetry.write().await;
         if let Some(last_telemetry) = last_telemetry.as_mut() {
             last_telemetry.ts = now_millis;
-            last_telemetry.telemetry = payload_json.clone();
+            // Telemetrias históricas mais antigas que a registrada não substituem a última telemetria
+            if !is_older_telemetry(payload_json, &last_telemetry.telemetry) {
+                last_telemetry.telemetry = payload_json.clone();
+            }
         } else {
             *last_telemetry = Some(DevLastMessage {
                 ts: now_millis,
                 telemetry: payload_json.clone(),
             });
         }
+        drop(last_telemetry);
+
+        // Atualiza a estimativa de desvio do relógio do dispositivo
+        clock_drift::on_device_telemetry(dev_info, payload_json, now_millis).await;
     }
 }
 
+fn is_older_telemetry(payload_json: &serde_json::Value, stored: &serde_json::Value) -> bool {
+    let (Ok((payload_ts, payload_gmt)), Ok((stored_ts, stored_gmt))) = (
+        get_json_timestamp_with_gmt(payload_json),
+        get_json_timestamp_with_gmt(stored),
+    ) else {
+        return false;
+    };
+    // Os timestamps são no horário local do dispositivo, compara em UTC porque o GMT pode ter mudado
+    let payload_utc = payload_ts - TimeDelta::hours(payload_gmt);
+    let stored_utc = stored_ts - TimeDelta::hours(stored_gmt);
+    payload_utc < stored_utc
+}
+
 enum ResultJsonParse<T> {
     Ok(T),
     Err(String),
@@ -209,8 +229,8 @@ fn parse_apiserver_message<'a>(
 }
 
 fn process_payload_from_apiserver(packet: rumqttc::Publish, globs: &Arc<GlobalVars>) {
-    // Por enquanto o realtime só processa mensagens deste tópico:
-    if packet.topic != "apiserver/notif-change" {
+    // Por enquanto o realtime só processa mensagens destes tópicos:
+    if packet.topic != "apiserver/notif-change" && packet.topic != "apiserver/maintenance-window" {
         return;
     }
 
@@ -231,6 +251,14 @@ fn process_payload_from_apiserver(packet: rumqttc::Publish, globs: &Arc<GlobalVa
         // Houve mudança de config de notificações, tem que atualizar
         on_notif_change(globs, &payload_json);
     }
+
+    if packet.topic == "apiserver/maintenance-window" {
+        // Janela de manutenção: as detecções dos dispositivos informados não são enviadas até o final da janela
+        tokio::spawn(notifications::snooze::on_maintenance_window(
+            payload_json,
+            globs.clone(),
+        ));
+    }
 }
 
 fn on_notif_change(globs: &Arc<GlobalVars>, payload_json: &serde_json::Value) {
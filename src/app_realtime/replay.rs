@@ -0,0 +1,225 @@
+/*
+Modo "--replay": simulação das notificações a partir de uma captura das mensagens MQTT.
+Permite testar uma alteração nas regras (ou nos limites configurados) com os dados de dias anteriores sem publicar o serviço.
+ - A captura é um arquivo JSONL (ou JSONL.GZ, como os gravados em "./capture"), uma mensagem por linha:
+   { "ts": 1735689600000, "topic": "data/dut/DUT123", "payload": "{...}" }
+   onde "ts" é o horário do servidor (em milissegundos) em que a mensagem chegou.
+ - As notificações vêm de um arquivo JSON no mesmo formato da resposta do "get-notifs-cfg-for-realtime" do API-Server.
+ - As mensagens passam pelo mesmo "process_payload" -> "on_device_telemetry" do serviço, com o relógio
+   simulado no horário de chegada de cada mensagem. A verificação de offline roda a cada 30s do tempo simulado.
+ - As detecções que seriam enviadas ao API-Server são impressas no stdout, uma por linha, sem agrupar em incidentes.
+Uso: realtime --replay ./captura.jsonl ./notifs-cfg.json
+*/
+
+use super::capture::CaptureLine;
+use super::configs::ConfigFile;
+use super::global_vars::{DevInfo, GlobalVars};
+use super::notifications::notifs_cfg;
+use super::notifications::offline::check_offline::check_all_devices;
+use super::notifications::send_queue::MsgToQueue;
+use super::on_mqtt_message::process_payload;
+use super::sim_clock;
+use flate2::read::MultiGzDecoder;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+// Mesmo intervalo da tarefa "offline::run_service"
+const OFFLINE_CHECK_INTERVAL_MS: u64 = 30 * 1000;
+
+pub async fn run(capture_path: &str, notifs_cfg_path: &str) -> Result<(), String> {
+    let detections = replay_detections(capture_path, notifs_cfg_path).await?;
+
+    let mut detections_by_path: HashMap<&'static str, u64> = HashMap::new();
+    for (notif_path, detection) in detections {
+        *detections_by_path.entry(notif_path).or_default() += 1;
+        println!(
+            "{}",
+            serde_json::json!({ "notif_path": notif_path, "detection": detection })
+        );
+    }
+
+    let mut by_path: Vec<_> = detections_by_path.into_iter().collect();
+    by_path.sort();
+    for (notif_path, count) in by_path {
+        eprintln!("[REPLAY] {notif_path}: {count} detecções");
+    }
+
+    Ok(())
+}
+
+/// Processa a captura e retorna as detecções na ordem em que seriam enviadas ao API-Server
+pub async fn replay_detections(
+    capture_path: &str,
+    notifs_cfg_path: &str,
+) -> Result<Vec<MsgToQueue>, String> {
+    let configfile = ConfigFile::for_replay()?;
+    // A gravação fica desligada na simulação, então a thread de gravação não é iniciada
+    let (globs, mut receiver_notifs, _receiver_notifs_update, _capture_writer) =
+        GlobalVars::new(configfile).await;
+    let globs = Arc::new(globs);
+
+    let file =
+        std::fs::File::open(capture_path).map_err(|err| format!("[245] {capture_path}: {err}"))?;
+    // Os arquivos gravados pelo realtime ("./capture/*.jsonl.gz") são compactados
+    let file: Box<dyn Read> = if capture_path.ends_with(".gz") {
+        Box::new(MultiGzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+
+    let mut detections = Vec::new();
+    let mut messages: u64 = 0;
+    let mut service_start: Option<u64> = None;
+    let mut next_offline_check = 0;
+
+    for (line_index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|err| format!("[245] {capture_path}: {err}"))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let msg: CaptureLine = match serde_json::from_str(&line) {
+            Ok(x) => x,
+            Err(err) => {
+                eprintln!("[REPLAY] Linha {} ignorada: {err}", line_index + 1);
+                continue;
+            }
+        };
+
+        // As notificações são carregadas no horário da primeira mensagem, como se o serviço tivesse iniciado nela
+        let service_start = match service_start {
+            Some(x) => x,
+            None => {
+                sim_clock::set_simulated_now(msg.ts);
+                load_notifs_cfg(&globs, notifs_cfg_path, msg.ts).await?;
+                next_offline_check = msg.ts + OFFLINE_CHECK_INTERVAL_MS;
+                *service_start.insert(msg.ts)
+            }
+        };
+
+        // Verificações de offline que teriam rodado antes desta mensagem chegar
+        while next_offline_check <= msg.ts {
+            sim_clock::set_simulated_now(next_offline_check);
+            check_all_devices(&globs, next_offline_check, service_start).await;
+            collect_detections(&mut receiver_notifs, &mut detections);
+            next_offline_check += OFFLINE_CHECK_INTERVAL_MS;
+        }
+
+        // As notificações da simulação são as do arquivo, então as mensagens do API-Server são ignoradas
+        if msg.topic.starts_with("apiserver/") {
+            continue;
+        }
+
+        sim_clock::set_simulated_now(msg.ts);
+        messages += 1;
+        let packet = rumqttc::Publish::new(msg.topic, rumqttc::QoS::AtMostOnce, msg.payload);
+        if let Some(task) = process_payload(packet, &globs) {
+            task.await.map_err(|err| format!("[246] {err}"))?;
+        }
+        collect_detections(&mut receiver_notifs, &mut detections);
+    }
+
+    // Volta para o relógio do sistema
+    sim_clock::set_simulated_now(0);
+    eprintln!("[REPLAY] {messages} mensagens processadas");
+
+    Ok(detections)
+}
+
+/// Carrega as notificações do arquivo. Os dispositivos são registrados antes para que todos recebam as notificações.
+async fn load_notifs_cfg(
+    globs: &Arc<GlobalVars>,
+    notifs_cfg_path: &str,
+    now_millis: u64,
+) -> Result<(), String> {
+    let file_contents = std::fs::read_to_string(notifs_cfg_path)
+        .map_err(|err| format!("[245] {notifs_cfg_path}: {err}"))?;
+    let parsed: serde_json::Value =
+        serde_json::from_str(&file_contents).map_err(|err| format!("[72] {err}"))?;
+
+    let mut devs_info = globs.devs_info.write().await;
+    for notif in parsed["notifs_list"].as_array().into_iter().flatten() {
+        for dev_id in notif["DEV_IDS"].as_array().into_iter().flatten() {
+            let Some(dev_id) = dev_id.as_str() else {
+                continue;
+            };
+            if !devs_info.contains_key(dev_id) {
+                devs_info.insert(dev_id.to_owned(), DevInfo::new(now_millis, dev_id));
+            }
+        }
+    }
+    drop(devs_info);
+
+    notifs_cfg::parse_complete_notifs_update(parsed, globs).await
+}
+
+fn collect_detections(receiver: &mut mpsc::Receiver<MsgToQueue>, detections: &mut Vec<MsgToQueue>) {
+    while let Ok(msg) = receiver.try_recv() {
+        detections.push(msg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    const CAPTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/replay_capture.jsonl"
+    );
+    const NOTIFS_CFG: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/replay_notifs_cfg.json"
+    );
+
+    // O relógio simulado é global, então as simulações não podem rodar em paralelo
+    static REPLAY_LOCK: Mutex<()> = Mutex::new(());
+
+    fn replay_fixture() -> Vec<MsgToQueue> {
+        let _guard = REPLAY_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Error creating tokio runtime");
+        rt.block_on(replay_detections(CAPTURE, NOTIFS_CFG)).unwrap()
+    }
+
+    fn detections_for<'a>(
+        detections: &'a [MsgToQueue],
+        notif_path: &str,
+    ) -> Vec<&'a serde_json::Value> {
+        detections
+            .iter()
+            .filter(|(path, _)| *path == notif_path)
+            .map(|(_, detection)| detection)
+            .collect()
+    }
+
+    #[test]
+    fn dut_temperature_above_critical_limit() {
+        let detections = replay_fixture();
+        // TUSEMAX 25 + OFFSET_OVER_T_MAX 1, temperatura em 30 a partir das 07:03 por mais de 5 minutos
+        let found = detections_for(&detections, "/DUT_T/AcimaLimiteCritica");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0]["dev_id"], "DUT001");
+        assert_eq!(found[0]["notif_id"], 1);
+        assert_eq!(found[0]["temperature_limit"], 26.0);
+        assert_eq!(found[0]["detection_time"], "2026-01-01T10:09:00Z");
+        assert_eq!(found[0]["late"], false);
+    }
+
+    #[test]
+    fn dac_ambient_temperature_above_limit() {
+        let detections = replay_fixture();
+        // LIMIT_MAX 35, Tamb em 40 a partir das 07:03 por 5 minutos
+        let found = detections_for(&detections, "/DAC_TAMB/ForaDaFaixa");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0]["dev_id"], "DAC001");
+        assert_eq!(found[0]["notif_id"], 2);
+        assert_eq!(found[0]["value"], 40.0);
+        assert_eq!(found[0]["limit_max"], 35.0);
+        assert_eq!(found[0]["detection_time"], "2026-01-01T10:08:01Z");
+    }
+}
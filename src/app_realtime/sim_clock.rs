@@ -0,0 +1,59 @@
+/*
+Horário do servidor usado pelo processamento das mensagens e pelas regras das notificações.
+Normalmente é o relógio do sistema. No modo "--replay" é o horário em que cada mensagem gravada chegou,
+assim as regras veem o tempo passar como na captura (intervalo entre notificações, offline, etc).
+*/
+
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 0 indica que o relógio do sistema deve ser usado
+#[cfg(not(test))]
+static SIMULATED_NOW_MILLIS: AtomicU64 = AtomicU64::new(0);
+
+// Nos testes cada thread tem o seu próprio relógio, porque os testes rodam em paralelo
+#[cfg(test)]
+thread_local! {
+    static SIMULATED_NOW_MILLIS: AtomicU64 = const { AtomicU64::new(0) };
+}
+
+#[cfg(not(test))]
+fn simulated_millis() -> u64 {
+    SIMULATED_NOW_MILLIS.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+fn simulated_millis() -> u64 {
+    SIMULATED_NOW_MILLIS.with(|x| x.load(Ordering::Relaxed))
+}
+
+/// Passa a usar o horário informado no lugar do relógio do sistema
+pub fn set_simulated_now(now_millis: u64) {
+    #[cfg(not(test))]
+    SIMULATED_NOW_MILLIS.store(now_millis, Ordering::Relaxed);
+    #[cfg(test)]
+    SIMULATED_NOW_MILLIS.with(|x| x.store(now_millis, Ordering::Relaxed));
+}
+
+/// Timestamp atual do servidor em milissegundos
+pub fn now_millis() -> u64 {
+    let simulated = simulated_millis();
+    if simulated != 0 {
+        return simulated;
+    }
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_millis()
+        .try_into()
+        .expect("timestamp too large")
+}
+
+pub fn now_utc() -> DateTime<Utc> {
+    let simulated = simulated_millis();
+    if simulated == 0 {
+        return Utc::now();
+    }
+    DateTime::from_timestamp_millis(simulated as i64).unwrap_or_else(Utc::now)
+}
@@ -133,6 +133,18 @@ pub fn get_var_u16_optional(name: &str) -> Result<Option<u16>, String> {
         .map_err(|err| format!("A configuração '{name}' informada é inválida: {err}"))?;
     Ok(Some(val))
 }
+pub fn get_var_u64_optional(name: &str) -> Result<Option<u64>, String> {
+    let val = get_var_string_optional(name);
+    let val = match val {
+        Some(val) => val,
+        None => {
+            return Ok(None);
+        }
+    };
+    let val = u64::from_str(&val)
+        .map_err(|err| format!("A configuração '{name}' informada é inválida: {err}"))?;
+    Ok(Some(val))
+}
 pub fn get_var_u16_required(name: &str) -> Result<u16, String> {
     let val = get_var_string_optional(name);
     let val = match val {
@@ -1,4 +1,4 @@
-use super::parse_json_props::get_i64_optional;
+use super::parse_json_props::{get_bool_optional, get_i64_optional};
 use chrono::{DateTime, Duration, FixedOffset, NaiveDateTime};
 use serde::{
     de::{Error, Unexpected},
@@ -544,3 +544,8 @@ pub fn get_json_sampling_time(payload_json: &serde_json::Value) -> Option<i64> {
         .or_else(|| get_i64_optional(&payload_json["sampling_time"]))
         .or_else(|| get_i64_optional(&payload_json["SamplingTime"]))
 }
+
+/// Indica se a telemetria foi armazenada no dispositivo e enviada depois (por exemplo quando o gateway reconecta)
+pub fn get_json_saved_data(payload_json: &serde_json::Value) -> bool {
+    get_bool_optional(&payload_json["saved_data"]).unwrap_or(false)
+}
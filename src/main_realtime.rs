@@ -20,6 +20,8 @@ mod helpers {
 }
 
 mod app_realtime {
+    pub mod capture;
+    pub mod clock_drift;
     pub mod configs;
     pub mod devs_cache;
     pub mod global_vars;
@@ -27,10 +29,18 @@ mod app_realtime {
     pub mod mqtt_task;
     pub mod notifications;
     pub mod on_mqtt_message;
+    pub mod replay;
+    pub mod sim_clock;
     pub mod endpoints {
+        pub mod get_dac_compressor_usage;
+        pub mod get_devices_clock_drift;
         pub mod get_devices_last_telemetries;
         pub mod get_devices_last_ts;
+        pub mod get_notif_coverage;
+        pub mod get_notif_history;
         pub mod inspect_dev_notifications;
+        pub mod mqtt_capture;
+        pub mod notif_snoozes;
     }
 }
 
@@ -71,6 +81,24 @@ fn main() {
         }
     }
 
+    // Modo de simulação das notificações a partir de uma captura das mensagens MQTT
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|x| x == "--replay") {
+        let (Some(capture_path), Some(notifs_cfg_path)) = (args.get(1), args.get(2)) else {
+            println!("Uso: realtime --replay <captura.jsonl> <notifs-cfg.json>");
+            std::process::exit(1);
+        };
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Error creating tokio runtime");
+        if let Err(err) = rt.block_on(replay::run(capture_path, notifs_cfg_path)) {
+            eprintln!("[REPLAY] {err}");
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
+
     crate::write_to_log_file("INIT", "Serviço iniciado");
 
     let rt = tokio::runtime::Builder::new_current_thread()
@@ -85,7 +113,8 @@ fn main() {
 
 async fn main2() {
     let configfile = ConfigFile::from_env().expect("configfile inválido");
-    let (globs, receiver_notifs, receiver_notifs_update) = GlobalVars::new(configfile).await;
+    let (globs, receiver_notifs, receiver_notifs_update, capture_writer) =
+        GlobalVars::new(configfile).await;
     let globs = Arc::new(globs);
 
     // Inicia e aguarda as threads principais
@@ -110,11 +139,31 @@ async fn main2() {
             notifs_cfg::run_service(globs.clone())
         ) => { panic!("Some thread stopped: {:?}", result.unwrap()); },
 
-        // Tarefa que envia para o API-Server as notificações detectadas, fazendo até 3 tentativas por detecção.
+        // Tarefa que envia para o API-Server as notificações detectadas (agrupando em incidentes), fazendo até 3 tentativas por detecção.
         result = tokio::spawn(
             notifications::send_queue::start_queue_manager(receiver_notifs, globs.clone())
         ) => { panic!("Some thread stopped: {:?}", result.unwrap()); },
 
+        // Tarefa que confere periodicamente se algum dispositivo monitorado ficou sem enviar telemetria
+        result = tokio::spawn(
+            notifications::offline::run_service(globs.clone())
+        ) => { panic!("Some thread stopped: {:?}", result.unwrap()); },
+
+        // Tarefa que carrega os snoozes salvos e remove os que já expiraram
+        result = tokio::spawn(
+            notifications::snooze::run_service(globs.clone())
+        ) => { panic!("Some thread stopped: {:?}", result.unwrap()); },
+
+        // Tarefa que salva no disco o histórico de detecções de cada dispositivo
+        result = tokio::spawn(
+            notifications::history::run_service(globs.clone())
+        ) => { panic!("Some thread stopped: {:?}", result.unwrap()); },
+
+        // Thread que grava no disco as mensagens MQTT (quando ligada), encerra no horário combinado e apaga as gravações antigas
+        result = tokio::task::spawn_blocking(
+            move || capture_writer.run()
+        ) => { panic!("Some thread stopped: {:?}", result.unwrap()); },
+
         // Recebe do API-Server avisos quando tem alterações nas notificações
         result = tokio::spawn(
             notifications::update_queue::start_update_queue_manager(receiver_notifs_update, globs.clone())